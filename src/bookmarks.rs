@@ -0,0 +1,145 @@
+use super::errors::FxError;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+/// Named bookmarks of directories, persisted across sessions.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
+pub struct Bookmarks {
+    #[serde(default)]
+    pub bookmarks: BTreeMap<String, PathBuf>,
+}
+
+impl Bookmarks {
+    /// Read the bookmarks file. If it does not exist or is broken, start with no bookmarks.
+    pub fn read(bookmarks_path: &Path) -> Self {
+        Self::load(bookmarks_path).unwrap_or_else(|e| {
+            warn!("{}", e);
+            Bookmarks::default()
+        })
+    }
+
+    /// Read the bookmarks file, which is empty if it does not exist.
+    /// Unlike `read`, a broken file is an error, so that it is not overwritten.
+    fn load(bookmarks_path: &Path) -> Result<Self, FxError> {
+        match read_to_string(bookmarks_path) {
+            Ok(s) => serde_yaml::from_str(&s).map_err(|e| {
+                FxError::Io(format!(
+                    "Cannot read bookmarks from {}: {}",
+                    bookmarks_path.display(),
+                    e
+                ))
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Bookmarks::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Apply the change to the bookmarks in the file and write them back at once,
+    /// so that bookmarks changed by other instances are kept.
+    /// The bookmarks are replaced with the ones in the file after the change.
+    pub fn update<T, F>(&mut self, bookmarks_path: &Path, change: F) -> Result<T, FxError>
+    where
+        F: FnOnce(&mut Bookmarks) -> Result<T, FxError>,
+    {
+        let mut latest = Self::load(bookmarks_path)?;
+        let result = change(&mut latest)?;
+        latest.write(bookmarks_path)?;
+        *self = latest;
+        Ok(result)
+    }
+
+    pub fn write(&self, bookmarks_path: &Path) -> Result<(), FxError> {
+        let serialized = serde_yaml::to_string(self)?;
+        std::fs::write(bookmarks_path, serialized)?;
+        Ok(())
+    }
+
+    /// Add the bookmark, replacing the one with the same name.
+    pub fn add(&mut self, name: &str, dir: &Path) -> Result<(), FxError> {
+        if name.is_empty() || name.chars().any(char::is_whitespace) {
+            return Err(FxError::Io(format!("Invalid bookmark name: {}", name)));
+        }
+        self.bookmarks.insert(name.to_owned(), dir.to_path_buf());
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<PathBuf, FxError> {
+        self.bookmarks
+            .remove(name)
+            .ok_or_else(|| FxError::Io(format!("Bookmark not found: {}", name)))
+    }
+
+    /// Directory of the bookmark, which must exist.
+    pub fn get(&self, name: &str) -> Result<&PathBuf, FxError> {
+        let dir = self
+            .bookmarks
+            .get(name)
+            .ok_or_else(|| FxError::Io(format!("Bookmark not found: {}", name)))?;
+        if !dir.is_dir() {
+            return Err(FxError::Io(format!(
+                "Bookmarked directory not found: {}",
+                dir.display()
+            )));
+        }
+        Ok(dir)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.bookmarks.keys().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bookmarks() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut bookmarks = Bookmarks::default();
+        bookmarks.add("proj", dir.path()).unwrap();
+        bookmarks.add("gone", &dir.path().join("gone")).unwrap();
+        assert!(bookmarks.add("a b", dir.path()).is_err());
+        assert!(bookmarks.add("", dir.path()).is_err());
+        assert_eq!(bookmarks.names(), vec!["gone", "proj"]);
+
+        assert_eq!(bookmarks.get("proj").unwrap(), dir.path());
+        assert!(bookmarks.get("gone").is_err());
+        assert!(bookmarks.get("none").is_err());
+
+        assert!(bookmarks.remove("gone").is_ok());
+        assert!(bookmarks.remove("gone").is_err());
+        assert_eq!(bookmarks.names(), vec!["proj"]);
+    }
+
+    #[test]
+    fn test_read_write_bookmarks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".bookmarks");
+        assert_eq!(Bookmarks::read(&path), Bookmarks::default());
+
+        let mut bookmarks = Bookmarks::default();
+        bookmarks.add("proj", dir.path()).unwrap();
+        bookmarks.write(&path).unwrap();
+        assert_eq!(Bookmarks::read(&path), bookmarks);
+
+        //Bookmarks added by another instance are kept.
+        let mut other = Bookmarks::read(&path);
+        bookmarks
+            .update(&path, |b| b.add("tmp", dir.path()))
+            .unwrap();
+        other.update(&path, |b| b.remove("proj")).unwrap();
+        assert_eq!(other.names(), vec!["tmp"]);
+        assert_eq!(Bookmarks::read(&path), other);
+
+        //A broken file is not overwritten.
+        std::fs::write(&path, "{ broken").unwrap();
+        assert_eq!(Bookmarks::read(&path), Bookmarks::default());
+        assert!(other.update(&path, |b| b.add("a", dir.path())).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{ broken");
+        assert_eq!(other.names(), vec!["tmp"]);
+    }
+}
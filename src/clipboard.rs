@@ -0,0 +1,89 @@
+use super::errors::FxError;
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Copy the text to the system clipboard by the OSC 52 escape sequence,
+/// which works over SSH without external tools if the terminal supports it.
+/// If `command` (such as `wl-copy` or `xclip -selection clipboard`) is set,
+/// the text is also passed to its standard input, for terminals without OSC 52.
+pub fn copy_to_clipboard(text: &str, command: Option<&str>) -> Result<(), FxError> {
+    let mut stdout = std::io::stdout();
+    write!(stdout, "{}", osc52(text, std::env::var("TMUX").is_ok()))?;
+    stdout.flush()?;
+
+    if let Some(command) = command {
+        let mut args = command.split_whitespace();
+        let program = args
+            .next()
+            .ok_or_else(|| FxError::Io("Empty clipboard command.".to_owned()))?;
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| FxError::Io(format!("Cannot run {}: {}", program, e)))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        if !child.wait()?.success() {
+            return Err(FxError::Io(format!("{} failed.", program)));
+        }
+    }
+    Ok(())
+}
+
+/// OSC 52 sequence to set the clipboard.
+/// Inside tmux, it is wrapped so that tmux passes it through to the terminal.
+fn osc52(text: &str, in_tmux: bool) -> String {
+    let sequence = format!("\x1b]52;c;{}\x07", base64(text.as_bytes()));
+    if in_tmux {
+        format!("\x1bPtmux;\x1b{}\x1b\\", sequence)
+    } else {
+        sequence
+    }
+}
+
+fn base64(bytes: &[u8]) -> String {
+    let mut result = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                result.push(BASE64_CHARS[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                result.push('=');
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64("/tmp/写真".as_bytes()), "L3RtcC/lhpnnnJ8=");
+    }
+
+    #[test]
+    fn test_osc52() {
+        assert_eq!(osc52("foo", false), "\x1b]52;c;Zm9v\x07");
+        assert_eq!(osc52("foo", true), "\x1bPtmux;\x1b\x1b]52;c;Zm9v\x07\x1b\\");
+    }
+}
@@ -0,0 +1,346 @@
+use super::functions::shorten_str_including_wide_char;
+use super::options::RuntimeOption;
+use super::state::BEGINNING_ROW;
+use super::term::*;
+
+use crossterm::style::Stylize;
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Built-in commands of the command line.
+pub const BUILTIN_COMMANDS: [&str; 16] = [
+    "bookmark", "cd", "config", "e", "empty", "grep", "h", "invert", "jumps", "marks", "q", "reg",
+    "select", "set", "trash", "unselect",
+];
+/// Maximum number of candidates shown in the popup at once.
+const POPUP_MAX: u16 = 10;
+
+/// State of Tab completion in a prompt.
+#[derive(Debug, Default)]
+pub struct Completion {
+    /// Position (in chars) where the completed word starts.
+    pub start: usize,
+    /// Position (in chars) where the inserted candidate ends.
+    pub end: usize,
+    pub candidates: Vec<String>,
+    pub index: usize,
+}
+
+impl Completion {
+    pub fn new(start: usize, end: usize, candidates: Vec<String>) -> Self {
+        Completion {
+            start,
+            end,
+            candidates,
+            index: 0,
+        }
+    }
+
+    pub fn next(&mut self) {
+        self.index = (self.index + 1) % self.candidates.len();
+    }
+
+    pub fn prev(&mut self) {
+        self.index = (self.index + self.candidates.len() - 1) % self.candidates.len();
+    }
+
+    /// Replace the word (or the previous candidate) in the input with the current candidate,
+    /// and return the new cursor position in chars.
+    pub fn apply(&mut self, input: &mut Vec<char>) -> usize {
+        let candidate: Vec<char> = self.candidates[self.index].chars().collect();
+        let len = candidate.len();
+        input.splice(self.start..self.end, candidate);
+        self.end = self.start + len;
+        self.end
+    }
+
+    /// Show candidates below the prompt, highlighting the current one.
+    pub fn print_candidates(&self, terminal_row: u16, terminal_column: u16) {
+        let height = POPUP_MAX.min(terminal_row.saturating_sub(BEGINNING_ROW)) as usize;
+        let skip = (self.index + 1).saturating_sub(height);
+        for (i, candidate) in self.candidates.iter().enumerate().skip(skip).take(height) {
+            move_to(1, BEGINNING_ROW + (i - skip) as u16);
+            clear_current_line();
+            let candidate = shorten_str_including_wide_char(
+                candidate,
+                terminal_column.saturating_sub(1).into(),
+            );
+            if i == self.index {
+                print!("{}", candidate.negative());
+            } else {
+                print!("{}", candidate);
+            }
+        }
+    }
+}
+
+/// Return the position (in chars) of the last word of the input and its candidates.
+/// The first word is completed by built-in commands and executables on `$PATH`,
+/// and the others by paths (only directories after `cd`, option names after `set`).
+pub fn complete_command_line(input: &str, current_dir: &Path) -> (usize, Vec<String>) {
+    let start = input
+        .rfind(char::is_whitespace)
+        .map(|i| i + input[i..].chars().next().map_or(1, char::len_utf8))
+        .unwrap_or(0);
+    let word = &input[start..];
+    let is_first = input[..start].trim().is_empty();
+
+    let candidates = if is_first && !is_path_like(word) {
+        let mut candidates: Vec<String> = BUILTIN_COMMANDS
+            .iter()
+            .filter(|c| c.starts_with(word))
+            .map(|c| c.to_string())
+            .collect();
+        for executable in executables(word) {
+            if !candidates.contains(&executable) {
+                candidates.push(executable);
+            }
+        }
+        candidates
+    } else if input.split_whitespace().next() == Some("set") {
+        RuntimeOption::ALL
+            .into_iter()
+            .map(|option| option.name())
+            .filter(|name| name.starts_with(word))
+            .map(|name| name.to_owned())
+            .collect()
+    } else {
+        let dir_only = input.split_whitespace().next() == Some("cd");
+        complete_path(word, current_dir, dir_only)
+    };
+
+    (input[..start].chars().count(), candidates)
+}
+
+fn is_path_like(word: &str) -> bool {
+    word.starts_with(['.', '~', '$']) || word.contains('/')
+}
+
+/// Return paths that start with the word, relative to the current directory.
+/// Directories end with `/`. Hidden items are listed only if the word starts with `.`.
+pub fn complete_path(word: &str, current_dir: &Path, dir_only: bool) -> Vec<String> {
+    let expanded = expand_path(word);
+    let (dir_part, prefix) = match expanded.rfind('/') {
+        Some(i) => expanded.split_at(i + 1),
+        None => ("", expanded.as_str()),
+    };
+    let dir = if dir_part.is_empty() {
+        current_dir.to_path_buf()
+    } else {
+        current_dir.join(dir_part)
+    };
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    let mut result: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+            let is_dir = entry.path().is_dir();
+            if dir_only && !is_dir {
+                return None;
+            }
+            Some(format!(
+                "{}{}{}",
+                dir_part,
+                name,
+                if is_dir { "/" } else { "" }
+            ))
+        })
+        .collect();
+    result.sort_by(|a, b| natord::compare(a, b));
+    result
+}
+
+/// Expand `~` at the beginning and environment variables (`$VAR` or `${VAR}`).
+/// Unknown variables are left as is.
+pub fn expand_path(word: &str) -> String {
+    let word = match dirs::home_dir() {
+        Some(home) if word == "~" || word.starts_with("~/") => {
+            format!("{}{}", home.display(), &word[1..])
+        }
+        _ => word.to_owned(),
+    };
+
+    let mut result = String::new();
+    let mut chars = word.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            result.push(c);
+            continue;
+        }
+        let braced = chars.next_if_eq(&'{').is_some();
+        let mut name = String::new();
+        while let Some(n) = chars.next_if(|n| n.is_ascii_alphanumeric() || *n == '_') {
+            name.push(n);
+        }
+        let closed = braced && chars.next_if_eq(&'}').is_some();
+        match env::var(&name) {
+            Ok(value) if !name.is_empty() && braced == closed => result.push_str(&value),
+            _ => {
+                result.push('$');
+                if braced {
+                    result.push('{');
+                }
+                result.push_str(&name);
+                if closed {
+                    result.push('}');
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Executables on `$PATH` that start with the prefix.
+fn executables(prefix: &str) -> BTreeSet<String> {
+    let mut result = BTreeSet::new();
+    if let Some(paths) = env::var_os("PATH") {
+        for dir in env::split_paths(&paths) {
+            if let Ok(entries) = fs::read_dir(dir) {
+                for entry in entries.flatten() {
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    if name.starts_with(prefix) && is_executable(&entry.path()) {
+                        result.insert(name);
+                    }
+                }
+            }
+        }
+    }
+    result
+}
+
+#[cfg(target_family = "unix")]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path)
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(target_family = "unix"))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Directories in the zoxide database that match the keywords.
+pub fn complete_zoxide(query: &str) -> Vec<String> {
+    match std::process::Command::new("zoxide")
+        .arg("query")
+        .arg("--list")
+        .arg("--")
+        .args(query.split_whitespace())
+        .output()
+    {
+        Ok(output) => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| line.to_owned())
+            .collect(),
+        Err(_) => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_path() {
+        env::set_var("FX_TEST_EXPAND", "foo");
+        assert_eq!(expand_path("$FX_TEST_EXPAND/bar"), "foo/bar".to_string());
+        assert_eq!(expand_path("${FX_TEST_EXPAND}bar"), "foobar".to_string());
+        assert_eq!(
+            expand_path("$FX_TEST_UNKNOWN/a"),
+            "$FX_TEST_UNKNOWN/a".to_string()
+        );
+        assert_eq!(expand_path("a$"), "a$".to_string());
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand_path("~/a"), format!("{}/a", home.display()));
+        assert_eq!(expand_path("a~"), "a~".to_string());
+    }
+
+    #[test]
+    fn test_complete_path() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::create_dir(dir.path().join("src/sub")).unwrap();
+        fs::write(dir.path().join("src/main.rs"), "").unwrap();
+        fs::write(dir.path().join("some.txt"), "").unwrap();
+        fs::write(dir.path().join(".secret"), "").unwrap();
+
+        assert_eq!(
+            complete_path("s", dir.path(), false),
+            vec!["some.txt".to_string(), "src/".to_string()]
+        );
+        assert_eq!(
+            complete_path("s", dir.path(), true),
+            vec!["src/".to_string()]
+        );
+        assert_eq!(
+            complete_path("src/", dir.path(), false),
+            vec!["src/main.rs".to_string(), "src/sub/".to_string()]
+        );
+        assert_eq!(
+            complete_path(".s", dir.path(), false),
+            vec![".secret".to_string()]
+        );
+
+        let absolute = format!("{}/src/m", dir.path().display());
+        assert_eq!(
+            complete_path(&absolute, dir.path(), false),
+            vec![format!("{}/src/main.rs", dir.path().display())]
+        );
+    }
+
+    #[test]
+    fn test_complete_command_line() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("target")).unwrap();
+        fs::write(dir.path().join("test.txt"), "").unwrap();
+
+        let (start, candidates) = complete_command_line("tr", dir.path());
+        assert_eq!(start, 0);
+        assert_eq!(candidates[0], "trash".to_string());
+
+        let (start, candidates) = complete_command_line("cd t", dir.path());
+        assert_eq!(start, 3);
+        assert_eq!(candidates, vec!["target/".to_string()]);
+
+        let (start, candidates) = complete_command_line("set sh", dir.path());
+        assert_eq!(start, 4);
+        assert_eq!(candidates, vec!["show_hidden".to_string()]);
+
+        let (start, candidates) = complete_command_line("cat t", dir.path());
+        assert_eq!(start, 4);
+        assert_eq!(
+            candidates,
+            vec!["target/".to_string(), "test.txt".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_completion_cycle() {
+        let mut input: Vec<char> = "cd s rest".chars().collect();
+        let mut completion =
+            Completion::new(3, 4, vec!["src/".to_string(), "screenshots/".to_string()]);
+        assert_eq!(completion.apply(&mut input), 7);
+        assert_eq!(input.iter().collect::<String>(), "cd src/ rest".to_string());
+        completion.next();
+        assert_eq!(completion.apply(&mut input), 15);
+        assert_eq!(
+            input.iter().collect::<String>(),
+            "cd screenshots/ rest".to_string()
+        );
+        completion.next();
+        assert_eq!(completion.index, 0);
+        completion.prev();
+        assert_eq!(completion.index, 1);
+    }
+}
@@ -1,4 +1,7 @@
 use crate::errors::FxError;
+use crate::keymap::{parse_keys, Action};
+use crate::matcher::SearchMode;
+use crate::trash::{parse_size, TrashBackend};
 
 use serde::Deserialize;
 use std::collections::BTreeMap;
@@ -21,7 +24,27 @@ pub struct Config {
     pub match_vim_exit_behavior: Option<bool>,
     pub exec: Option<BTreeMap<String, Vec<String>>>,
     pub ignore_case: Option<bool>,
+    pub smart_case: Option<bool>,
+    pub search_mode: Option<SearchMode>,
+    pub respect_gitignore: Option<bool>,
+    pub builtin_z: Option<bool>,
+    pub restore_last_dir: Option<bool>,
+    pub clipboard_command: Option<String>,
+    pub trash_backend: Option<TrashBackend>,
+    pub trash_max_age_days: Option<u64>,
+    pub trash_max_size: Option<String>,
     pub color: Option<ConfigColor>,
+    pub keymap: Option<BTreeMap<String, Action>>,
+    pub commands: Option<BTreeMap<String, UserCommand>>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct UserCommand {
+    pub key: Option<String>,
+    pub run: String,
+    pub wait: Option<bool>,
+    pub reload: Option<bool>,
+    pub background: Option<bool>,
 }
 
 #[derive(Deserialize, Debug, Clone, PartialEq)]
@@ -72,7 +95,18 @@ impl Default for Config {
             match_vim_exit_behavior: Default::default(),
             exec: Default::default(),
             ignore_case: Some(false),
+            smart_case: Default::default(),
+            search_mode: Default::default(),
+            respect_gitignore: Default::default(),
+            builtin_z: Default::default(),
+            restore_last_dir: Default::default(),
+            clipboard_command: Default::default(),
+            trash_backend: Default::default(),
+            trash_max_age_days: Default::default(),
+            trash_max_size: Default::default(),
             color: Some(Default::default()),
+            keymap: Default::default(),
+            commands: Default::default(),
         }
     }
 }
@@ -80,6 +114,19 @@ impl Default for Config {
 pub fn read_config(p: &Path) -> Result<ConfigWithPath, FxError> {
     let s = read_to_string(p)?;
     let deserialized: Config = serde_yaml::from_str(&s)?;
+    if let Some(keymap) = &deserialized.keymap {
+        for keys in keymap.keys() {
+            parse_keys(keys)?;
+        }
+    }
+    if let Some(commands) = &deserialized.commands {
+        for key in commands.values().filter_map(|c| c.key.as_ref()) {
+            parse_keys(key)?;
+        }
+    }
+    if let Some(size) = &deserialized.trash_max_size {
+        parse_size(size)?;
+    }
     Ok(ConfigWithPath {
         config_path: Some(p.to_path_buf()),
         config: deserialized,
@@ -155,7 +202,18 @@ mod tests {
         assert_eq!(default_config.match_vim_exit_behavior, None);
         assert_eq!(default_config.exec, None);
         assert_eq!(default_config.ignore_case, None);
+        assert_eq!(default_config.smart_case, None);
+        assert_eq!(default_config.search_mode, None);
+        assert_eq!(default_config.respect_gitignore, None);
+        assert_eq!(default_config.builtin_z, None);
+        assert_eq!(default_config.restore_last_dir, None);
+        assert_eq!(default_config.clipboard_command, None);
+        assert_eq!(default_config.trash_backend, None);
+        assert_eq!(default_config.trash_max_age_days, None);
+        assert_eq!(default_config.trash_max_size, None);
         assert_eq!(default_config.color, None);
+        assert_eq!(default_config.keymap, None);
+        assert_eq!(default_config.commands, None);
     }
 
     #[test]
@@ -170,6 +228,15 @@ exec:
   'feh -.':
     [jpg, jpeg, png, gif, svg, hdr]
 ignore_case: true
+smart_case: true
+search_mode: regex
+respect_gitignore: false
+builtin_z: true
+restore_last_dir: true
+clipboard_command: wl-copy
+trash_backend: freedesktop
+trash_max_age_days: 30
+trash_max_size: 10GB
 color:
   dir_fg: LightCyan
   file_fg: LightWhite
@@ -196,6 +263,15 @@ color:
             ])
         );
         assert_eq!(full_config.ignore_case, Some(true));
+        assert_eq!(full_config.smart_case, Some(true));
+        assert_eq!(full_config.search_mode, Some(SearchMode::Regex));
+        assert_eq!(full_config.respect_gitignore, Some(false));
+        assert_eq!(full_config.builtin_z, Some(true));
+        assert_eq!(full_config.restore_last_dir, Some(true));
+        assert_eq!(full_config.clipboard_command, Some("wl-copy".to_string()));
+        assert_eq!(full_config.trash_backend, Some(TrashBackend::Freedesktop));
+        assert_eq!(full_config.trash_max_age_days, Some(30));
+        assert_eq!(full_config.trash_max_size, Some("10GB".to_string()));
         assert_eq!(
             full_config.color.clone().unwrap().dir_fg,
             Colorname::LightCyan
@@ -210,4 +286,57 @@ color:
         );
         assert_eq!(full_config.color.unwrap().dirty_fg, Colorname::Red);
     }
+
+    #[test]
+    fn test_read_keymap_config() {
+        let config: Config = serde_yaml::from_str(
+            r#"
+keymap:
+  x: delete
+  a: new_file
+  <C-n>: move_down
+  gp: put
+"#,
+        )
+        .unwrap();
+        let keymap = config.keymap.unwrap();
+        assert_eq!(keymap.get("x"), Some(&Action::Delete));
+        assert_eq!(keymap.get("a"), Some(&Action::NewFile));
+        assert_eq!(keymap.get("<C-n>"), Some(&Action::MoveDown));
+        assert_eq!(keymap.get("gp"), Some(&Action::Put));
+
+        let invalid: Result<Config, _> = serde_yaml::from_str("keymap:\n  x: explode\n");
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn test_read_commands_config() {
+        let config: Config = serde_yaml::from_str(
+            r#"
+commands:
+  zip:
+    key: gz
+    run: zip -r archive.zip "$@"
+    reload: true
+  push:
+    key: gP
+    run: git push
+    wait: true
+  sync:
+    run: rsync -a . backup
+    background: true
+"#,
+        )
+        .unwrap();
+        let commands = config.commands.unwrap();
+        let zip = commands.get("zip").unwrap();
+        assert_eq!(zip.key, Some("gz".to_owned()));
+        assert_eq!(zip.run, "zip -r archive.zip \"$@\"".to_owned());
+        assert_eq!(zip.reload, Some(true));
+        assert_eq!(zip.wait, None);
+        assert_eq!(commands.get("push").unwrap().wait, Some(true));
+        let sync = commands.get("sync").unwrap();
+        assert_eq!(sync.key, None);
+        assert_eq!(sync.background, Some(true));
+    }
 }
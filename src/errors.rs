@@ -11,6 +11,9 @@ pub enum FxError {
     OpenNewWindow(String),
     DefaultEditor,
     Yaml(String),
+    Keymap(String),
+    SetOption(String),
+    Search(String),
     WalkDir(String),
     Encode,
     PutItem(PathBuf),
@@ -42,6 +45,9 @@ impl std::fmt::Display for FxError {
                 "$EDITOR may not be set, or config file may be invalid.".to_owned()
             }
             FxError::Yaml(s) => s.to_owned(),
+            FxError::Keymap(s) => s.to_owned(),
+            FxError::SetOption(s) => s.to_owned(),
+            FxError::Search(s) => s.to_owned(),
             FxError::WalkDir(s) => s.to_owned(),
             FxError::Encode => "Error: Incorrect encoding".to_owned(),
             FxError::PutItem(s) => format!("Error: Cannot copy -> {:?}", s),
@@ -0,0 +1,234 @@
+use super::functions::shorten_str_including_wide_char;
+use super::state::BEGINNING_ROW;
+use super::term::*;
+
+use crossterm::style::Stylize;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Maximum number of items to walk, to keep the finder responsive in a huge tree.
+const MAX_ENTRIES: usize = 100_000;
+
+/// Fuzzy finder for items under a directory.
+pub struct Finder {
+    /// Shown in the header, such as `find`.
+    pub title: &'static str,
+    pub root: PathBuf,
+    /// Paths relative to the root. Directories end with `/`.
+    pub entries: Vec<String>,
+    /// Indices of the entries that match the query, from best to worst.
+    pub results: Vec<usize>,
+    pub selected: usize,
+    matcher: SkimMatcherV2,
+}
+
+impl Finder {
+    pub fn new(root: &Path, show_hidden: bool, respect_gitignore: bool) -> Self {
+        let entries = walk(root, show_hidden, respect_gitignore);
+        Finder::from_entries("find", root, entries)
+    }
+
+    /// Picker of the given entries, such as bookmark names.
+    pub fn from_entries(title: &'static str, root: &Path, entries: Vec<String>) -> Self {
+        Finder {
+            title,
+            root: root.to_path_buf(),
+            results: (0..entries.len()).collect(),
+            entries,
+            selected: 0,
+            matcher: SkimMatcherV2::default(),
+        }
+    }
+
+    /// Score the entries by the query and sort them.
+    /// Entries with the same score are sorted by length.
+    pub fn update(&mut self, query: &str) {
+        self.selected = 0;
+        if query.is_empty() {
+            self.results = (0..self.entries.len()).collect();
+            return;
+        }
+        let mut scored: Vec<(i64, usize)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, entry)| {
+                self.matcher
+                    .fuzzy_match(entry, query)
+                    .map(|score| (score, i))
+            })
+            .collect();
+        scored.sort_by(|a, b| {
+            b.0.cmp(&a.0)
+                .then(self.entries[a.1].len().cmp(&self.entries[b.1].len()))
+        });
+        self.results = scored.into_iter().map(|(_, i)| i).collect();
+    }
+
+    pub fn move_down(&mut self) {
+        if self.selected + 1 < self.results.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn move_up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn selected_entry(&self) -> Option<&str> {
+        self.results
+            .get(self.selected)
+            .map(|i| self.entries[*i].as_str())
+    }
+
+    /// Absolute path of the selected entry.
+    pub fn selected_path(&self) -> Option<PathBuf> {
+        self.results
+            .get(self.selected)
+            .map(|i| self.root.join(self.entries[*i].trim_end_matches('/')))
+    }
+
+    /// Show the number of matches in the header, and the results in the list space.
+    pub fn print(&self, terminal_row: u16, terminal_column: u16) {
+        move_to(1, 1);
+        clear_current_line();
+        print!(
+            "[{}] {}/{}",
+            self.title,
+            self.results.len(),
+            self.entries.len()
+        );
+
+        let height = terminal_row.saturating_sub(BEGINNING_ROW) as usize;
+        let skip = (self.selected + 1).saturating_sub(height);
+        for row in 0..height {
+            move_to(1, BEGINNING_ROW + row as u16);
+            clear_current_line();
+            if let Some(i) = self.results.get(skip + row) {
+                let entry = shorten_str_including_wide_char(
+                    &self.entries[*i],
+                    terminal_column.saturating_sub(3).into(),
+                );
+                if skip + row == self.selected {
+                    print!("> {}", entry.negative());
+                } else {
+                    print!("  {}", entry);
+                }
+            }
+        }
+    }
+}
+
+/// List up the items under the root, skipping `.git`, hidden items (unless `show_hidden`)
+/// and items ignored by git (if `respect_gitignore`).
+pub fn walk(root: &Path, show_hidden: bool, respect_gitignore: bool) -> Vec<String> {
+    let repo = if respect_gitignore {
+        git2::Repository::discover(root).ok()
+    } else {
+        None
+    };
+    let workdir = repo
+        .as_ref()
+        .and_then(|repo| repo.workdir().map(|p| p.to_path_buf()));
+
+    WalkDir::new(root)
+        .min_depth(1)
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            if name == ".git" || (!show_hidden && name.starts_with('.')) {
+                return false;
+            }
+            if let (Some(repo), Some(workdir)) = (&repo, &workdir) {
+                if let Ok(relative) = entry.path().strip_prefix(workdir) {
+                    //libgit2 treats the path as a directory if it ends with `/`.
+                    let mut relative = relative.to_string_lossy().to_string();
+                    if entry.file_type().is_dir() {
+                        relative.push('/');
+                    }
+                    if repo.is_path_ignored(&relative).unwrap_or(false) {
+                        return false;
+                    }
+                }
+            }
+            true
+        })
+        .flatten()
+        .take(MAX_ENTRIES)
+        .filter_map(|entry| {
+            let mut relative = entry
+                .path()
+                .strip_prefix(root)
+                .ok()?
+                .to_string_lossy()
+                .to_string();
+            if entry.file_type().is_dir() {
+                relative.push('/');
+            }
+            Some(relative)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_finder() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src/state")).unwrap();
+        fs::write(root.join("src/state/mod.rs"), "").unwrap();
+        fs::write(root.join("src/main.rs"), "").unwrap();
+        fs::write(root.join("README.md"), "").unwrap();
+        fs::write(root.join(".hidden"), "").unwrap();
+
+        let mut finder = Finder::new(root, false, false);
+        assert_eq!(finder.entries.len(), 5);
+        assert!(!finder.entries.contains(&".hidden".to_string()));
+        assert!(finder.entries.contains(&"src/state/".to_string()));
+
+        finder.update("smain");
+        assert_eq!(finder.results.len(), 1);
+        assert_eq!(finder.selected_path(), Some(root.join("src/main.rs")));
+
+        finder.update("state");
+        assert_eq!(finder.selected_path(), Some(root.join("src/state")));
+        finder.move_down();
+        assert_eq!(finder.selected_path(), Some(root.join("src/state/mod.rs")));
+        finder.move_down();
+        assert_eq!(finder.selected, 1);
+
+        finder.update("zzz");
+        assert_eq!(finder.selected_path(), None);
+
+        let finder = Finder::new(root, true, false);
+        assert!(finder.entries.contains(&".hidden".to_string()));
+    }
+
+    #[test]
+    fn test_finder_gitignore() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        git2::Repository::init(root).unwrap();
+        fs::write(root.join(".gitignore"), "target/\n*.log\n").unwrap();
+        fs::create_dir(root.join("target")).unwrap();
+        fs::write(root.join("target/out"), "").unwrap();
+        fs::write(root.join("debug.log"), "").unwrap();
+        fs::write(root.join("main.rs"), "").unwrap();
+
+        let finder = Finder::new(root, false, true);
+        assert_eq!(finder.entries, vec!["main.rs".to_string()]);
+
+        let mut finder = Finder::new(root, false, false);
+        finder.entries.sort();
+        assert_eq!(
+            finder.entries,
+            vec!["debug.log", "main.rs", "target/", "target/out"]
+        );
+    }
+}
@@ -0,0 +1,224 @@
+use super::errors::FxError;
+use serde::{Deserialize, Serialize};
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// When the total rank exceeds this, all ranks are aged (as zoxide does).
+const MAX_TOTAL_RANK: f64 = 10_000.0;
+/// Entries whose rank falls below this after aging are dropped.
+const MIN_RANK: f64 = 1.0;
+/// Entries not visited for this many seconds (90 days) are dropped.
+const MAX_AGE: u64 = 90 * 24 * 60 * 60;
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+/// Directory visited by `chdir`.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct FrecencyEntry {
+    pub path: PathBuf,
+    pub rank: f64,
+    /// Last visited time in seconds since the unix epoch.
+    pub last_accessed: u64,
+}
+
+impl FrecencyEntry {
+    /// Rank weighted by how recently the directory was visited.
+    fn score(&self, now: u64) -> f64 {
+        let elapsed = now.saturating_sub(self.last_accessed);
+        let weight = if elapsed < HOUR {
+            4.0
+        } else if elapsed < DAY {
+            2.0
+        } else if elapsed < WEEK {
+            0.5
+        } else {
+            0.25
+        };
+        self.rank * weight
+    }
+}
+
+/// Built-in database of directories ranked by frecency, used by `z` when zoxide is absent.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
+pub struct Frecency {
+    #[serde(default)]
+    pub entries: Vec<FrecencyEntry>,
+}
+
+impl Frecency {
+    /// Read the database file. If it does not exist or is broken, start with an empty one.
+    pub fn read(frecency_path: &Path) -> Self {
+        match read_to_string(frecency_path) {
+            Ok(s) => serde_yaml::from_str(&s).unwrap_or_default(),
+            Err(_) => Frecency::default(),
+        }
+    }
+
+    /// Write the database after dropping stale entries.
+    pub fn write(&mut self, frecency_path: &Path) -> Result<(), FxError> {
+        self.prune(now());
+        let serialized = serde_yaml::to_string(self)?;
+        std::fs::write(frecency_path, serialized)?;
+        Ok(())
+    }
+
+    /// Add the directory or increment its rank.
+    pub fn add(&mut self, path: &Path, now: u64) {
+        match self.entries.iter_mut().find(|x| x.path == path) {
+            Some(entry) => {
+                entry.rank += 1.0;
+                entry.last_accessed = now;
+            }
+            None => self.entries.push(FrecencyEntry {
+                path: path.to_path_buf(),
+                rank: 1.0,
+                last_accessed: now,
+            }),
+        }
+        self.age();
+    }
+
+    /// Scale down all ranks if the total gets too large, dropping the lowest ones.
+    fn age(&mut self) {
+        let total: f64 = self.entries.iter().map(|x| x.rank).sum();
+        if total > MAX_TOTAL_RANK {
+            let factor = 0.9 * MAX_TOTAL_RANK / total;
+            for entry in self.entries.iter_mut() {
+                entry.rank *= factor;
+            }
+            self.entries.retain(|x| x.rank >= MIN_RANK);
+        }
+    }
+
+    /// Drop entries that no longer exist or have not been visited for a long time.
+    pub fn prune(&mut self, now: u64) {
+        self.entries
+            .retain(|x| now.saturating_sub(x.last_accessed) < MAX_AGE && x.path.is_dir());
+    }
+
+    /// Directories that match the keywords, from the highest score.
+    /// Like zoxide, the keywords must appear in the path in order (ignoring case),
+    /// and the last one must match the last component.
+    pub fn query(&self, keywords: &[&str], exclude: &Path, now: u64) -> Vec<PathBuf> {
+        let keywords: Vec<String> = keywords.iter().map(|x| x.to_lowercase()).collect();
+        let mut matched: Vec<(f64, &Path)> = self
+            .entries
+            .iter()
+            .filter(|x| x.path != exclude && x.path.is_dir() && is_match(&x.path, &keywords))
+            .map(|x| (x.score(now), x.path.as_path()))
+            .collect();
+        matched.sort_by(|a, b| b.0.total_cmp(&a.0));
+        matched.into_iter().map(|(_, p)| p.to_path_buf()).collect()
+    }
+}
+
+fn is_match(path: &Path, keywords: &[String]) -> bool {
+    let path = path.to_string_lossy().to_lowercase();
+    let mut rest = path.as_str();
+    for keyword in keywords {
+        match rest.find(keyword.as_str()) {
+            Some(i) => rest = &rest[i + keyword.len()..],
+            None => return false,
+        }
+    }
+    match keywords.last() {
+        Some(last) => {
+            let name = path.rsplit(std::path::MAIN_SEPARATOR).next().unwrap_or("");
+            name.contains(last.as_str())
+        }
+        None => true,
+    }
+}
+
+/// Current time in seconds since the unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_frecency_query() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let foo = root.join("projects/foo");
+        let foobar = root.join("projects/foobar");
+        let docs = root.join("docs/foo_notes");
+        for p in [&foo, &foobar, &docs] {
+            fs::create_dir_all(p).unwrap();
+        }
+
+        let now = 1_000_000_000;
+        let mut frecency = Frecency::default();
+        frecency.add(&foo, now - WEEK * 2);
+        frecency.add(&foo, now - WEEK * 2);
+        frecency.add(&foo, now - WEEK * 2);
+        frecency.add(&foobar, now);
+        frecency.add(&docs, now - DAY * 2);
+
+        //Recently visited one comes first: 1 * 4 > 3 * 0.25.
+        assert_eq!(
+            frecency.query(&["foo"], root, now),
+            vec![foobar.clone(), foo.clone(), docs.clone()]
+        );
+        //Keywords in order, the last one in the last component.
+        assert_eq!(
+            frecency.query(&["proj", "FOO"], root, now),
+            vec![foobar.clone(), foo.clone()]
+        );
+        assert!(frecency.query(&["foo", "proj"], root, now).is_empty());
+        assert!(frecency.query(&["projects"], root, now).is_empty());
+        //The current directory is excluded.
+        assert_eq!(
+            frecency.query(&["foobar"], &foobar, now),
+            Vec::<PathBuf>::new()
+        );
+    }
+
+    #[test]
+    fn test_frecency_aging() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a");
+        let b = dir.path().join("b");
+        fs::create_dir(&a).unwrap();
+
+        let now = 1_000_000_000;
+        let mut frecency = Frecency::default();
+        frecency.add(&b, now);
+        for _ in 0..MAX_TOTAL_RANK as usize {
+            frecency.add(&a, now);
+        }
+        //Aged once: the rank of `b` falls below the minimum.
+        assert_eq!(frecency.entries.len(), 1);
+        assert!(frecency.entries[0].rank < MAX_TOTAL_RANK);
+
+        frecency.add(&b, now);
+        //`b` does not exist.
+        frecency.prune(now);
+        assert_eq!(frecency.entries.len(), 1);
+        //Not visited for a long time.
+        frecency.prune(now + MAX_AGE);
+        assert!(frecency.entries.is_empty());
+    }
+
+    #[test]
+    fn test_read_write_frecency() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".frecency");
+        assert_eq!(Frecency::read(&path), Frecency::default());
+
+        let mut frecency = Frecency::default();
+        frecency.add(dir.path(), now());
+        frecency.write(&path).unwrap();
+        assert_eq!(Frecency::read(&path), frecency);
+    }
+}
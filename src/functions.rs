@@ -71,6 +71,95 @@ pub fn rename_dir(dir_name: &str, name_set: &BTreeSet<String>) -> String {
     new_name
 }
 
+/// Check if rename(2) failed because the destination is on another device.
+pub fn is_cross_device(e: &std::io::Error) -> bool {
+    #[cfg(unix)]
+    {
+        e.raw_os_error() == Some(nix::libc::EXDEV)
+    }
+    //ERROR_NOT_SAME_DEVICE
+    #[cfg(windows)]
+    {
+        e.raw_os_error() == Some(17)
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = e;
+        false
+    }
+}
+
+/// Names of the items in the directory, including ones hidden from the list,
+/// to find a free name for putting or moving items.
+pub fn read_names(dir: &Path) -> Result<BTreeSet<String>, FxError> {
+    let mut name_set = BTreeSet::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        name_set.insert(
+            entry
+                .file_name()
+                .into_string()
+                .unwrap_or_else(|_| "".to_string()),
+        );
+    }
+    Ok(name_set)
+}
+
+/// Move the item by rename(2), which does not copy the contents.
+/// If the destination is on another device, copy the item and then remove the original.
+pub fn move_item(from: &Path, to: &Path) -> Result<(), FxError> {
+    match std::fs::rename(from, to) {
+        Ok(_) => Ok(()),
+        Err(e) if is_cross_device(&e) => {
+            copy_item(from, to)?;
+            if from.symlink_metadata()?.is_dir() {
+                std::fs::remove_dir_all(from)?;
+            } else {
+                std::fs::remove_file(from)?;
+            }
+            Ok(())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Copy the item recursively, keeping symlinks as they are.
+fn copy_item(from: &Path, to: &Path) -> Result<(), FxError> {
+    //walkdir follows the symlink at the root, so copy the link itself.
+    if from.symlink_metadata()?.file_type().is_symlink() {
+        return copy_symlink(from, to);
+    }
+    for entry in walkdir::WalkDir::new(from) {
+        let entry = entry?;
+        let dest = match entry.path().strip_prefix(from) {
+            Ok(relative) if relative.as_os_str().is_empty() => to.to_path_buf(),
+            Ok(relative) => to.join(relative),
+            Err(_) => continue,
+        };
+        let file_type = entry.file_type();
+        if file_type.is_dir() {
+            std::fs::create_dir_all(&dest)?;
+        } else if file_type.is_symlink() {
+            copy_symlink(entry.path(), &dest)?;
+        } else {
+            std::fs::copy(entry.path(), &dest)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path) -> Result<(), FxError> {
+    std::os::unix::fs::symlink(std::fs::read_link(from)?, to)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(from: &Path, to: &Path) -> Result<(), FxError> {
+    std::fs::copy(from, to)?;
+    Ok(())
+}
+
 /// Print the result of operation, such as put/delete/redo/undo.
 pub fn print_info<T: std::fmt::Display>(message: T, then: u16) {
     delete_pointer();
@@ -297,6 +386,65 @@ pub fn split_lines_including_wide_char(s: &str, width: usize) -> Vec<String> {
     result
 }
 
+/// Quote a string so that the shell treats it as a single word.
+pub fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+/// Expand placeholders such as `%f` in the command, joining multiple values with spaces.
+/// `%%` is expanded to `%`, and unknown placeholders are left as is.
+pub fn expand_placeholders(
+    command: &str,
+    values: &BTreeMap<char, Vec<String>>,
+    quote: bool,
+) -> String {
+    let mut result = String::new();
+    let mut chars = command.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            result.push(c);
+            continue;
+        }
+        match chars.peek() {
+            Some('%') => {
+                result.push('%');
+                chars.next();
+            }
+            Some(p) => match values.get(p) {
+                Some(v) => {
+                    let v: Vec<String> = if quote {
+                        v.iter().map(|s| shell_quote(s)).collect()
+                    } else {
+                        v.clone()
+                    };
+                    result.push_str(&v.join(" "));
+                    chars.next();
+                }
+                None => result.push('%'),
+            },
+            None => result.push('%'),
+        }
+    }
+    result
+}
+
+/// Expand placeholders in each argument for running the command without the shell.
+/// An argument that is just a placeholder becomes one argument per value,
+/// so that each selected item is passed separately.
+pub fn expand_args(args: &[&str], values: &BTreeMap<char, Vec<String>>) -> Vec<String> {
+    let mut result = vec![];
+    for arg in args {
+        let mut chars = arg.chars();
+        match (chars.next(), chars.next(), chars.next()) {
+            (Some('%'), Some(p), None) if values.contains_key(&p) => {
+                result.extend(values[&p].iter().cloned());
+            }
+            _ => result.push(expand_placeholders(arg, values, false)),
+        }
+    }
+    result
+}
+
 //cargo test -- --nocapture
 #[cfg(test)]
 mod tests {
@@ -452,4 +600,80 @@ mod tests {
             assert_eq!(formatted[0], "東京都心は、かつての".to_string());
         }
     }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("a b"), "'a b'".to_string());
+        assert_eq!(shell_quote("it's"), "'it'\\''s'".to_string());
+    }
+
+    #[test]
+    fn test_expand_placeholders() {
+        let mut values = BTreeMap::new();
+        values.insert('f', vec!["/tmp/a b".to_string()]);
+        values.insert('s', vec!["/tmp/a".to_string(), "/tmp/b".to_string()]);
+        values.insert('r', vec![]);
+        assert_eq!(
+            expand_placeholders("cat %f", &values, true),
+            "cat '/tmp/a b'".to_string()
+        );
+        assert_eq!(
+            expand_placeholders("zip x.zip %s %r", &values, true),
+            "zip x.zip '/tmp/a' '/tmp/b' ".to_string()
+        );
+        assert_eq!(
+            expand_placeholders("date +%%d %x %", &values, false),
+            "date +%d %x %".to_string()
+        );
+        assert_eq!(
+            expand_placeholders("echo %f", &values, false),
+            "echo /tmp/a b".to_string()
+        );
+    }
+
+    #[test]
+    fn test_expand_args() {
+        let mut values = BTreeMap::new();
+        values.insert('s', vec!["/tmp/a b".to_string(), "/tmp/c".to_string()]);
+        values.insert('r', vec![]);
+        assert_eq!(
+            expand_args(&["644", "%s", "%r", "x%s", "%%"], &values),
+            vec!["644", "/tmp/a b", "/tmp/c", "x/tmp/a b /tmp/c", "%"]
+        );
+    }
+
+    #[test]
+    fn test_move_item() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        std::fs::create_dir_all(src.join("sub")).unwrap();
+        std::fs::write(src.join("sub/a.txt"), "a").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("sub/a.txt", src.join("link")).unwrap();
+
+        //Fallback across devices.
+        let copied = dir.path().join("copied");
+        copy_item(&src, &copied).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(copied.join("sub/a.txt")).unwrap(),
+            "a"
+        );
+        #[cfg(unix)]
+        assert_eq!(
+            std::fs::read_link(copied.join("link")).unwrap(),
+            PathBuf::from("sub/a.txt")
+        );
+        //A symlink itself is copied as a link, not as its target.
+        #[cfg(unix)]
+        {
+            let link = dir.path().join("link_copied");
+            copy_item(&src.join("link"), &link).unwrap();
+            assert!(link.symlink_metadata().unwrap().file_type().is_symlink());
+        }
+
+        let moved = dir.path().join("moved");
+        move_item(&src, &moved).unwrap();
+        assert!(!src.exists());
+        assert!(moved.join("sub/a.txt").exists());
+    }
 }
@@ -0,0 +1,229 @@
+use super::finder::walk;
+use super::functions::shorten_str_including_wide_char;
+use super::layout::Layout;
+use super::state::BEGINNING_ROW;
+use super::term::*;
+
+use crossterm::style::Stylize;
+use std::path::{Path, PathBuf};
+
+/// Files larger than this are not searched.
+const MAX_SIZE_TO_GREP: u64 = 10_000_000;
+/// Maximum number of matches, to keep the results view responsive.
+const MAX_MATCHES: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrepMatch {
+    pub path: PathBuf,
+    pub line_number: usize,
+    pub text: String,
+}
+
+/// Search file contents under the root recursively, skipping binary files.
+pub fn grep(
+    root: &Path,
+    pattern: &str,
+    ignore_case: bool,
+    show_hidden: bool,
+    respect_gitignore: bool,
+) -> Vec<GrepMatch> {
+    let pattern = if ignore_case {
+        pattern.to_lowercase()
+    } else {
+        pattern.to_owned()
+    };
+    let mut result = vec![];
+    for entry in walk(root, show_hidden, respect_gitignore) {
+        if entry.ends_with('/') {
+            continue;
+        }
+        let path = root.join(entry);
+        match path.metadata() {
+            Ok(metadata) if metadata.is_file() && metadata.len() <= MAX_SIZE_TO_GREP => {}
+            _ => continue,
+        }
+        let content = match std::fs::read(&path) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        if !content_inspector::inspect(&content).is_text() {
+            continue;
+        }
+        for (i, line) in String::from_utf8_lossy(&content).lines().enumerate() {
+            let is_match = if ignore_case {
+                line.to_lowercase().contains(&pattern)
+            } else {
+                line.contains(&pattern)
+            };
+            if is_match {
+                result.push(GrepMatch {
+                    path: path.clone(),
+                    line_number: i + 1,
+                    text: line.trim().replace('\t', "    "),
+                });
+                if result.len() >= MAX_MATCHES {
+                    return result;
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Results view of `:grep`.
+pub struct GrepView {
+    pub root: PathBuf,
+    pub pattern: String,
+    pub matches: Vec<GrepMatch>,
+    pub selected: usize,
+}
+
+impl GrepView {
+    pub fn new(root: &Path, pattern: &str, matches: Vec<GrepMatch>) -> Self {
+        GrepView {
+            root: root.to_path_buf(),
+            pattern: pattern.to_owned(),
+            matches,
+            selected: 0,
+        }
+    }
+
+    pub fn move_down(&mut self, n: usize) {
+        self.selected = (self.selected + n).min(self.matches.len().saturating_sub(1));
+    }
+
+    pub fn move_up(&mut self, n: usize) {
+        self.selected = self.selected.saturating_sub(n);
+    }
+
+    pub fn selected_match(&self) -> Option<&GrepMatch> {
+        self.matches.get(self.selected)
+    }
+
+    /// Format the match as `path:line: text`, the path being relative to the root.
+    fn format_match(&self, m: &GrepMatch) -> String {
+        let path = m.path.strip_prefix(&self.root).unwrap_or(&m.path);
+        format!("{}:{}: {}", path.display(), m.line_number, m.text)
+    }
+
+    /// Show matches in the list space, and the lines around the selected one in the preview space.
+    pub fn print(&self, layout: &Layout) {
+        move_to(1, 1);
+        clear_current_line();
+        print!("[grep] {}: {} matches", self.pattern, self.matches.len());
+
+        let height = layout.terminal_row.saturating_sub(BEGINNING_ROW) as usize;
+        let skip = (self.selected + 1).saturating_sub(height);
+        for row in 0..height {
+            move_to(1, BEGINNING_ROW + row as u16);
+            clear_current_line();
+            if let Some(m) = self.matches.get(skip + row) {
+                let line = shorten_str_including_wide_char(
+                    &self.format_match(m),
+                    layout.terminal_column.saturating_sub(3).into(),
+                );
+                if skip + row == self.selected {
+                    print!("> {}", line.negative());
+                } else {
+                    print!("  {}", line);
+                }
+            }
+        }
+
+        if layout.is_preview() {
+            if let Some(m) = self.selected_match() {
+                let lines = context_lines(m, layout.preview_space.1 as usize);
+                layout.print_grep_context(&lines, m.line_number);
+            }
+        }
+    }
+}
+
+/// Lines around the match (with their line numbers), with the match in the middle.
+fn context_lines(m: &GrepMatch, height: usize) -> Vec<(usize, String)> {
+    let content = match std::fs::read(&m.path) {
+        Ok(content) => content,
+        Err(_) => return vec![],
+    };
+    let start = m.line_number.saturating_sub(height / 2).max(1);
+    String::from_utf8_lossy(&content)
+        .lines()
+        .enumerate()
+        .skip(start - 1)
+        .take(height)
+        .map(|(i, line)| (i + 1, line.replace('\t', "    ")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_grep() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join("src")).unwrap();
+        fs::write(
+            root.join("src/main.rs"),
+            "fn main() {\n\tprintln!(\"Hello\");\n}\n",
+        )
+        .unwrap();
+        fs::write(root.join("notes.txt"), "hello world\n").unwrap();
+        fs::write(
+            root.join("image.bin"),
+            [0u8, 159, 146, 150, b'H', b'e', b'l', b'l', b'o'],
+        )
+        .unwrap();
+
+        let matches = grep(root, "Hello", false, false, false);
+        assert_eq!(
+            matches,
+            vec![GrepMatch {
+                path: root.join("src/main.rs"),
+                line_number: 2,
+                text: "println!(\"Hello\");".to_string(),
+            }]
+        );
+
+        let mut matches = grep(root, "hello", true, false, false);
+        matches.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].path, root.join("notes.txt"));
+
+        let view = GrepView::new(root, "hello", matches);
+        assert_eq!(
+            view.format_match(&view.matches[1]),
+            "src/main.rs:2: println!(\"Hello\");".to_string()
+        );
+        assert_eq!(
+            context_lines(&view.matches[1], 2),
+            vec![
+                (1, "fn main() {".to_string()),
+                (2, "    println!(\"Hello\");".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_grep_view_move() {
+        let m = GrepMatch {
+            path: PathBuf::from("/a"),
+            line_number: 1,
+            text: String::new(),
+        };
+        let mut view = GrepView::new(Path::new("/"), "a", vec![m.clone(), m.clone(), m]);
+        view.move_down(5);
+        assert_eq!(view.selected, 2);
+        view.move_up(1);
+        assert_eq!(view.selected, 1);
+        view.move_up(5);
+        assert_eq!(view.selected, 0);
+
+        let mut empty = GrepView::new(Path::new("/"), "a", vec![]);
+        empty.move_down(1);
+        assert_eq!(empty.selected, 0);
+        assert!(empty.selected_match().is_none());
+    }
+}
@@ -15,6 +15,8 @@ Both relative and absolute path available.
 `--log`  | `-l`   => Launch the app, automatically generating a log file.
 `--init`          => Returns a shell script that can be sourcedfor
                      for shell integration.
+`--bookmark` | `-b` <name>
+                  => Launch the app in the bookmarked directory.
 
 ## Manual
 j / <Down>         :Go down.
@@ -27,18 +29,39 @@ gg                 :Go to the top.
 G                  :Go to the bottom.
 z<CR>              :Go to the home directory.
 z {keyword}<CR>    :Jump to a directory that matches the keyword.
-                    (zoxide required)
+                    (zoxide, or the built-in frecency database)
 <C-o>              :Jump backward.
 <C-i>              :Jump forward.
+m{a-z}             :Set the mark on the current directory and item.
+                    Uppercase marks (m{A-Z}) are kept across sessions.
+'{a-z}             :Jump to the marked item.
+:marks             :Show marks. To hide it, press v.
+:jumps             :Show the jumplist, marking the current position by `>`.
+:bookmark add {name}<CR>
+                   :Bookmark the current directory.
+:bookmark rm {name}<CR>
+                   :Remove the bookmark.
+:bookmark<CR>      :Pick a bookmark to jump to, filtered as you type.
+<C-f>              :Find items under the current directory by fuzzy matching.
+  - <Up> / <Down>  :In the finder, select the item (also <C-p> / <C-n>).
+  - <CR>           :In the finder, jump to the selected item.
+  - <C-o>          :In the finder, open the selected item.
 i{file name}<CR>   :Create a new empty file.
 I{dir name}<CR>    :Create a new empty directory.
 o                  :Open item in a new window.
 e                  :Unpack archive/compressed file.
 dd                 :Delete and yank item.
 yy                 :Yank item.
+yp                 :Copy the path of the item (or selected items) to the clipboard.
+yn                 :Copy the name of the item to the clipboard.
+yd                 :Copy the path of the current directory to the clipboard.
 p                  :Put yanked item(s) from register zero
                     in the current directory.
+X (uppercase)      :Cut item (or selected items) to move.
+P (uppercase)      :Move cut item(s) to the current directory.
+                    Uses rename, so the trash is not involved.
 :reg               :Show registers. To hide it, press v.
+                    Registers are shared between instances and sessions.
 "ayy               :Yank item to register a.
 "add               :Delete and yank item to register a.
 "Ayy               :Append item to register a.
@@ -47,13 +70,21 @@ p                  :Put yanked item(s) from register zero
 V                  :Switch to the linewise visual mode.
   - y              :In the visual mode, yank selected item(s).
   - d              :In the visual mode, delete and yank selected item(s).
+  - Y              :In the visual mode, copy paths of selected items to the clipboard.
+  - X              :In the visual mode, cut selected items to move.
   - "ay            :In the visual mode, yank items to register a.
   - "ad            :In the visual mode, delete and yank items to register a.
   - "Ay            :In the visual mode, append items to register a.
   - "Ad            :In the visual mode, delete and append items to register a.
   - c              :Rename selected items in default editor.
+<Space>            :Toggle the selection of the item and go down.
+                    The selection is kept across directories, and dd, yy, "a,
+                    c and commands (%s) act on it. <Esc> clears it.
+<C-a>              :Select all the items in the list.
 u                  :Undo put/delete/rename.
 <C-r>              :Redo put/delete/rename.
+.                  :Repeat the last delete/put/rename/new item at the cursor.
+{count}j, {count}k  :Go down/up {count} items (also dd, yy and G).
 v                  :Toggle whether to show the preview.
 s                  :Toggle between vertical / horizontal split in the preview mode.
 <Alt-j>
@@ -64,26 +95,66 @@ s                  :Toggle between vertical / horizontal split in the preview mo
 t                  :Toggle the sort order (name <-> modified time).
 c                  :Switch to the rename mode.
 /{keyword}         :Search items by a keyword.
+                    Prefix flags: \r (regex), \g (glob), \p (plain),
+                    \c (ignore case), \C (match case). e.g. /\r\.rs$
 n                  :Go forward to the item that matches the keyword.
 N                  :Go backward to the item that matches the keyword.
+F{keyword}         :Hide items that do not contain the keyword (filter mode).
+                    The filter is kept until <Esc> or changing the directory.
 :                  :Switch to the command line.
   - <C-r>a         :In the command line, paste item name in register a.
+  - <Up> / <C-p>   :In the command line, recall older history that starts with the input.
+  - <Down> / <C-n> :In the command line, recall newer history.
+                    Also available after `/` and `z`, with separate history.
+  - <Tab> / <S-Tab>:In the command line, cycle through candidates of commands
+                    and paths (`~` and `$VAR` are expanded).
+                    After `z`, cycle through the top matches of directories.
 :cd<CR>            :Go to the home directory.
 :cd {path}<CR>     :Go to the path.
 :e<CR>             :Reload the current directory.
 :config<CR>        :Go to the directory that contains the config file if exists.
-:trash<CR>         :Go to the trash directory.
+:trash<CR>         :Browse the trash with the original path, deletion time
+                    and size of the items. j/k to move, <Space> to mark,
+                    r to restore items to where they were deleted from,
+                    d to delete them permanently, <CR> to go to the item
+                    in the trash directory, and <Esc> or q to close.
 :empty<CR>         :Empty the trash directory.
+:empty --older-than {age}<CR>
+                   :Delete items in the trash deleted more than {age} ago
+                    permanently, e.g. :empty --older-than 7d (m/h/d/w).
+:grep {pattern}<CR>:Search file contents recursively, and show matches.
+                    In the results, j/k to move, <C-d>/<C-u> to scroll,
+                    <CR> to jump to the file, and <Esc> or q to close.
+:select {glob}<CR> :Select items that match the glob(s), e.g. :select *.log
+                    Without globs, select all the items in the list.
+:unselect {glob}<CR>
+                   :Unselect items that match the glob(s).
+                    Without globs, unselect all the items in the list.
+:invert<CR>        :Invert the selection of the items in the list.
+:set<CR>           :Show runtime options.
+:set {option}={value}<CR>
+                   :Set the option. `:set {option}!` toggles it,
+                    and `:set {option}?` shows its value.
+                    Options: sort_by (name/time), show_hidden, preview,
+                    split (vertical/horizontal), ignore_case, smart_case,
+                    search_mode (plain/regex/glob), respect_gitignore.
 :h<CR>             :Show help.
 :q<CR>             :Exit.
 :{command}         :Execute a command e.g. :zip test *.md
-<Esc>              :Return to the normal mode.
+  - %f / %s        :In the command, expand to the current item / selected items.
+  - %d / %r        :In the command, expand to the current directory / items in register a.
+                    Use %% for %. Also exported as $FX_FILE, $FX_SELECTION,
+                    $FX_DIR and $FX_REGISTER (newline-separated).
+<Esc>              :Return to the normal mode, or clear the filter.
 <C-h>              :Works as Backspace after `i`, `I`, `c`, `/`, `:` and `z`.
 ZZ                 :Exit without cd to last working directory
                     (if `match_vim_exit_behavior` is `false`).
 ZQ                 :cd into the last working directory and exit
                     (if shell setting is ready and `match_vim_exit_behavior is `false`).
 
+Keys can be remapped by `keymap` in the config file.
+Shell commands can be bound to keys by `commands` in the config file.
+
 ## Preview feature
 By default, text files and directories can be previewed.
 To preview images, you need to install chafa (>= v1.10.0).
@@ -97,6 +168,8 @@ Please see https://hpjansson.org/chafa/
 config file    : $XDG_CONFIG_HOME/felix/config.yaml(config.yml)
 trash directory: $XDG_DATA_HOME/felix/trash
 log files      : $XDG_DATA_HOME/felix/log
+With `trash_backend: freedesktop`, the trash directory is $XDG_DATA_HOME/Trash/files
+(or `.Trash-$uid` at the top of other mounts).
 
 ### macOS
 On macOS, felix looks for the config file in the following locations:
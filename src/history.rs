@@ -0,0 +1,170 @@
+use super::errors::FxError;
+use serde::{Deserialize, Serialize};
+use std::fs::read_to_string;
+use std::path::Path;
+
+/// Maximum number of entries kept for each prompt.
+const HISTORY_MAX: usize = 200;
+
+/// History of the command line, search and zoxide prompts, persisted across sessions.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
+pub struct History {
+    #[serde(default)]
+    pub command: Vec<String>,
+    #[serde(default)]
+    pub search: Vec<String>,
+    #[serde(default)]
+    pub zoxide: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryKind {
+    Command,
+    Search,
+    Zoxide,
+}
+
+impl History {
+    /// Read the history file. If it does not exist or is broken, start with empty history.
+    pub fn read(history_path: &Path) -> Self {
+        match read_to_string(history_path) {
+            Ok(s) => serde_yaml::from_str(&s).unwrap_or_default(),
+            Err(_) => History::default(),
+        }
+    }
+
+    pub fn write(&self, history_path: &Path) -> Result<(), FxError> {
+        let serialized = serde_yaml::to_string(self)?;
+        std::fs::write(history_path, serialized)?;
+        Ok(())
+    }
+
+    /// Entries of the prompt, from oldest to newest.
+    pub fn entries(&self, kind: HistoryKind) -> &[String] {
+        match kind {
+            HistoryKind::Command => &self.command,
+            HistoryKind::Search => &self.search,
+            HistoryKind::Zoxide => &self.zoxide,
+        }
+    }
+
+    /// Add the entry as the newest one, removing the duplicate if exists.
+    pub fn add(&mut self, kind: HistoryKind, entry: &str) {
+        let entry = entry.trim();
+        if entry.is_empty() {
+            return;
+        }
+        let entries = match kind {
+            HistoryKind::Command => &mut self.command,
+            HistoryKind::Search => &mut self.search,
+            HistoryKind::Zoxide => &mut self.zoxide,
+        };
+        entries.retain(|x| x != entry);
+        entries.push(entry.to_owned());
+        if entries.len() > HISTORY_MAX {
+            let over = entries.len() - HISTORY_MAX;
+            entries.drain(..over);
+        }
+    }
+}
+
+/// Position in the history while recalling entries in a prompt.
+/// Only entries that start with the input typed before recalling are shown, like vim.
+#[derive(Debug, Default)]
+pub struct HistoryCursor {
+    pos: Option<usize>,
+    prefix: String,
+}
+
+impl HistoryCursor {
+    /// Return the previous (older) matching entry.
+    pub fn prev(&mut self, entries: &[String], input: &str) -> Option<String> {
+        if self.pos.is_none() {
+            self.prefix = input.to_owned();
+        }
+        let end = self.pos.unwrap_or(entries.len());
+        let i = entries[..end]
+            .iter()
+            .rposition(|x| x.starts_with(&self.prefix))?;
+        self.pos = Some(i);
+        Some(entries[i].clone())
+    }
+
+    /// Return the next (newer) matching entry, or the original input at the end.
+    pub fn next(&mut self, entries: &[String]) -> Option<String> {
+        let start = self.pos? + 1;
+        match entries[start..]
+            .iter()
+            .position(|x| x.starts_with(&self.prefix))
+        {
+            Some(i) => {
+                self.pos = Some(start + i);
+                Some(entries[start + i].clone())
+            }
+            None => {
+                self.pos = None;
+                Some(self.prefix.clone())
+            }
+        }
+    }
+
+    /// Stop recalling, e.g. when the input is edited.
+    pub fn reset(&mut self) {
+        self.pos = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_history() {
+        let mut history = History::default();
+        history.add(HistoryKind::Command, "ls");
+        history.add(HistoryKind::Command, "  ");
+        history.add(HistoryKind::Command, "git status");
+        history.add(HistoryKind::Command, "ls");
+        history.add(HistoryKind::Search, "foo");
+        assert_eq!(history.command, vec!["git status", "ls"]);
+        assert_eq!(history.entries(HistoryKind::Search), &["foo".to_string()]);
+        assert!(history.zoxide.is_empty());
+
+        for i in 0..HISTORY_MAX + 10 {
+            history.add(HistoryKind::Zoxide, &i.to_string());
+        }
+        assert_eq!(history.zoxide.len(), HISTORY_MAX);
+        assert_eq!(history.zoxide[0], "10".to_string());
+    }
+
+    #[test]
+    fn test_history_cursor() {
+        let entries: Vec<String> = ["cd foo", "ls", "cd bar", "git log"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect();
+        let mut cursor = HistoryCursor::default();
+        assert_eq!(cursor.next(&entries), None);
+        assert_eq!(cursor.prev(&entries, "cd"), Some("cd bar".to_string()));
+        assert_eq!(cursor.prev(&entries, "cd bar"), Some("cd foo".to_string()));
+        assert_eq!(cursor.prev(&entries, "cd foo"), None);
+        assert_eq!(cursor.next(&entries), Some("cd bar".to_string()));
+        assert_eq!(cursor.next(&entries), Some("cd".to_string()));
+
+        cursor.reset();
+        assert_eq!(cursor.prev(&entries, ""), Some("git log".to_string()));
+    }
+
+    #[test]
+    fn test_read_write_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".history");
+        assert_eq!(History::read(&path), History::default());
+
+        let mut history = History::default();
+        history.add(HistoryKind::Search, "foo");
+        history.add(HistoryKind::Zoxide, "bar");
+        history.write(&path).unwrap();
+        assert_eq!(History::read(&path), history);
+    }
+}
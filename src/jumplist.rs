@@ -1,5 +1,12 @@
+use super::errors::FxError;
+use super::functions::split_lines_including_wide_char;
+use log::warn;
+use std::fs::read_to_string;
 use std::{collections::VecDeque, path::Path, path::PathBuf};
 
+/// Maximum number of directories kept in the jumplist.
+const JUMPLIST_MAX: usize = 100;
+
 #[derive(Debug, Default)]
 pub struct JumpList {
     pub pos: usize,
@@ -7,6 +14,41 @@ pub struct JumpList {
 }
 
 impl JumpList {
+    /// Read the jumplist saved in the previous session, skipping directories that no longer exist.
+    /// If the file does not exist or is broken, start with an empty jumplist.
+    pub fn read(jumplist_path: &Path) -> Self {
+        let list: Vec<PathBuf> = match read_to_string(jumplist_path) {
+            Ok(s) => serde_yaml::from_str(&s).unwrap_or_else(|e| {
+                warn!("Cannot read jumplist {}: {}", jumplist_path.display(), e);
+                vec![]
+            }),
+            Err(_) => vec![],
+        };
+        let mut jumplist = JumpList::default();
+        for p in list {
+            if p.is_dir() && !jumplist.list.contains(&p) {
+                jumplist.list.push_back(p);
+            }
+        }
+        jumplist.list.truncate(JUMPLIST_MAX);
+        jumplist
+    }
+
+    /// Write the jumplist from the current position, skipping ones that no longer exist.
+    /// As in `add`, directories forward are dropped, so that the current one comes first next time.
+    pub fn write(&self, jumplist_path: &Path) -> Result<(), FxError> {
+        let list: Vec<&PathBuf> = self
+            .list
+            .iter()
+            .skip(self.pos)
+            .filter(|p| p.is_dir())
+            .collect();
+        let serialized = serde_yaml::to_string(&list)?;
+        std::fs::write(jumplist_path, serialized)?;
+        Ok(())
+    }
+
+    /// Add the directory as the newest one, removing the duplicate if exists.
     pub fn add(&mut self, p: &Path) {
         if self.pos != 0 {
             for _i in 0..self.pos {
@@ -14,7 +56,9 @@ impl JumpList {
             }
             self.pos = 0;
         }
+        self.list.retain(|x| x != p);
         self.list.push_front(p.to_path_buf());
+        self.list.truncate(JUMPLIST_MAX);
     }
 
     pub fn get_backward(&self) -> Option<PathBuf> {
@@ -52,4 +96,80 @@ impl JumpList {
     pub fn remove_forward(&mut self) {
         self.list.remove(self.pos - 1);
     }
+
+    /// Lines to be shown by `:jumps`, from the newest. The current position is marked by `>`.
+    pub fn prepare_jumps(&self, width: u16) -> Vec<String> {
+        let s = self
+            .list
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let marker = if i == self.pos { '>' } else { ' ' };
+                format!("{}{:>3} {}", marker, i, p.display())
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+        split_lines_including_wide_char(&s, width.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jumplist() {
+        let mut jumplist = JumpList::default();
+        jumplist.add(Path::new("/a"));
+        jumplist.add(Path::new("/b"));
+        jumplist.add(Path::new("/a"));
+        assert_eq!(
+            jumplist.list,
+            vec![PathBuf::from("/a"), PathBuf::from("/b")]
+        );
+        assert_eq!(jumplist.get_backward(), Some(PathBuf::from("/b")));
+        jumplist.pos_backward();
+        assert_eq!(
+            jumplist.prepare_jumps(80),
+            vec!["   0 /a".to_string(), ">  1 /b".to_string()]
+        );
+
+        //Adding a directory drops the ones forward.
+        jumplist.add(Path::new("/c"));
+        assert_eq!(
+            jumplist.list,
+            vec![PathBuf::from("/c"), PathBuf::from("/b")]
+        );
+        assert_eq!(jumplist.pos, 0);
+
+        for i in 0..JUMPLIST_MAX + 10 {
+            jumplist.add(&PathBuf::from(format!("/{}", i)));
+        }
+        assert_eq!(jumplist.list.len(), JUMPLIST_MAX);
+    }
+
+    #[test]
+    fn test_read_write_jumplist() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".jumplist");
+        assert!(JumpList::read(&path).list.is_empty());
+
+        let forward = dir.path().join("forward");
+        std::fs::create_dir(&forward).unwrap();
+        let mut jumplist = JumpList::default();
+        jumplist.add(&dir.path().join("gone"));
+        jumplist.add(dir.path());
+        jumplist.add(&forward);
+        jumplist.pos_backward();
+        jumplist.write(&path).unwrap();
+
+        //Directories forward are dropped, and the current one comes first.
+        let read = JumpList::read(&path);
+        assert_eq!(read.list, vec![dir.path().to_path_buf()]);
+        assert_eq!(read.pos, 0);
+
+        //A broken file is read as empty.
+        std::fs::write(&path, "{ broken").unwrap();
+        assert!(JumpList::read(&path).list.is_empty());
+    }
 }
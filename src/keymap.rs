@@ -0,0 +1,506 @@
+use super::config::Config;
+use super::errors::FxError;
+
+use crossterm::event::{KeyCode, KeyModifiers};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Actions that can be bound to keys via `keymap` in the config file.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    MoveDown,
+    MoveUp,
+    HalfPageDown,
+    HalfPageUp,
+    GoToTop,
+    GoToBottom,
+    Open,
+    OpenInNewWindow,
+    GoToParent,
+    JumpBackward,
+    JumpForward,
+    SetMark,
+    JumpToMark,
+    Unpack,
+    Zoxide,
+    FuzzyFind,
+    NewFile,
+    NewDir,
+    Visual,
+    ToggleSelect,
+    SelectAll,
+    ToggleSort,
+    ToggleHidden,
+    TogglePreview,
+    ToggleSplit,
+    ScrollDownPreview,
+    ScrollUpPreview,
+    Delete,
+    Yank,
+    CopyPath,
+    CopyName,
+    CopyDir,
+    Put,
+    Cut,
+    MoveHere,
+    Rename,
+    Search,
+    SearchNext,
+    SearchPrevious,
+    Filter,
+    Register,
+    CommandLine,
+    Repeat,
+    Undo,
+    Redo,
+    Escape,
+    Exit,
+    ExitWithLwd,
+    /// Command defined in `commands` of the config file.
+    #[serde(skip)]
+    UserCommand(String),
+}
+
+/// Single key press, normalized so that it can be compared with the keymap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyInput {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyInput {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // Shift is already reflected in the character itself (e.g. `G`),
+        // and terminals do not report it consistently.
+        let modifiers = match code {
+            KeyCode::Char(_) | KeyCode::BackTab => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        KeyInput { code, modifiers }
+    }
+}
+
+impl std::fmt::Display for KeyInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self.code {
+            KeyCode::Char(' ') => "Space".to_owned(),
+            KeyCode::Char('<') => "lt".to_owned(),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::Enter => "CR".to_owned(),
+            KeyCode::Esc => "Esc".to_owned(),
+            KeyCode::Backspace => "BS".to_owned(),
+            KeyCode::Tab => "Tab".to_owned(),
+            KeyCode::BackTab => "S-Tab".to_owned(),
+            KeyCode::Delete => "Del".to_owned(),
+            KeyCode::F(n) => format!("F{}", n),
+            code => format!("{:?}", code),
+        };
+        let mut prefix = String::new();
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            prefix.push_str("C-");
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            prefix.push_str("A-");
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            prefix.push_str("S-");
+        }
+        if prefix.is_empty() && name.chars().count() == 1 {
+            write!(f, "{}", name)
+        } else {
+            write!(f, "<{}{}>", prefix, name)
+        }
+    }
+}
+
+/// Result of looking up the keys typed so far.
+#[derive(Debug, PartialEq, Eq)]
+pub enum KeyMatch {
+    Found(Action),
+    /// The keys are the beginning of a longer sequence.
+    Pending,
+    NotFound,
+}
+
+#[derive(Debug, Default)]
+pub struct Keymap {
+    normal: HashMap<Vec<KeyInput>, Action>,
+    visual: HashMap<Vec<KeyInput>, Action>,
+}
+
+const DEFAULT_NORMAL: &[(&str, Action)] = &[
+    ("j", Action::MoveDown),
+    ("<Down>", Action::MoveDown),
+    ("k", Action::MoveUp),
+    ("<Up>", Action::MoveUp),
+    ("<C-d>", Action::HalfPageDown),
+    ("<C-u>", Action::HalfPageUp),
+    ("gg", Action::GoToTop),
+    ("G", Action::GoToBottom),
+    ("l", Action::Open),
+    ("<Right>", Action::Open),
+    ("<CR>", Action::Open),
+    ("o", Action::OpenInNewWindow),
+    ("h", Action::GoToParent),
+    ("<Left>", Action::GoToParent),
+    ("<C-o>", Action::JumpBackward),
+    ("<Tab>", Action::JumpForward),
+    ("m", Action::SetMark),
+    ("'", Action::JumpToMark),
+    ("e", Action::Unpack),
+    ("z", Action::Zoxide),
+    ("<C-f>", Action::FuzzyFind),
+    ("i", Action::NewFile),
+    ("I", Action::NewDir),
+    ("V", Action::Visual),
+    ("<Space>", Action::ToggleSelect),
+    ("<C-a>", Action::SelectAll),
+    ("t", Action::ToggleSort),
+    ("<BS>", Action::ToggleHidden),
+    ("v", Action::TogglePreview),
+    ("s", Action::ToggleSplit),
+    ("<A-j>", Action::ScrollDownPreview),
+    ("<A-Down>", Action::ScrollDownPreview),
+    ("<A-k>", Action::ScrollUpPreview),
+    ("<A-Up>", Action::ScrollUpPreview),
+    ("dd", Action::Delete),
+    ("yy", Action::Yank),
+    ("yp", Action::CopyPath),
+    ("yn", Action::CopyName),
+    ("yd", Action::CopyDir),
+    ("p", Action::Put),
+    ("X", Action::Cut),
+    ("P", Action::MoveHere),
+    ("c", Action::Rename),
+    ("/", Action::Search),
+    ("n", Action::SearchNext),
+    ("N", Action::SearchPrevious),
+    ("F", Action::Filter),
+    ("\"", Action::Register),
+    (":", Action::CommandLine),
+    (".", Action::Repeat),
+    ("u", Action::Undo),
+    ("<C-r>", Action::Redo),
+    ("<Esc>", Action::Escape),
+    ("ZZ", Action::Exit),
+    ("ZQ", Action::ExitWithLwd),
+];
+
+const DEFAULT_VISUAL: &[(&str, Action)] = &[
+    ("j", Action::MoveDown),
+    ("<Down>", Action::MoveDown),
+    ("k", Action::MoveUp),
+    ("<Up>", Action::MoveUp),
+    ("<C-d>", Action::HalfPageDown),
+    ("<C-u>", Action::HalfPageUp),
+    ("gg", Action::GoToTop),
+    ("G", Action::GoToBottom),
+    ("<C-o>", Action::JumpBackward),
+    ("<Tab>", Action::JumpForward),
+    ("V", Action::Visual),
+    ("v", Action::TogglePreview),
+    ("s", Action::ToggleSplit),
+    ("<A-j>", Action::ScrollDownPreview),
+    ("<A-Down>", Action::ScrollDownPreview),
+    ("<A-k>", Action::ScrollUpPreview),
+    ("<A-Up>", Action::ScrollUpPreview),
+    ("d", Action::Delete),
+    ("y", Action::Yank),
+    ("Y", Action::CopyPath),
+    ("X", Action::Cut),
+    ("c", Action::Rename),
+    ("\"", Action::Register),
+    (":", Action::CommandLine),
+    ("<Esc>", Action::Escape),
+];
+
+impl Keymap {
+    /// Build the keymap from the default bindings, the keys of `commands`
+    /// and the `keymap` section of the config.
+    /// Custom bindings are applied to both normal and visual mode.
+    pub fn new(config: &Config) -> Self {
+        let mut keymap = Keymap {
+            normal: to_table(DEFAULT_NORMAL),
+            visual: to_table(DEFAULT_VISUAL),
+        };
+
+        if config.match_vim_exit_behavior.unwrap_or_default() {
+            for (keys, action) in keymap.normal.iter_mut() {
+                if keys.first().map(|k| k.code) == Some(KeyCode::Char('Z')) {
+                    *action = match action {
+                        Action::Exit => Action::ExitWithLwd,
+                        Action::ExitWithLwd => Action::Exit,
+                        _ => continue,
+                    };
+                }
+            }
+        }
+
+        if let Some(commands) = &config.commands {
+            for (name, command) in commands {
+                if let Some(Ok(keys)) = command.key.as_deref().map(parse_keys) {
+                    let action = Action::UserCommand(name.clone());
+                    bind(&mut keymap.normal, keys.clone(), action.clone());
+                    bind(&mut keymap.visual, keys, action);
+                }
+            }
+        }
+
+        if let Some(custom) = &config.keymap {
+            for (keys, action) in custom {
+                if let Ok(keys) = parse_keys(keys) {
+                    bind(&mut keymap.normal, keys.clone(), action.clone());
+                    bind(&mut keymap.visual, keys, action.clone());
+                }
+            }
+        }
+        keymap
+    }
+
+    /// Look up the action bound to the keys typed so far.
+    pub fn get(&self, keys: &[KeyInput], is_visual: bool) -> KeyMatch {
+        let table = if is_visual {
+            &self.visual
+        } else {
+            &self.normal
+        };
+        if let Some(action) = table.get(keys) {
+            return KeyMatch::Found(action.clone());
+        }
+        if table.keys().any(|k| k.starts_with(keys)) {
+            KeyMatch::Pending
+        } else {
+            KeyMatch::NotFound
+        }
+    }
+}
+
+fn to_table(defaults: &[(&str, Action)]) -> HashMap<Vec<KeyInput>, Action> {
+    defaults
+        .iter()
+        .map(|(keys, action)| (parse_keys(keys).unwrap(), action.clone()))
+        .collect()
+}
+
+/// Add the binding, removing existing ones that would shadow it or be shadowed by it
+/// (e.g. binding `d` removes `dd`).
+fn bind(table: &mut HashMap<Vec<KeyInput>, Action>, keys: Vec<KeyInput>, action: Action) {
+    table.retain(|k, _| !k.starts_with(&keys) && !keys.starts_with(k));
+    table.insert(keys, action);
+}
+
+/// Parse vim-like key notation such as `gg`, `<C-d>`, `<A-j>` or `<CR>`.
+pub fn parse_keys(s: &str) -> Result<Vec<KeyInput>, FxError> {
+    let mut result = Vec::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '<' {
+            result.push(KeyInput::new(KeyCode::Char(c), KeyModifiers::NONE));
+            continue;
+        }
+        let mut notation = String::new();
+        let mut closed = false;
+        for c in chars.by_ref() {
+            if c == '>' {
+                closed = true;
+                break;
+            }
+            notation.push(c);
+        }
+        if !closed || notation.is_empty() {
+            return Err(FxError::Keymap(format!("Invalid key: {}", s)));
+        }
+        result.push(
+            parse_notation(&notation)
+                .ok_or_else(|| FxError::Keymap(format!("Invalid key: <{}> in {}", notation, s)))?,
+        );
+    }
+    if result.is_empty() {
+        Err(FxError::Keymap("Empty key in keymap.".to_owned()))
+    } else {
+        Ok(result)
+    }
+}
+
+/// Parse the inside of `<...>`.
+fn parse_notation(notation: &str) -> Option<KeyInput> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut name = notation;
+    while name.len() > 2 && name.as_bytes()[1] == b'-' {
+        match name.as_bytes()[0].to_ascii_lowercase() {
+            b'c' => modifiers |= KeyModifiers::CONTROL,
+            b'a' | b'm' => modifiers |= KeyModifiers::ALT,
+            b's' => modifiers |= KeyModifiers::SHIFT,
+            _ => return None,
+        }
+        name = &name[2..];
+    }
+
+    let code = match name.to_ascii_lowercase().as_str() {
+        "cr" | "enter" | "return" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "bs" | "backspace" => KeyCode::Backspace,
+        "tab" => {
+            if modifiers.contains(KeyModifiers::SHIFT) {
+                KeyCode::BackTab
+            } else {
+                KeyCode::Tab
+            }
+        }
+        "space" => KeyCode::Char(' '),
+        "lt" => KeyCode::Char('<'),
+        "del" | "delete" => KeyCode::Delete,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        lower => {
+            let mut chars = name.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => KeyCode::Char(c),
+                _ => {
+                    let n = lower.strip_prefix('f')?.parse::<u8>().ok()?;
+                    KeyCode::F(n)
+                }
+            }
+        }
+    };
+    Some(KeyInput::new(code, modifiers))
+}
+
+/// Convert the keys to the string shown while waiting for the rest of the sequence.
+pub fn keys_to_string(keys: &[KeyInput]) -> String {
+    keys.iter().map(|k| k.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::UserCommand;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_parse_keys() {
+        assert_eq!(
+            parse_keys("gg").unwrap(),
+            vec![
+                KeyInput::new(KeyCode::Char('g'), KeyModifiers::NONE),
+                KeyInput::new(KeyCode::Char('g'), KeyModifiers::NONE)
+            ]
+        );
+        assert_eq!(
+            parse_keys("<C-d>").unwrap(),
+            vec![KeyInput::new(KeyCode::Char('d'), KeyModifiers::CONTROL)]
+        );
+        assert_eq!(
+            parse_keys("<A-Down>").unwrap(),
+            vec![KeyInput::new(KeyCode::Down, KeyModifiers::ALT)]
+        );
+        assert_eq!(
+            parse_keys("<CR>").unwrap(),
+            vec![KeyInput::new(KeyCode::Enter, KeyModifiers::NONE)]
+        );
+        assert_eq!(
+            parse_keys("<S-Tab>").unwrap(),
+            vec![KeyInput::new(KeyCode::BackTab, KeyModifiers::SHIFT)]
+        );
+        assert!(parse_keys("").is_err());
+        assert!(parse_keys("<C-d").is_err());
+        assert!(parse_keys("<foo>").is_err());
+    }
+
+    #[test]
+    fn test_keys_to_string() {
+        assert_eq!(keys_to_string(&parse_keys("ZQ").unwrap()), "ZQ");
+        assert_eq!(keys_to_string(&parse_keys("<C-r>a").unwrap()), "<C-r>a");
+        assert_eq!(
+            keys_to_string(&parse_keys("<lt><Space>").unwrap()),
+            "<lt><Space>"
+        );
+    }
+
+    #[test]
+    fn test_default_keymap() {
+        let keymap = Keymap::new(&Config::default());
+        let g = parse_keys("g").unwrap();
+        assert_eq!(keymap.get(&g, false), KeyMatch::Pending);
+        let gg = parse_keys("gg").unwrap();
+        assert_eq!(keymap.get(&gg, false), KeyMatch::Found(Action::GoToTop));
+        let d = parse_keys("d").unwrap();
+        assert_eq!(keymap.get(&d, false), KeyMatch::Pending);
+        assert_eq!(keymap.get(&d, true), KeyMatch::Found(Action::Delete));
+        let x = parse_keys("x").unwrap();
+        assert_eq!(keymap.get(&x, false), KeyMatch::NotFound);
+        // Uppercase letters may come with the shift modifier.
+        let shift_g = [KeyInput::new(KeyCode::Char('G'), KeyModifiers::SHIFT)];
+        assert_eq!(
+            keymap.get(&shift_g, false),
+            KeyMatch::Found(Action::GoToBottom)
+        );
+    }
+
+    #[test]
+    fn test_match_vim_exit_behavior() {
+        let keymap = Keymap::new(&Config {
+            match_vim_exit_behavior: Some(true),
+            ..Default::default()
+        });
+        let zz = parse_keys("ZZ").unwrap();
+        let zq = parse_keys("ZQ").unwrap();
+        assert_eq!(keymap.get(&zz, false), KeyMatch::Found(Action::ExitWithLwd));
+        assert_eq!(keymap.get(&zq, false), KeyMatch::Found(Action::Exit));
+    }
+
+    #[test]
+    fn test_custom_keymap() {
+        let mut custom = BTreeMap::new();
+        custom.insert("x".to_owned(), Action::Delete);
+        custom.insert("a".to_owned(), Action::NewFile);
+        custom.insert("d".to_owned(), Action::Yank);
+        let keymap = Keymap::new(&Config {
+            keymap: Some(custom),
+            ..Default::default()
+        });
+        let x = parse_keys("x").unwrap();
+        assert_eq!(keymap.get(&x, false), KeyMatch::Found(Action::Delete));
+        assert_eq!(keymap.get(&x, true), KeyMatch::Found(Action::Delete));
+        let a = parse_keys("a").unwrap();
+        assert_eq!(keymap.get(&a, false), KeyMatch::Found(Action::NewFile));
+        // `d` shadows the default `dd`.
+        let d = parse_keys("d").unwrap();
+        assert_eq!(keymap.get(&d, false), KeyMatch::Found(Action::Yank));
+        let dd = parse_keys("dd").unwrap();
+        assert_eq!(keymap.get(&dd, false), KeyMatch::NotFound);
+    }
+
+    #[test]
+    fn test_user_command_keymap() {
+        let mut commands = BTreeMap::new();
+        commands.insert(
+            "zip".to_owned(),
+            UserCommand {
+                key: Some("gz".to_owned()),
+                run: "zip -r archive.zip \"$@\"".to_owned(),
+                wait: None,
+                reload: None,
+                background: None,
+            },
+        );
+        let keymap = Keymap::new(&Config {
+            commands: Some(commands),
+            ..Default::default()
+        });
+        let gz = parse_keys("gz").unwrap();
+        let action = KeyMatch::Found(Action::UserCommand("zip".to_owned()));
+        assert_eq!(keymap.get(&gz, false), action);
+        assert_eq!(keymap.get(&gz, true), action);
+        // `gg` is still available.
+        let gg = parse_keys("gg").unwrap();
+        assert_eq!(keymap.get(&gg, false), KeyMatch::Found(Action::GoToTop));
+    }
+}
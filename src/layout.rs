@@ -2,10 +2,11 @@ use super::config::*;
 use super::errors::FxError;
 use super::functions::*;
 use super::nums::*;
-use super::session::{read_session, SortKey};
+use super::session::SortKey;
 use super::state::{ItemInfo, BEGINNING_ROW};
 use super::term::*;
 
+use crossterm::style::Stylize;
 use log::error;
 use serde::{Deserialize, Serialize};
 
@@ -52,6 +53,8 @@ pub enum Side {
     #[default]
     Preview,
     Reg,
+    Marks,
+    Jumps,
     None,
 }
 
@@ -63,7 +66,8 @@ pub enum Split {
 }
 
 impl Layout {
-    pub fn new(session_path: &std::path::Path, config: Config) -> Result<Self, FxError> {
+    /// Options saved in the session file are applied afterwards by `State::new`.
+    pub fn new(config: &Config) -> Result<Self, FxError> {
         let (original_column, original_row) = terminal_size()?;
         // Return error if terminal size may cause panic
         if original_column < 4 {
@@ -77,13 +81,11 @@ impl Layout {
 
         // Prepare state fields.
         let (time_start, name_max) = make_layout(original_column);
-        let session = read_session(session_path);
-        let split = session.split.unwrap_or_default();
         let has_bat = check_bat();
         let has_chafa = check_chafa();
         let is_kitty = check_kitty_support();
 
-        let colors = config.color.unwrap_or_default();
+        let colors = config.color.clone().unwrap_or_default();
 
         Ok(Layout {
             nums: Num::new(),
@@ -92,13 +94,10 @@ impl Layout {
             terminal_column: original_column,
             name_max_len: name_max,
             time_start_pos: time_start,
-            sort_by: session.sort_by,
-            show_hidden: session.show_hidden,
-            side: match session.preview.unwrap_or(false) {
-                true => Side::Preview,
-                false => Side::None,
-            },
-            split,
+            sort_by: SortKey::default(),
+            show_hidden: false,
+            side: Side::None,
+            split: Split::default(),
             preview_start: (0, 0),
             preview_space: (0, 0),
             has_bat,
@@ -116,12 +115,26 @@ impl Layout {
         self.side == Side::Reg
     }
 
+    pub fn is_marks(&self) -> bool {
+        self.side == Side::Marks
+    }
+
+    pub fn is_jumps(&self) -> bool {
+        self.side == Side::Jumps
+    }
+
+    /// Whether the side space is used by preview, registers, marks or the jumplist.
+    pub fn has_side(&self) -> bool {
+        self.side != Side::None
+    }
+
     pub fn show_preview(&mut self) {
         self.side = Side::Preview;
     }
 
-    pub fn show_reg(&mut self) {
-        self.side = Side::Reg;
+    /// Show registers, marks or the jumplist in the side space.
+    pub fn show_list(&mut self, side: Side) {
+        self.side = side;
     }
 
     pub fn reset_side(&mut self) {
@@ -129,6 +142,19 @@ impl Layout {
     }
 
     pub fn print_reg(&self, reg: &[String]) {
+        self.print_side_lines(reg, "No registers found.");
+    }
+
+    pub fn print_marks(&self, marks: &[String]) {
+        self.print_side_lines(marks, "No marks set.");
+    }
+
+    pub fn print_jumps(&self, jumps: &[String]) {
+        self.print_side_lines(jumps, "No jumps.");
+    }
+
+    /// Print lines in the side space, or the message if there is nothing to show.
+    fn print_side_lines(&self, reg: &[String], empty: &str) {
         match self.split {
             Split::Vertical => {
                 self.clear_preview(self.preview_start.0);
@@ -139,7 +165,7 @@ impl Layout {
         }
 
         if reg.iter().all(|x| x.is_empty()) {
-            print!("No registers found.");
+            print!("{}", empty);
             return;
         }
 
@@ -320,6 +346,37 @@ impl Layout {
         reset_color();
     }
 
+    /// Print lines around a match of `:grep` in the preview space, highlighting the matched line.
+    pub fn print_grep_context(&self, lines: &[(usize, String)], matched: usize) {
+        let start_column = match self.split {
+            Split::Vertical => {
+                self.clear_preview(self.preview_start.0);
+                self.preview_start.0
+            }
+            Split::Horizontal => {
+                self.clear_preview(self.preview_start.1);
+                1
+            }
+        };
+        for (i, (line_number, line)) in lines.iter().enumerate() {
+            if i as u16 >= self.preview_space.1 {
+                break;
+            }
+            move_to(start_column, self.preview_start.1 + i as u16);
+            let line = shorten_str_including_wide_char(
+                &format!("{:>4} {}", line_number, line),
+                self.preview_space.0 as usize,
+            );
+            if *line_number == matched {
+                print!("{}", line.negative());
+            } else {
+                set_color(&TermColor::ForeGround(&Colorname::LightBlack));
+                print!("{}", line);
+                reset_color();
+            }
+        }
+    }
+
     /// Print text preview on the right half of the terminal (Experimental).
     fn preview_image(&self, item: &ItemInfo) -> Result<(), FxError> {
         let wxh = match self.split {
@@ -382,7 +439,7 @@ impl Layout {
     }
 
     pub fn update_column_and_row(&mut self) -> Result<(u16, u16), FxError> {
-        if self.is_preview() || self.is_reg() {
+        if self.has_side() {
             match self.split {
                 Split::Vertical => Ok((self.terminal_column >> 1, self.terminal_row)),
                 Split::Horizontal => Ok((self.terminal_column, self.terminal_row >> 1)),
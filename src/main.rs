@@ -1,18 +1,32 @@
+mod bookmarks;
+mod clipboard;
+mod completion;
 mod config;
 mod errors;
+mod finder;
+mod frecency;
 mod functions;
+mod grep;
 mod help;
+mod history;
 mod jumplist;
+mod keymap;
 mod layout;
 mod magic_image;
 mod magic_packed;
+mod marks;
+mod matcher;
 mod nums;
 mod op;
+mod options;
+mod reg_file;
+mod repeat;
 mod run;
 mod session;
 mod shell;
 mod state;
 mod term;
+mod trash;
 
 use std::path::PathBuf;
 
@@ -21,10 +35,7 @@ fn main() -> Result<(), errors::FxError> {
     let len = args.len();
     match len {
         1 => {
-            if let Err(e) = run::run(
-                std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
-                false,
-            ) {
+            if let Err(e) = run::run(None, false, None) {
                 eprintln!("{}", e);
             }
         }
@@ -34,10 +45,7 @@ fn main() -> Result<(), errors::FxError> {
                 print!("{}", help::HELP);
             }
             "-l" | "--log" => {
-                if let Err(e) = run::run(
-                    std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
-                    true,
-                ) {
+                if let Err(e) = run::run(None, true, None) {
                     eprintln!("{}", e);
                 }
             }
@@ -45,14 +53,18 @@ fn main() -> Result<(), errors::FxError> {
                 print!("{}", shell::INTEGRATION_CODE);
             }
             _ => {
-                if let Err(e) = run::run(PathBuf::from(&args[1]), false) {
+                if let Err(e) = run::run(Some(PathBuf::from(&args[1])), false, None) {
                     eprintln!("{}", e);
                 }
             }
         },
         3 => {
             if args[1] == "-l" || args[1] == "--log" {
-                if let Err(e) = run::run(PathBuf::from(&args[2]), true) {
+                if let Err(e) = run::run(Some(PathBuf::from(&args[2])), true, None) {
+                    eprintln!("{}", e);
+                }
+            } else if args[1] == "-b" || args[1] == "--bookmark" {
+                if let Err(e) = run::run(None, false, Some(args[2].clone())) {
                     eprintln!("{}", e);
                 }
             } else {
@@ -0,0 +1,154 @@
+use super::errors::FxError;
+use super::functions::split_lines_including_wide_char;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+/// Position set by `m{a-z}` or `m{A-Z}`.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Mark {
+    pub dir: PathBuf,
+    /// Item under the cursor, if the directory is not empty.
+    pub item: Option<PathBuf>,
+}
+
+/// Marks of directories and items.
+/// Lowercase marks live in the session, while uppercase ones are persisted across sessions.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
+pub struct Marks {
+    #[serde(default)]
+    pub marks: BTreeMap<char, Mark>,
+}
+
+impl Marks {
+    /// Read uppercase marks from the file. If it does not exist or is broken, start with no marks.
+    pub fn read(marks_path: &Path) -> Self {
+        Self::load(marks_path).unwrap_or_else(|e| {
+            warn!("{}", e);
+            Marks::default()
+        })
+    }
+
+    /// Read uppercase marks from the file, which are empty if it does not exist.
+    /// Unlike `read`, a broken file is an error, so that it is not overwritten.
+    fn load(marks_path: &Path) -> Result<Self, FxError> {
+        let mut marks: Marks = match read_to_string(marks_path) {
+            Ok(s) => serde_yaml::from_str(&s).map_err(|e| {
+                FxError::Io(format!(
+                    "Cannot read marks from {}: {}",
+                    marks_path.display(),
+                    e
+                ))
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Marks::default(),
+            Err(e) => return Err(e.into()),
+        };
+        marks.marks.retain(|c, _| c.is_ascii_uppercase());
+        Ok(marks)
+    }
+
+    /// Write the uppercase mark to the file at once, keeping the ones set by other instances,
+    /// which are taken in as well.
+    pub fn persist(&mut self, c: char, marks_path: &Path) -> Result<(), FxError> {
+        let mut latest = Self::load(marks_path)?;
+        if let Some(mark) = self.marks.get(&c) {
+            latest.marks.insert(c, mark.clone());
+        }
+        latest.write(marks_path)?;
+        self.marks.retain(|c, _| !c.is_ascii_uppercase());
+        self.marks.extend(latest.marks);
+        Ok(())
+    }
+
+    /// Write uppercase marks to the file.
+    pub fn write(&self, marks_path: &Path) -> Result<(), FxError> {
+        let persisted = Marks {
+            marks: self
+                .marks
+                .iter()
+                .filter(|(c, _)| c.is_ascii_uppercase())
+                .map(|(c, mark)| (*c, mark.clone()))
+                .collect(),
+        };
+        let serialized = serde_yaml::to_string(&persisted)?;
+        std::fs::write(marks_path, serialized)?;
+        Ok(())
+    }
+
+    pub fn set(&mut self, c: char, dir: &Path, item: Option<&Path>) {
+        self.marks.insert(
+            c,
+            Mark {
+                dir: dir.to_path_buf(),
+                item: item.map(|x| x.to_path_buf()),
+            },
+        );
+    }
+
+    pub fn get(&self, c: char) -> Option<&Mark> {
+        self.marks.get(&c)
+    }
+
+    /// Lines to be shown by `:marks`.
+    pub fn prepare_marks(&self, width: u16) -> Vec<String> {
+        let s = self
+            .marks
+            .iter()
+            .map(|(c, mark)| {
+                let path = mark.item.as_ref().unwrap_or(&mark.dir);
+                format!("{} {}", c, path.display())
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+        split_lines_including_wide_char(&s, width.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_write_marks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".marks");
+        assert_eq!(Marks::read(&path), Marks::default());
+
+        let mut marks = Marks::default();
+        marks.set('a', dir.path(), None);
+        marks.set('A', dir.path(), Some(&path));
+        marks.write(&path).unwrap();
+
+        let read = Marks::read(&path);
+        assert!(read.get('a').is_none());
+        assert_eq!(read.get('A'), marks.get('A'));
+
+        //Marks set by another instance are kept.
+        let mut other = Marks::default();
+        other.set('B', dir.path(), None);
+        other.persist('B', &path).unwrap();
+        assert_eq!(other.get('A'), marks.get('A'));
+        let read = Marks::read(&path);
+        assert!(read.get('A').is_some() && read.get('B').is_some());
+
+        //A broken file is not overwritten.
+        std::fs::write(&path, "{ broken").unwrap();
+        assert_eq!(Marks::read(&path), Marks::default());
+        assert!(marks.persist('A', &path).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{ broken");
+    }
+
+    #[test]
+    fn test_prepare_marks() {
+        let mut marks = Marks::default();
+        assert!(marks.prepare_marks(80).iter().all(|x| x.is_empty()));
+        marks.set('b', Path::new("/tmp"), None);
+        marks.set('a', Path::new("/tmp"), Some(Path::new("/tmp/foo")));
+        assert_eq!(
+            marks.prepare_marks(80),
+            vec!["a /tmp/foo".to_string(), "b /tmp".to_string()]
+        );
+    }
+}
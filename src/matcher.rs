@@ -0,0 +1,173 @@
+use super::errors::FxError;
+
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+
+/// How the keyword of search and filter is interpreted.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMode {
+    /// Item names that contain the keyword match.
+    #[default]
+    Plain,
+    /// Item names that contain a match of the regular expression match.
+    Regex,
+    /// Item names that match the glob pattern as a whole (`*`, `?` and `[...]`).
+    Glob,
+}
+
+impl SearchMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SearchMode::Plain => "plain",
+            SearchMode::Regex => "regex",
+            SearchMode::Glob => "glob",
+        }
+    }
+}
+
+/// Matcher of item names, shared by `/`, `n`, `N` and the filter.
+#[derive(Debug, Clone)]
+pub struct Matcher {
+    regex: Regex,
+}
+
+impl Matcher {
+    /// Build the matcher from the keyword.
+    /// Flags at the beginning of the keyword override the config for this search:
+    /// `\r` (regex), `\g` (glob), `\p` (plain), `\c` (ignore case) and `\C` (match case).
+    /// With `smart_case`, the case is ignored unless the keyword contains an uppercase letter.
+    pub fn new(
+        keyword: &str,
+        mode: SearchMode,
+        ignore_case: bool,
+        smart_case: bool,
+    ) -> Result<Self, FxError> {
+        let mut mode = mode;
+        let mut case = None;
+        let mut pattern = keyword;
+        while let Some(rest) = pattern.strip_prefix('\\') {
+            match rest.chars().next() {
+                Some('r') => mode = SearchMode::Regex,
+                Some('g') => mode = SearchMode::Glob,
+                Some('p') => mode = SearchMode::Plain,
+                Some('c') => case = Some(true),
+                Some('C') => case = Some(false),
+                _ => break,
+            }
+            pattern = &rest[1..];
+        }
+
+        let ignore_case = case.unwrap_or(if smart_case {
+            !pattern.chars().any(char::is_uppercase)
+        } else {
+            ignore_case
+        });
+        let regex = match mode {
+            SearchMode::Plain => regex::escape(pattern),
+            SearchMode::Regex => pattern.to_owned(),
+            SearchMode::Glob => glob_to_regex(pattern),
+        };
+        RegexBuilder::new(&regex)
+            .case_insensitive(ignore_case)
+            .build()
+            .map(|regex| Matcher { regex })
+            .map_err(|_| FxError::Search(format!("Invalid pattern: {}", keyword)))
+    }
+
+    pub fn is_match(&self, file_name: &str) -> bool {
+        self.regex.is_match(file_name)
+    }
+}
+
+/// Convert the glob pattern to an anchored regular expression.
+fn glob_to_regex(glob: &str) -> String {
+    let mut result = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => result.push_str(".*"),
+            '?' => result.push('.'),
+            '[' => {
+                let mut class = String::new();
+                if chars.next_if(|c| *c == '!' || *c == '^').is_some() {
+                    class.push('^');
+                }
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        closed = true;
+                        break;
+                    }
+                    if c == '\\' || c == '[' {
+                        class.push('\\');
+                    }
+                    class.push(c);
+                }
+                if closed && !class.is_empty() {
+                    result.push('[');
+                    result.push_str(&class);
+                    result.push(']');
+                } else {
+                    //Not a character class: match `[` literally.
+                    result.push_str(&regex::escape("["));
+                    result.push_str(&regex::escape(&class));
+                    if closed {
+                        result.push_str(&regex::escape("]"));
+                    }
+                }
+            }
+            c => result.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    result.push('$');
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_match(keyword: &str, mode: SearchMode, smart_case: bool, name: &str) -> bool {
+        Matcher::new(keyword, mode, false, smart_case)
+            .unwrap()
+            .is_match(name)
+    }
+
+    #[test]
+    fn test_matcher_mode() {
+        assert!(is_match(".rs", SearchMode::Plain, false, "main.rs"));
+        assert!(!is_match(".rs", SearchMode::Plain, false, "mainrs"));
+        assert!(is_match("\\.rs$", SearchMode::Regex, false, "main.rs"));
+        assert!(!is_match("\\.rs$", SearchMode::Regex, false, "main.rs.bak"));
+        assert!(is_match("*.rs", SearchMode::Glob, false, "main.rs"));
+        assert!(!is_match("*.rs", SearchMode::Glob, false, "main.rs.bak"));
+        assert!(is_match("ma?n.[a-z]s", SearchMode::Glob, false, "main.rs"));
+        assert!(is_match("[!a]*", SearchMode::Glob, false, "main.rs"));
+        assert!(is_match("a[b", SearchMode::Glob, false, "a[b"));
+        assert!(Matcher::new("(", SearchMode::Regex, false, false).is_err());
+    }
+
+    #[test]
+    fn test_matcher_flags() {
+        assert!(is_match("\\r\\.rs$", SearchMode::Plain, false, "main.rs"));
+        assert!(is_match("\\g*.rs", SearchMode::Regex, false, "main.rs"));
+        assert!(is_match("\\p.*", SearchMode::Regex, false, "a.*"));
+        assert!(!is_match("\\p.*", SearchMode::Regex, false, "main.rs"));
+        assert!(is_match("\\cREADME", SearchMode::Plain, false, "readme"));
+        assert!(is_match("\\r\\cREAD", SearchMode::Plain, false, "readme"));
+        assert!(!is_match("\\Creadme", SearchMode::Plain, true, "README"));
+        assert!(is_match("\\x", SearchMode::Plain, false, "a\\x"));
+    }
+
+    #[test]
+    fn test_matcher_case() {
+        assert!(!is_match("readme", SearchMode::Plain, false, "README.md"));
+        assert!(Matcher::new("readme", SearchMode::Plain, true, false)
+            .unwrap()
+            .is_match("README.md"));
+        assert!(is_match("readme", SearchMode::Plain, true, "README.md"));
+        assert!(!is_match("Readme", SearchMode::Plain, true, "README.md"));
+        assert!(is_match("Readme", SearchMode::Plain, true, "Readme.md"));
+    }
+}
@@ -14,6 +14,8 @@ pub enum OpKind {
     Delete(DeletedFiles),
     Put(PutFiles),
     Rename(Vec<(PathBuf, PathBuf)>),
+    /// Items moved by rename(2) (or copy and delete across devices), without the trash.
+    Move(Vec<(PathBuf, PathBuf)>),
 }
 
 #[derive(Debug, Clone)]
@@ -66,6 +68,14 @@ fn log(op: &OpKind) {
                 );
             }
         }
+        OpKind::Move(op) => {
+            info!(
+                "MOVE: {:?}",
+                op.iter()
+                    .map(|v| format!("{:?} -> {:?}", v.0, v.1))
+                    .collect::<Vec<String>>()
+            );
+        }
     }
 }
 
@@ -96,6 +106,16 @@ pub fn relog(op: &OpKind, undo: bool) {
                 );
             }
         }
+        OpKind::Move(op) => {
+            result.push_str("MOVE");
+            info!(
+                "{} {:?}",
+                result,
+                op.iter()
+                    .map(|v| format!("{:?} -> {:?}", v.0, v.1))
+                    .collect::<Vec<String>>()
+            );
+        }
     }
 }
 
@@ -0,0 +1,329 @@
+use super::config::Config;
+use super::errors::FxError;
+use super::layout::Split;
+use super::matcher::SearchMode;
+use super::session::{Session, SortKey};
+
+/// Option that can be set by `:set` at runtime.
+/// `sort_by`, `show_hidden`, `preview` and `split` are saved to the session file,
+/// while the others come from the config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuntimeOption {
+    /// Sort key of the item list.
+    SortBy,
+    /// Show hidden items.
+    ShowHidden,
+    /// Show the preview.
+    Preview,
+    /// Split of the preview.
+    Split,
+    /// Ignore case in search.
+    IgnoreCase,
+    /// Ignore case in search unless the keyword contains uppercase.
+    SmartCase,
+    /// How the keyword of search and filter is interpreted.
+    SearchMode,
+    /// Skip items ignored by git in the fuzzy finder.
+    RespectGitignore,
+}
+
+/// Value of a runtime option.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OptionValue {
+    SortBy(SortKey),
+    ShowHidden(bool),
+    Preview(bool),
+    Split(Split),
+    IgnoreCase(bool),
+    SmartCase(bool),
+    SearchMode(SearchMode),
+    RespectGitignore(bool),
+}
+
+const SORT_KEYS: [SortKey; 2] = [SortKey::Name, SortKey::Time];
+const SPLITS: [Split; 2] = [Split::Vertical, Split::Horizontal];
+const SEARCH_MODES: [SearchMode; 3] = [SearchMode::Plain, SearchMode::Regex, SearchMode::Glob];
+
+impl RuntimeOption {
+    pub const ALL: [RuntimeOption; 8] = [
+        RuntimeOption::SortBy,
+        RuntimeOption::ShowHidden,
+        RuntimeOption::Preview,
+        RuntimeOption::Split,
+        RuntimeOption::IgnoreCase,
+        RuntimeOption::SmartCase,
+        RuntimeOption::SearchMode,
+        RuntimeOption::RespectGitignore,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RuntimeOption::SortBy => "sort_by",
+            RuntimeOption::ShowHidden => "show_hidden",
+            RuntimeOption::Preview => "preview",
+            RuntimeOption::Split => "split",
+            RuntimeOption::IgnoreCase => "ignore_case",
+            RuntimeOption::SmartCase => "smart_case",
+            RuntimeOption::SearchMode => "search_mode",
+            RuntimeOption::RespectGitignore => "respect_gitignore",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Self, FxError> {
+        RuntimeOption::ALL
+            .into_iter()
+            .find(|option| option.name() == name)
+            .ok_or_else(|| FxError::SetOption(format!("Unknown option: {}", name)))
+    }
+
+    pub fn is_bool(self) -> bool {
+        !matches!(
+            self,
+            RuntimeOption::SortBy | RuntimeOption::Split | RuntimeOption::SearchMode
+        )
+    }
+
+    /// Parse the value given by `:set`.
+    pub fn parse(self, value: &str) -> Result<OptionValue, FxError> {
+        let lower = value.to_lowercase();
+        let bool_value = || match lower.as_str() {
+            "true" | "on" | "yes" | "1" => Some(true),
+            "false" | "off" | "no" | "0" => Some(false),
+            _ => None,
+        };
+        let parsed = match self {
+            RuntimeOption::SortBy => SORT_KEYS
+                .into_iter()
+                .find(|x| sort_key_name(x) == lower)
+                .map(OptionValue::SortBy),
+            RuntimeOption::ShowHidden => bool_value().map(OptionValue::ShowHidden),
+            RuntimeOption::Preview => bool_value().map(OptionValue::Preview),
+            RuntimeOption::Split => SPLITS
+                .into_iter()
+                .find(|x| split_name(*x) == lower)
+                .map(OptionValue::Split),
+            RuntimeOption::IgnoreCase => bool_value().map(OptionValue::IgnoreCase),
+            RuntimeOption::SmartCase => bool_value().map(OptionValue::SmartCase),
+            RuntimeOption::SearchMode => SEARCH_MODES
+                .into_iter()
+                .find(|x| x.as_str() == lower)
+                .map(OptionValue::SearchMode),
+            RuntimeOption::RespectGitignore => bool_value().map(OptionValue::RespectGitignore),
+        };
+        parsed.ok_or_else(|| {
+            FxError::SetOption(format!("Invalid value for {}: {}", self.name(), value))
+        })
+    }
+}
+
+impl OptionValue {
+    pub fn option(&self) -> RuntimeOption {
+        match self {
+            OptionValue::SortBy(_) => RuntimeOption::SortBy,
+            OptionValue::ShowHidden(_) => RuntimeOption::ShowHidden,
+            OptionValue::Preview(_) => RuntimeOption::Preview,
+            OptionValue::Split(_) => RuntimeOption::Split,
+            OptionValue::IgnoreCase(_) => RuntimeOption::IgnoreCase,
+            OptionValue::SmartCase(_) => RuntimeOption::SmartCase,
+            OptionValue::SearchMode(_) => RuntimeOption::SearchMode,
+            OptionValue::RespectGitignore(_) => RuntimeOption::RespectGitignore,
+        }
+    }
+
+    /// The value after toggling: negated for bool, or the next choice.
+    pub fn toggled(&self) -> OptionValue {
+        fn next<T: PartialEq + Clone>(choices: &[T], current: &T) -> T {
+            let i = choices.iter().position(|x| x == current).unwrap_or(0);
+            choices[(i + 1) % choices.len()].clone()
+        }
+        match self {
+            OptionValue::SortBy(x) => OptionValue::SortBy(next(&SORT_KEYS, x)),
+            OptionValue::ShowHidden(b) => OptionValue::ShowHidden(!b),
+            OptionValue::Preview(b) => OptionValue::Preview(!b),
+            OptionValue::Split(x) => OptionValue::Split(next(&SPLITS, x)),
+            OptionValue::IgnoreCase(b) => OptionValue::IgnoreCase(!b),
+            OptionValue::SmartCase(b) => OptionValue::SmartCase(!b),
+            OptionValue::SearchMode(x) => OptionValue::SearchMode(next(&SEARCH_MODES, x)),
+            OptionValue::RespectGitignore(b) => OptionValue::RespectGitignore(!b),
+        }
+    }
+
+    /// Values of the options that come from the config file, with their defaults.
+    pub fn from_config(config: &Config) -> Vec<OptionValue> {
+        vec![
+            OptionValue::IgnoreCase(config.ignore_case.unwrap_or(false)),
+            OptionValue::SmartCase(config.smart_case.unwrap_or(false)),
+            OptionValue::SearchMode(config.search_mode.unwrap_or_default()),
+            OptionValue::RespectGitignore(config.respect_gitignore.unwrap_or(true)),
+        ]
+    }
+
+    /// Values of the options saved in the session file.
+    pub fn from_session(session: &Session) -> Vec<OptionValue> {
+        vec![
+            OptionValue::SortBy(session.sort_by.clone()),
+            OptionValue::ShowHidden(session.show_hidden),
+            OptionValue::Preview(session.preview.unwrap_or(false)),
+            OptionValue::Split(session.split.unwrap_or_default()),
+        ]
+    }
+}
+
+impl std::fmt::Display for OptionValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OptionValue::SortBy(x) => write!(f, "{}", sort_key_name(x)),
+            OptionValue::Split(x) => write!(f, "{}", split_name(*x)),
+            OptionValue::SearchMode(x) => write!(f, "{}", x.as_str()),
+            OptionValue::ShowHidden(b)
+            | OptionValue::Preview(b)
+            | OptionValue::IgnoreCase(b)
+            | OptionValue::SmartCase(b)
+            | OptionValue::RespectGitignore(b) => write!(f, "{}", b),
+        }
+    }
+}
+
+fn sort_key_name(sort_key: &SortKey) -> &'static str {
+    match sort_key {
+        SortKey::Name => "name",
+        SortKey::Time => "time",
+    }
+}
+
+fn split_name(split: Split) -> &'static str {
+    match split {
+        Split::Vertical => "vertical",
+        Split::Horizontal => "horizontal",
+    }
+}
+
+/// What `:set` does with the argument.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SetCommand {
+    /// `:set option=value`, `:set option` (bool) or `:set nooption` (bool)
+    Set(OptionValue),
+    /// `:set option!`, or `:set invoption` (bool)
+    Toggle(RuntimeOption),
+    /// `:set option?`, or `:set option` (not bool)
+    Query(RuntimeOption),
+}
+
+/// Parse an argument of `:set`, in the manner of vim.
+pub fn parse_set(arg: &str) -> Result<SetCommand, FxError> {
+    if let Some((name, value)) = arg.split_once('=') {
+        let option = RuntimeOption::from_name(name)?;
+        return Ok(SetCommand::Set(option.parse(value)?));
+    }
+    if let Some(name) = arg.strip_suffix('?') {
+        return Ok(SetCommand::Query(RuntimeOption::from_name(name)?));
+    }
+    if let Some(name) = arg.strip_suffix('!') {
+        return Ok(SetCommand::Toggle(RuntimeOption::from_name(name)?));
+    }
+    if let Ok(option) = RuntimeOption::from_name(arg) {
+        return if option.is_bool() {
+            Ok(SetCommand::Set(option.parse("true")?))
+        } else {
+            Ok(SetCommand::Query(option))
+        };
+    }
+    let bool_option = |name: &str| {
+        RuntimeOption::from_name(name)
+            .ok()
+            .filter(|option| option.is_bool())
+    };
+    if let Some(option) = arg.strip_prefix("no").and_then(bool_option) {
+        return Ok(SetCommand::Set(option.parse("false")?));
+    }
+    if let Some(option) = arg.strip_prefix("inv").and_then(bool_option) {
+        return Ok(SetCommand::Toggle(option));
+    }
+    Err(FxError::SetOption(format!("Unknown option: {}", arg)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_option_value() {
+        let show_hidden = RuntimeOption::from_name("show_hidden").unwrap();
+        assert_eq!(
+            show_hidden.parse("on").unwrap(),
+            OptionValue::ShowHidden(true)
+        );
+        assert_eq!(
+            show_hidden.parse("False").unwrap(),
+            OptionValue::ShowHidden(false)
+        );
+        assert!(show_hidden.parse("maybe").is_err());
+        let sort_by = RuntimeOption::from_name("sort_by").unwrap();
+        assert_eq!(
+            sort_by.parse("Time").unwrap(),
+            OptionValue::SortBy(SortKey::Time)
+        );
+        assert!(sort_by.parse("size").is_err());
+        assert!(RuntimeOption::from_name("unknown").is_err());
+        for option in RuntimeOption::ALL {
+            assert_eq!(RuntimeOption::from_name(option.name()).unwrap(), option);
+        }
+    }
+
+    #[test]
+    fn test_toggled() {
+        assert_eq!(
+            OptionValue::ShowHidden(true).toggled(),
+            OptionValue::ShowHidden(false)
+        );
+        assert_eq!(
+            OptionValue::Split(Split::Vertical).toggled(),
+            OptionValue::Split(Split::Horizontal)
+        );
+        assert_eq!(
+            OptionValue::Split(Split::Horizontal).toggled(),
+            OptionValue::Split(Split::Vertical)
+        );
+        assert_eq!(
+            OptionValue::SearchMode(SearchMode::Glob).toggled(),
+            OptionValue::SearchMode(SearchMode::Plain)
+        );
+        assert_eq!(OptionValue::Split(Split::Vertical).to_string(), "vertical");
+        assert_eq!(OptionValue::IgnoreCase(true).to_string(), "true");
+    }
+
+    #[test]
+    fn test_parse_set() {
+        assert_eq!(
+            parse_set("sort_by=time").unwrap(),
+            SetCommand::Set(OptionValue::SortBy(SortKey::Time))
+        );
+        assert_eq!(
+            parse_set("sort_by").unwrap(),
+            SetCommand::Query(RuntimeOption::SortBy)
+        );
+        assert_eq!(
+            parse_set("sort_by!").unwrap(),
+            SetCommand::Toggle(RuntimeOption::SortBy)
+        );
+        assert_eq!(
+            parse_set("show_hidden").unwrap(),
+            SetCommand::Set(OptionValue::ShowHidden(true))
+        );
+        assert_eq!(
+            parse_set("noshow_hidden").unwrap(),
+            SetCommand::Set(OptionValue::ShowHidden(false))
+        );
+        assert_eq!(
+            parse_set("invshow_hidden").unwrap(),
+            SetCommand::Toggle(RuntimeOption::ShowHidden)
+        );
+        assert_eq!(
+            parse_set("show_hidden?").unwrap(),
+            SetCommand::Query(RuntimeOption::ShowHidden)
+        );
+        assert!(parse_set("nosort_by").is_err());
+        assert!(parse_set("sort_by=size").is_err());
+        assert!(parse_set("unknown!").is_err());
+    }
+}
@@ -0,0 +1,156 @@
+use super::errors::FxError;
+use super::state::Registers;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+#[cfg(unix)]
+use nix::fcntl::{Flock, FlockArg};
+
+/// File held with an advisory lock, released when dropped.
+#[cfg(unix)]
+type LockedFile = Flock<File>;
+#[cfg(not(unix))]
+type LockedFile = File;
+
+/// Lock the whole file with flock, shared or exclusive.
+#[cfg(unix)]
+fn lock(file: File, exclusive: bool) -> Result<LockedFile, FxError> {
+    let arg = if exclusive {
+        FlockArg::LockExclusive
+    } else {
+        FlockArg::LockShared
+    };
+    Flock::lock(file, arg).map_err(|(_, e)| FxError::Nix(e.to_string()))
+}
+
+/// Locking is not supported on this platform.
+#[cfg(not(unix))]
+fn lock(file: File, _exclusive: bool) -> Result<LockedFile, FxError> {
+    Ok(file)
+}
+
+/// File to share registers between instances and across sessions.
+/// Reading takes a shared lock and writing an exclusive one,
+/// so that instances never see a half-written file.
+#[derive(Debug)]
+pub struct RegisterFile {
+    path: PathBuf,
+    /// Modified time of the file when it was last read or written by this instance.
+    modified: Option<SystemTime>,
+    /// Registers as of the last sync, to detect changes in this instance.
+    synced: Registers,
+}
+
+impl RegisterFile {
+    pub fn new(path: &Path) -> Self {
+        RegisterFile {
+            path: path.to_path_buf(),
+            modified: None,
+            synced: Registers::default(),
+        }
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        self.path.metadata().and_then(|m| m.modified()).ok()
+    }
+
+    /// Reload registers if the file was updated by another instance.
+    /// Items whose paths no longer exist are dropped.
+    /// If the file is broken, registers in this instance are kept as they are.
+    pub fn load(&mut self, registers: &mut Registers) -> Result<(), FxError> {
+        let modified = self.modified();
+        if modified.is_none() || modified == self.modified {
+            return Ok(());
+        }
+        //Do not retry until the file is updated again.
+        self.modified = modified;
+        let mut file = lock(File::open(&self.path)?, false)?;
+        let mut s = String::new();
+        file.read_to_string(&mut s)?;
+        drop(file);
+
+        let mut loaded: Registers = serde_yaml::from_str(&s).map_err(|e| {
+            FxError::Io(format!(
+                "Cannot read registers from {}: {}",
+                self.path.display(),
+                e
+            ))
+        })?;
+        loaded.prune();
+        *registers = loaded.clone();
+        self.synced = loaded;
+        Ok(())
+    }
+
+    /// Write registers if they were changed in this instance.
+    pub fn save(&mut self, registers: &Registers) -> Result<(), FxError> {
+        if *registers == self.synced {
+            return Ok(());
+        }
+        //Do not retry until registers change again, even if writing fails.
+        self.synced = registers.clone();
+        let serialized = serde_yaml::to_string(registers)?;
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&self.path)?;
+        let mut file = lock(file, true)?;
+        file.set_len(0)?;
+        file.write_all(serialized.as_bytes())?;
+        file.flush()?;
+        drop(file);
+
+        self.modified = self.modified();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::ItemBuffer;
+
+    #[test]
+    fn test_register_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".registers");
+        let item = dir.path().join("a");
+        std::fs::write(&item, "").unwrap();
+
+        let mut registers = Registers::default();
+        let mut first = RegisterFile::new(&path);
+        first.load(&mut registers).unwrap();
+        assert_eq!(registers, Registers::default());
+
+        let buffer = ItemBuffer::from_path(&item).unwrap();
+        let gone = ItemBuffer::from_path(&item).map(|mut b| {
+            b.file_path = dir.path().join("gone");
+            b
+        });
+        registers.yank_item(&[gone.unwrap()], Some('a'), false);
+        registers.yank_item(std::slice::from_ref(&buffer), None, false);
+        first.save(&registers).unwrap();
+
+        //Another instance reads the registers, dropping the vanished item.
+        let mut other = Registers::default();
+        let mut second = RegisterFile::new(&path);
+        second.load(&mut other).unwrap();
+        assert_eq!(other.unnamed, vec![buffer.clone()]);
+        assert!(other.named.is_empty());
+
+        //Not reloaded unless the file is updated.
+        other.unnamed.clear();
+        second.load(&mut other).unwrap();
+        assert!(other.unnamed.is_empty());
+
+        //A broken file keeps the registers in this instance.
+        std::fs::write(&path, "{ broken").unwrap();
+        let mut third = RegisterFile::new(&path);
+        assert!(third.load(&mut registers).is_err());
+        assert_eq!(registers.unnamed, vec![buffer.clone()]);
+        assert!(third.load(&mut registers).is_ok());
+    }
+}
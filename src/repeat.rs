@@ -0,0 +1,86 @@
+/// Last change, repeated by `.` at the cursor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// Delete the items from the cursor.
+    Delete {
+        count: usize,
+        reg: Option<char>,
+        append: bool,
+    },
+    /// Put items from the register (`"` for the unnamed one).
+    Put(char),
+    /// Rename the item in the same way as `from` to `to`.
+    Rename {
+        from: String,
+        to: String,
+    },
+    NewFile(String),
+    NewDir(String),
+}
+
+/// Apply the same edit as `from` -> `to` to the name.
+/// The edited part is found from the end of the name (e.g. changing the extension
+/// or appending `.bak`), or from the beginning (e.g. adding a prefix).
+/// Return None if the name does not contain the part to be edited.
+pub fn repeat_rename(from: &str, to: &str, name: &str) -> Option<String> {
+    let prefix = common_prefix_len(from, to);
+    let suffix = common_prefix_len(&reversed(&from[prefix..]), &reversed(&to[prefix..]));
+
+    //Edit near the end: replace the tail.
+    let (from_tail, to_tail) = (&from[prefix..], &to[prefix..]);
+    if let Some(head) = name.strip_suffix(from_tail) {
+        return Some(format!("{}{}", head, to_tail));
+    }
+    //Edit near the beginning: replace the head.
+    let (from_head, to_head) = (&from[..from.len() - suffix], &to[..to.len() - suffix]);
+    name.strip_prefix(from_head)
+        .map(|tail| format!("{}{}", to_head, tail))
+}
+
+/// Length in bytes of the common prefix, at a char boundary.
+fn common_prefix_len(a: &str, b: &str) -> usize {
+    a.char_indices()
+        .zip(b.chars())
+        .find(|((_, x), y)| x != y)
+        .map_or_else(|| a.len().min(b.len()), |((i, _), _)| i)
+}
+
+fn reversed(s: &str) -> String {
+    s.chars().rev().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repeat_rename() {
+        //Change the extension.
+        assert_eq!(
+            repeat_rename("a.jpeg", "a.jpg", "image.jpeg"),
+            Some("image.jpg".to_string())
+        );
+        //Append.
+        assert_eq!(
+            repeat_rename("foo.txt", "foo.txt.bak", "bar.md"),
+            Some("bar.md.bak".to_string())
+        );
+        //Prepend.
+        assert_eq!(
+            repeat_rename("x.txt", "old_x.txt", "y.md"),
+            Some("old_y.md".to_string())
+        );
+        //Remove a prefix.
+        assert_eq!(
+            repeat_rename("draft_a.md", "a.md", "draft_b.txt"),
+            Some("b.txt".to_string())
+        );
+        assert_eq!(repeat_rename("a.jpeg", "a.jpg", "b.png"), None);
+        //Multibyte characters.
+        assert_eq!(
+            repeat_rename("写真.jpeg", "写真.jpg", "画像.jpeg"),
+            Some("画像.jpg".to_string())
+        );
+        assert_eq!(common_prefix_len("写真", "写実"), "写".len());
+    }
+}
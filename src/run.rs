@@ -1,12 +1,25 @@
+use super::bookmarks::Bookmarks;
+use super::clipboard::copy_to_clipboard;
+use super::completion::*;
 use super::config::{read_config, FELIX};
 use super::errors::FxError;
+use super::finder::Finder;
+use super::frecency::Frecency;
 use super::functions::*;
-use super::layout::{PreviewType, Split};
+use super::grep::{grep, GrepView};
+use super::history::*;
+use super::jumplist::JumpList;
+use super::keymap::{keys_to_string, Action, KeyInput, KeyMatch};
+use super::layout::{PreviewType, Side, Split};
+use super::marks::Marks;
 use super::nums::*;
-use super::op::*;
-use super::session::*;
+use super::options::RuntimeOption;
+use super::reg_file::RegisterFile;
+use super::repeat::Change;
+use super::session::read_session;
 use super::state::*;
 use super::term::*;
+use super::trash::{self, TrashBackend};
 
 use crossterm::cursor::{RestorePosition, SavePosition};
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
@@ -23,7 +36,14 @@ use std::thread;
 use std::time::Instant;
 
 const TRASH: &str = "Trash";
+const TRASH_INFO: &str = "TrashInfo";
 const SESSION_FILE: &str = ".session";
+const HISTORY_FILE: &str = ".history";
+const MARKS_FILE: &str = ".marks";
+const BOOKMARKS_FILE: &str = ".bookmarks";
+const FRECENCY_FILE: &str = ".frecency";
+const JUMPLIST_FILE: &str = ".jumplist";
+const REGISTERS_FILE: &str = ".registers";
 /// Where the item list starts to scroll.
 const SCROLL_POINT: u16 = 3;
 const CLRSCR: &str = "\x1B[2J";
@@ -33,10 +53,30 @@ const PROMPT_INSERT_FILE: &str = "New file: ";
 const PROMPT_INSERT_DIR: &str = "New directory: ";
 const PROMPT_RENAME: &str = "New name: ";
 const PROMPT_SEARCH: &str = "/";
+const PROMPT_FIND: &str = "Find: ";
+const PROMPT_FILTER: &str = "Filter: ";
+const PROMPT_BOOKMARK: &str = "Bookmark: ";
 const PROMPT_COMMAND_LINE: &str = ":";
 
+/// Files in the data local directory, written at the end of the session
+/// (registers are written whenever they change, and uppercase marks and bookmarks when they are set).
+struct DataFiles {
+    session: PathBuf,
+    history: PathBuf,
+    marks: PathBuf,
+    bookmarks: PathBuf,
+    frecency: PathBuf,
+    jumplist: PathBuf,
+    registers: PathBuf,
+}
+
 /// Launch the app. If initialization goes wrong, return error.
-pub fn run(arg: PathBuf, log: bool) -> Result<(), FxError> {
+/// Without `arg`, the app starts in the current directory,
+/// or in the last directory of the previous session if `restore_last_dir` is enabled.
+/// With `bookmark`, the app starts in the bookmarked directory instead.
+pub fn run(arg: Option<PathBuf>, log: bool, bookmark: Option<String>) -> Result<(), FxError> {
+    let has_arg = arg.is_some();
+    let arg = arg.unwrap_or_else(|| env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
     //Check if argument path is valid.
     if !&arg.exists() {
         println!();
@@ -102,6 +142,12 @@ pub fn run(arg: PathBuf, log: bool) -> Result<(), FxError> {
     if !trash_dir_path.exists() {
         std::fs::create_dir_all(&trash_dir_path)?;
     }
+    //`.trashinfo` of the items in the trash directory, as in the freedesktop.org Trash specification.
+    let trash_info_path = {
+        let mut path = data_local_path.clone();
+        path.push(TRASH_INFO);
+        path
+    };
 
     //If `-l / --log` is set, initialize logger.
     if log {
@@ -110,15 +156,103 @@ pub fn run(arg: PathBuf, log: bool) -> Result<(), FxError> {
 
     //Set the session file path.
     let session_path = {
-        let mut path = data_local_path;
+        let mut path = data_local_path.clone();
         path.push(SESSION_FILE);
         path
     };
 
+    //Set the history file path.
+    let history_path = {
+        let mut path = data_local_path.clone();
+        path.push(HISTORY_FILE);
+        path
+    };
+
+    //Set the marks file path.
+    let marks_path = {
+        let mut path = data_local_path.clone();
+        path.push(MARKS_FILE);
+        path
+    };
+
+    //Set the bookmarks file path.
+    let bookmarks_path = {
+        let mut path = data_local_path.clone();
+        path.push(BOOKMARKS_FILE);
+        path
+    };
+
+    //Set the path of the built-in frecency database.
+    let frecency_path = {
+        let mut path = data_local_path.clone();
+        path.push(FRECENCY_FILE);
+        path
+    };
+
+    //Set the jumplist file path.
+    let jumplist_path = {
+        let mut path = data_local_path.clone();
+        path.push(JUMPLIST_FILE);
+        path
+    };
+
+    //Set the path of the file to share registers.
+    let registers_path = {
+        let mut path = data_local_path;
+        path.push(REGISTERS_FILE);
+        path
+    };
+
     //Initialize app state. Inside `State::new()`, config file is read.
     let mut state = State::new(&session_path)?;
-    state.trash_dir = trash_dir_path;
+    (state.trash_dir, state.trash_info_dir) = match state.trash_backend {
+        TrashBackend::Felix => (trash_dir_path, trash_info_path),
+        TrashBackend::Freedesktop => {
+            let home_trash = trash::home_trash()?;
+            let path = trash::files_dir(&home_trash);
+            if !path.exists() {
+                std::fs::create_dir_all(&path)?;
+            }
+            (path, trash::info_dir(&home_trash))
+        }
+    };
+    //Purge old items in the trash in the background.
+    if state.trash_max_age_days.is_some() || state.trash_max_size.is_some() {
+        let files = state.trash_dir.clone();
+        let info = state.trash_info_dir.clone();
+        let backend = state.trash_backend;
+        let (max_age_days, max_size) = (state.trash_max_age_days, state.trash_max_size);
+        thread::spawn(move || {
+            if let Err(e) = trash::auto_purge(&files, &info, backend, max_age_days, max_size) {
+                error!("{}", e);
+            }
+        });
+    }
     state.lwd_file = lwd_file_path;
+    state.history = History::read(&history_path);
+    state.marks = Marks::read(&marks_path);
+    state.bookmarks = Bookmarks::read(&bookmarks_path);
+    state.frecency = Frecency::read(&frecency_path);
+    state.jumplist = JumpList::read(&jumplist_path);
+    //Directory to jump to after launching, or the item to put the cursor on.
+    let mut start_dir = None;
+    let mut start_item = None;
+    if let Some(name) = bookmark {
+        match state.bookmarks.get(&name) {
+            Ok(dir) => start_dir = Some(dir.clone()),
+            Err(e) => return Err(FxError::Arg(format!("{}\n`fx -h` shows help.", e))),
+        }
+    } else if state.restore_last_dir && !has_arg {
+        //Restore the last directory and item of the previous session.
+        //If the directory was removed, fall back to the nearest existing ancestor.
+        let session = read_session(&session_path);
+        if let Some(last_dir) = session.last_dir {
+            match session.last_item.map(|name| last_dir.join(name)) {
+                Some(item) if item.symlink_metadata().is_ok() => start_item = Some(item),
+                _ => start_dir = last_dir.ancestors().find(|p| p.is_dir()).map(PathBuf::from),
+            }
+        }
+    }
     let normalized_arg = arg.normalize();
     if normalized_arg.is_err() {
         return Err(FxError::Arg(format!(
@@ -134,7 +268,16 @@ pub fn run(arg: PathBuf, log: bool) -> Result<(), FxError> {
     };
 
     //If the main function causes panic, catch it.
-    let result = panic::catch_unwind(|| _run(state, session_path));
+    let data_files = DataFiles {
+        session: session_path,
+        history: history_path,
+        marks: marks_path,
+        bookmarks: bookmarks_path,
+        frecency: frecency_path,
+        jumplist: jumplist_path,
+        registers: registers_path,
+    };
+    let result = panic::catch_unwind(|| _run(state, data_files, start_dir, start_item));
     leave_raw_mode();
 
     if let Err(panic) = result {
@@ -155,7 +298,12 @@ pub fn run(arg: PathBuf, log: bool) -> Result<(), FxError> {
 }
 
 /// Run the app. (Containing the main loop)
-fn _run(mut state: State, session_path: PathBuf) -> Result<(), FxError> {
+fn _run(
+    mut state: State,
+    data_files: DataFiles,
+    start_dir: Option<PathBuf>,
+    start_item: Option<PathBuf>,
+) -> Result<(), FxError> {
     //Save the current cursor position and enter the alternate screen with crossterm
     let mut screen = stdout();
     write!(screen, "{}", SavePosition)?;
@@ -177,6 +325,13 @@ fn _run(mut state: State, session_path: PathBuf) -> Result<(), FxError> {
     } else {
         state.reload(BEGINNING_ROW)?;
     }
+    //If launched by `--bookmark` or restoring the last directory, jump to it.
+    if let Some(dir) = start_dir {
+        state.chdir(&dir, Move::Jump)?;
+    }
+    if let Some(item) = start_item {
+        state.jump_to_item(&item)?;
+    }
     screen.flush()?;
 
     // Spawn another thread to watch the config file.
@@ -210,7 +365,23 @@ fn _run(mut state: State, session_path: PathBuf) -> Result<(), FxError> {
         });
     }
 
+    // Keys typed so far, until they match an action in the keymap.
+    let mut keys: Vec<KeyInput> = Vec::new();
+    // Count typed before the keys (e.g. 5 of 5j).
+    let mut count_input: Option<usize> = None;
+
+    //Registers are shared with other instances through the file.
+    let mut reg_file = RegisterFile::new(&data_files.registers);
+    if let Err(e) = reg_file.load(&mut state.registers) {
+        print_warning(e, state.layout.y);
+    }
+
     'main: loop {
+        //Save registers changed by the last action.
+        if let Err(e) = reg_file.save(&state.registers) {
+            print_warning(e, state.layout.y);
+        }
+
         // Check if config file is updated
         if state.config_path.is_some() {
             if let Ok(mut wait_update) = wait_update.lock() {
@@ -243,423 +414,997 @@ fn _run(mut state: State, session_path: PathBuf) -> Result<(), FxError> {
                 kind: KeyEventKind::Press,
                 ..
             }) => {
-                match modifiers {
-                    KeyModifiers::CONTROL => match code {
-                        // go down 1/2 page
-                        KeyCode::Char('d') => {
-                            let half = state.layout.terminal_row.div_ceil(2);
-                            let mut cursor_move_count = 0;
-                            if let Some(start_pos) = state.v_start {
-                                // visual mode
-                                for _n in 0..half {
-                                    if len == 0 || state.layout.nums.index == len - 1 {
-                                        break;
-                                    } else if state.layout.y + cursor_move_count
-                                        >= state.layout.terminal_row - 4
-                                        && len
-                                            > (state.layout.terminal_row - BEGINNING_ROW) as usize
-                                                - 1
-                                    {
-                                        if state.layout.nums.index >= start_pos {
-                                            state.layout.nums.go_down();
-                                            state.layout.nums.inc_skip();
-                                            let item = state.get_item_mut()?;
-                                            item.selected = true;
-                                        } else {
-                                            let item = state.get_item_mut()?;
-                                            item.selected = false;
-                                            state.layout.nums.go_down();
-                                            state.layout.nums.inc_skip();
-                                        }
-                                    } else if state.layout.nums.index >= start_pos {
+                //Reload registers if another instance updated them.
+                if let Err(e) = reg_file.load(&mut state.registers) {
+                    print_warning(e, state.layout.y);
+                }
+                let key = KeyInput::new(code, modifiers);
+                //Digits before the keys are the count, unless they are mapped to actions.
+                if let KeyCode::Char(c @ '0'..='9') = code {
+                    if keys.is_empty()
+                        && key.modifiers == KeyModifiers::NONE
+                        && (c != '0' || count_input.is_some())
+                        && state.keymap.get(&[key], state.v_start.is_some()) == KeyMatch::NotFound
+                    {
+                        let digit = c.to_digit(10).unwrap_or(0) as usize;
+                        let count = count_input
+                            .unwrap_or(0)
+                            .saturating_mul(10)
+                            .saturating_add(digit);
+                        count_input = Some(count);
+                        go_to_info_line_and_reset();
+                        print!("{}", count);
+                        continue;
+                    }
+                }
+                keys.push(key);
+                let action = match state.keymap.get(&keys, state.v_start.is_some()) {
+                    KeyMatch::Found(action) => {
+                        if keys.len() > 1 || count_input.is_some() {
+                            state.escape();
+                        }
+                        keys.clear();
+                        action
+                    }
+                    KeyMatch::Pending => {
+                        //Show the keys typed so far, waiting for the rest of the sequence.
+                        go_to_info_line_and_reset();
+                        print!(
+                            "{}{}",
+                            count_input.map(|n| n.to_string()).unwrap_or_default(),
+                            keys_to_string(&keys)
+                        );
+                        show_cursor();
+                        continue;
+                    }
+                    KeyMatch::NotFound => {
+                        //If input does not match any of the defined keys, ignore it.
+                        if keys.len() > 1 || count_input.is_some() {
+                            state.escape();
+                        }
+                        keys.clear();
+                        count_input = None;
+                        continue;
+                    }
+                };
+                let count = count_input.take();
+
+                match action {
+                    // go down 1/2 page
+                    Action::HalfPageDown => {
+                        let half = state.layout.terminal_row.div_ceil(2);
+                        let mut cursor_move_count = 0;
+                        if let Some(start_pos) = state.v_start {
+                            // visual mode
+                            for _n in 0..half {
+                                if len == 0 || state.layout.nums.index == len - 1 {
+                                    break;
+                                } else if state.layout.y + cursor_move_count
+                                    >= state.layout.terminal_row - 4
+                                    && len
+                                        > (state.layout.terminal_row - BEGINNING_ROW) as usize - 1
+                                {
+                                    if state.layout.nums.index >= start_pos {
                                         state.layout.nums.go_down();
+                                        state.layout.nums.inc_skip();
                                         let item = state.get_item_mut()?;
                                         item.selected = true;
-                                        cursor_move_count += 1;
                                     } else {
                                         let item = state.get_item_mut()?;
                                         item.selected = false;
-                                        state.layout.nums.go_down();
-                                        cursor_move_count += 1;
-                                    }
-                                }
-                                state.redraw(state.layout.y + cursor_move_count);
-                            } else {
-                                // normal mode
-                                for _n in 0..half {
-                                    if len == 0 || state.layout.nums.index == len - 1 {
-                                        break;
-                                    } else if state.layout.y + cursor_move_count
-                                        >= state.layout.terminal_row - 1 - SCROLL_POINT
-                                        && len
-                                            > (state.layout.terminal_row - BEGINNING_ROW) as usize
-                                                - 1
-                                    {
                                         state.layout.nums.go_down();
                                         state.layout.nums.inc_skip();
-                                    } else {
-                                        state.layout.nums.go_down();
-                                        cursor_move_count += 1;
                                     }
+                                } else if state.layout.nums.index >= start_pos {
+                                    state.layout.nums.go_down();
+                                    let item = state.get_item_mut()?;
+                                    item.selected = true;
+                                    cursor_move_count += 1;
+                                } else {
+                                    let item = state.get_item_mut()?;
+                                    item.selected = false;
+                                    state.layout.nums.go_down();
+                                    cursor_move_count += 1;
+                                }
+                            }
+                            state.redraw(state.layout.y + cursor_move_count);
+                        } else {
+                            // normal mode
+                            for _n in 0..half {
+                                if len == 0 || state.layout.nums.index == len - 1 {
+                                    break;
+                                } else if state.layout.y + cursor_move_count
+                                    >= state.layout.terminal_row - 1 - SCROLL_POINT
+                                    && len
+                                        > (state.layout.terminal_row - BEGINNING_ROW) as usize - 1
+                                {
+                                    state.layout.nums.go_down();
+                                    state.layout.nums.inc_skip();
+                                } else {
+                                    state.layout.nums.go_down();
+                                    cursor_move_count += 1;
                                 }
-                                state.redraw(state.layout.y + cursor_move_count);
                             }
+                            state.redraw(state.layout.y + cursor_move_count);
                         }
+                    }
 
-                        // go up 1/2 page
-                        KeyCode::Char('u') => {
-                            let half = state.layout.terminal_row.div_ceil(2);
-                            let mut cursor_move_count = 0;
-                            if let Some(start_pos) = state.v_start {
-                                // visual mode
-                                for _n in 0..half {
-                                    if state.layout.nums.index == 0 {
-                                        break;
-                                    } else if state.layout.y - cursor_move_count
-                                        <= BEGINNING_ROW + 3
-                                        && state.layout.nums.skip != 0
-                                    {
-                                        if state.layout.nums.index > start_pos {
-                                            let item = state.get_item_mut()?;
-                                            item.selected = false;
-                                            state.layout.nums.go_up();
-                                            state.layout.nums.dec_skip();
-                                        } else {
-                                            state.layout.nums.go_up();
-                                            state.layout.nums.dec_skip();
-                                            let item = state.get_item_mut()?;
-                                            item.selected = true;
-                                        }
-                                    } else if state.layout.nums.index > start_pos {
+                    // go up 1/2 page
+                    Action::HalfPageUp => {
+                        let half = state.layout.terminal_row.div_ceil(2);
+                        let mut cursor_move_count = 0;
+                        if let Some(start_pos) = state.v_start {
+                            // visual mode
+                            for _n in 0..half {
+                                if state.layout.nums.index == 0 {
+                                    break;
+                                } else if state.layout.y - cursor_move_count <= BEGINNING_ROW + 3
+                                    && state.layout.nums.skip != 0
+                                {
+                                    if state.layout.nums.index > start_pos {
                                         let item = state.get_item_mut()?;
                                         item.selected = false;
                                         state.layout.nums.go_up();
-                                        cursor_move_count += 1;
+                                        state.layout.nums.dec_skip();
                                     } else {
                                         state.layout.nums.go_up();
+                                        state.layout.nums.dec_skip();
                                         let item = state.get_item_mut()?;
                                         item.selected = true;
-                                        cursor_move_count += 1;
                                     }
+                                } else if state.layout.nums.index > start_pos {
+                                    let item = state.get_item_mut()?;
+                                    item.selected = false;
+                                    state.layout.nums.go_up();
+                                    cursor_move_count += 1;
+                                } else {
+                                    state.layout.nums.go_up();
+                                    let item = state.get_item_mut()?;
+                                    item.selected = true;
+                                    cursor_move_count += 1;
                                 }
-                                state.redraw(state.layout.y - cursor_move_count);
-                            } else {
-                                //normal mode
-                                for _n in 0..half {
-                                    if state.layout.nums.index == 0 {
-                                        break;
-                                    } else if state.layout.y - cursor_move_count
-                                        <= BEGINNING_ROW + SCROLL_POINT
-                                        && state.layout.nums.skip != 0
-                                    {
-                                        state.layout.nums.go_up();
-                                        state.layout.nums.dec_skip();
-                                    } else {
-                                        state.layout.nums.go_up();
-                                        cursor_move_count += 1;
-                                    }
+                            }
+                            state.redraw(state.layout.y - cursor_move_count);
+                        } else {
+                            //normal mode
+                            for _n in 0..half {
+                                if state.layout.nums.index == 0 {
+                                    break;
+                                } else if state.layout.y - cursor_move_count
+                                    <= BEGINNING_ROW + SCROLL_POINT
+                                    && state.layout.nums.skip != 0
+                                {
+                                    state.layout.nums.go_up();
+                                    state.layout.nums.dec_skip();
+                                } else {
+                                    state.layout.nums.go_up();
+                                    cursor_move_count += 1;
                                 }
-                                state.redraw(state.layout.y - cursor_move_count);
                             }
+                            state.redraw(state.layout.y - cursor_move_count);
                         }
+                    }
 
-                        //redo
-                        KeyCode::Char('r') => {
-                            if state.v_start.is_some() {
+                    //redo
+                    Action::Redo => {
+                        if state.v_start.is_some() {
+                            continue;
+                        }
+                        let op_len = state.operations.op_list.len();
+                        if op_len == 0 || state.operations.pos == 0 || op_len < state.operations.pos
+                        {
+                            print_info("No operations left.", state.layout.y);
+                            continue;
+                        }
+                        if let Some(op) =
+                            state.operations.op_list.get(op_len - state.operations.pos)
+                        {
+                            let op = op.clone();
+                            if let Err(e) = state.redo(&op) {
+                                print_warning(e, state.layout.y);
                                 continue;
                             }
-                            let op_len = state.operations.op_list.len();
-                            if op_len == 0
-                                || state.operations.pos == 0
-                                || op_len < state.operations.pos
-                            {
-                                print_info("No operations left.", state.layout.y);
-                                continue;
+
+                            let new_len = state.list.len();
+                            if new_len == 0 {
+                                state.layout.nums.reset();
+                                state.move_cursor(BEGINNING_ROW);
+                            } else if state.layout.nums.index > new_len - 1 {
+                                let new_y = state.layout.y
+                                    - (state.layout.nums.index - (new_len - 1)) as u16;
+                                state.layout.nums.index = new_len - 1;
+                                state.move_cursor(new_y)
+                            } else {
+                                state.move_cursor(state.layout.y);
                             }
-                            if let Some(op) =
-                                state.operations.op_list.get(op_len - state.operations.pos)
-                            {
-                                let op = op.clone();
-                                if let Err(e) = state.redo(&op) {
-                                    print_warning(e, state.layout.y);
-                                    continue;
-                                }
+                        }
+                    }
 
-                                let new_len = state.list.len();
-                                if new_len == 0 {
-                                    state.layout.nums.reset();
-                                    state.move_cursor(BEGINNING_ROW);
-                                } else if state.layout.nums.index > new_len - 1 {
-                                    let new_y = state.layout.y
-                                        - (state.layout.nums.index - (new_len - 1)) as u16;
-                                    state.layout.nums.index = new_len - 1;
-                                    state.move_cursor(new_y)
-                                } else {
-                                    state.move_cursor(state.layout.y);
-                                }
+                    // jump backward
+                    Action::JumpBackward => {
+                        if let Some(path_to_jump_to) = state.jumplist.get_backward() {
+                            if path_to_jump_to.exists() {
+                                state.chdir(&path_to_jump_to, Move::List)?;
+                                state.jumplist.pos_backward();
+                            } else {
+                                print_warning(
+                                    "Directory backward not found: Removed from jumplist.",
+                                    state.layout.y,
+                                );
+                                state.jumplist.remove_backward();
                             }
                         }
+                    }
+
+                    //scroll down the previewed text
+                    Action::ScrollDownPreview => {
+                        if state.layout.is_preview() {
+                            state.scroll_down_preview(state.layout.y);
+                        }
+                    }
+                    //scroll up the previewed text
+                    Action::ScrollUpPreview => {
+                        if state.layout.is_preview() {
+                            state.scroll_up_preview(state.layout.y);
+                        }
+                    }
+
+                    //Reset visual selection and return to normal mode.
+                    //In normal mode, clear the filter if exists, and then the selection.
+                    Action::Escape => {
+                        if state.v_start.is_none() && state.filter.is_some() {
+                            state.clear_filter()?;
+                            continue;
+                        }
+                        if state.v_start.is_none() && !state.selection.is_empty() {
+                            state.selection.clear();
+                            state.redraw(state.layout.y);
+                            continue;
+                        }
+                        state.reset_selection();
+                        state.redraw(state.layout.y);
+                        continue;
+                    }
 
-                        // jump backward
-                        KeyCode::Char('o') => {
-                            if let Some(path_to_jump_to) = state.jumplist.get_backward() {
-                                if path_to_jump_to.exists() {
-                                    state.chdir(&path_to_jump_to, Move::List)?;
-                                    state.jumplist.pos_backward();
+                    //Go down. If lists exceed max-row, lists "scrolls" before the bottom of the list
+                    Action::MoveDown => {
+                        if let Some(count) = count {
+                            state.go_to_index(state.layout.nums.index.saturating_add(count));
+                            continue;
+                        }
+                        if let Some(start_pos) = state.v_start {
+                            //In visual mode
+                            if len == 0 || state.layout.nums.index == len - 1 {
+                                continue;
+                            } else if state.layout.y >= state.layout.terminal_row - 4
+                                && len > (state.layout.terminal_row - BEGINNING_ROW) as usize - 1
+                            {
+                                if state.layout.nums.index >= start_pos {
+                                    state.layout.nums.go_down();
+                                    state.layout.nums.inc_skip();
+                                    let item = state.get_item_mut()?;
+                                    item.selected = true;
+                                    state.redraw(state.layout.y);
                                 } else {
-                                    print_warning(
-                                        "Directory backward not found: Removed from jumplist.",
-                                        state.layout.y,
-                                    );
-                                    state.jumplist.remove_backward();
+                                    let item = state.get_item_mut()?;
+                                    item.selected = false;
+                                    state.layout.nums.go_down();
+                                    state.layout.nums.inc_skip();
+                                    state.redraw(state.layout.y);
                                 }
+                            } else if state.layout.nums.index >= start_pos {
+                                state.layout.nums.go_down();
+                                let item = state.get_item_mut()?;
+                                item.selected = true;
+                                state.redraw(state.layout.y + 1);
+                            } else {
+                                let item = state.get_item_mut()?;
+                                item.selected = false;
+                                state.layout.nums.go_down();
+                                state.redraw(state.layout.y + 1);
+                            }
+                        } else {
+                            //normal mode
+                            if len == 0 || state.layout.nums.index == len - 1 {
+                                continue;
+                            } else if state.layout.y >= state.layout.terminal_row - 1 - SCROLL_POINT
+                                && len > (state.layout.terminal_row - BEGINNING_ROW) as usize - 1
+                            {
+                                state.layout.nums.go_down();
+                                state.layout.nums.inc_skip();
+                                state.redraw(state.layout.y);
+                            } else {
+                                state.layout.nums.go_down();
+                                state.move_cursor(state.layout.y + 1);
                             }
                         }
+                    }
 
-                        //Other commands are disabled when Ctrl is pressed,
-                        //except <C-i> (equivalent to Tab).
-                        _ => {
+                    //Go up. If lists exceed max-row, lists "scrolls" before the top of the list
+                    Action::MoveUp => {
+                        if let Some(count) = count {
+                            state.go_to_index(state.layout.nums.index.saturating_sub(count));
                             continue;
                         }
-                    },
-                    KeyModifiers::ALT => match code {
-                        //scroll down the previewed text
-                        KeyCode::Char('j') | KeyCode::Down => {
-                            if state.layout.is_preview() {
-                                state.scroll_down_preview(state.layout.y);
+                        if let Some(start_pos) = state.v_start {
+                            //visual mode
+                            if state.layout.nums.index == 0 {
+                                continue;
+                            } else if state.layout.y <= BEGINNING_ROW + 3
+                                && state.layout.nums.skip != 0
+                            {
+                                if state.layout.nums.index > start_pos {
+                                    let item = state.get_item_mut()?;
+                                    item.selected = false;
+                                    state.layout.nums.go_up();
+                                    state.layout.nums.dec_skip();
+                                    state.redraw(state.layout.y);
+                                } else {
+                                    state.layout.nums.go_up();
+                                    state.layout.nums.dec_skip();
+                                    let item = state.get_item_mut()?;
+                                    item.selected = true;
+                                    state.redraw(state.layout.y);
+                                }
+                            } else if state.layout.nums.index > start_pos {
+                                let item = state.get_item_mut()?;
+                                item.selected = false;
+                                state.layout.nums.go_up();
+                                state.redraw(state.layout.y - 1);
+                            } else {
+                                state.layout.nums.go_up();
+                                let item = state.get_item_mut()?;
+                                item.selected = true;
+                                state.redraw(state.layout.y - 1);
+                            }
+                        } else {
+                            //normal mode
+                            if state.layout.nums.index == 0 {
+                                continue;
+                            } else if state.layout.y <= BEGINNING_ROW + SCROLL_POINT
+                                && state.layout.nums.skip != 0
+                            {
+                                state.layout.nums.go_up();
+                                state.layout.nums.dec_skip();
+                                state.redraw(state.layout.y);
+                            } else {
+                                state.layout.nums.go_up();
+                                state.move_cursor(state.layout.y - 1);
                             }
                         }
-                        //scroll up the previewed text
-                        KeyCode::Char('k') | KeyCode::Up => {
-                            if state.layout.is_preview() {
-                                state.scroll_up_preview(state.layout.y);
+                    }
+
+                    //Go to top
+                    Action::GoToTop => {
+                        //With the count, go to the item at the line.
+                        if let Some(count) = count {
+                            state.go_to_index(count.saturating_sub(1));
+                            continue;
+                        }
+                        if let Some(start_pos) = state.v_start {
+                            //visual mode
+                            if state.layout.nums.index == 0 {
+                                continue;
                             }
+                            state.select_from_top(start_pos);
                         }
-                        //Other commands are disabled when Alt is pressed.
-                        _ => {
+                        state.layout.nums.reset();
+                        state.redraw(BEGINNING_ROW);
+                    }
+
+                    //Go to bottom
+                    Action::GoToBottom => {
+                        //With the count, go to the item at the line.
+                        if let Some(count) = count {
+                            state.go_to_index(count.saturating_sub(1));
                             continue;
                         }
-                    },
-                    KeyModifiers::NONE | KeyModifiers::SHIFT => {
-                        match code {
-                            //Reset visual selection and return to normal mode
-                            KeyCode::Esc => {
-                                state.reset_selection();
-                                state.redraw(state.layout.y);
+                        if let Some(start_pos) = state.v_start {
+                            //visual mode
+                            if len > (state.layout.terminal_row - BEGINNING_ROW) as usize {
+                                state.select_to_bottom(start_pos);
+                                state.layout.nums.skip =
+                                    (len as u16) + BEGINNING_ROW - state.layout.terminal_row;
+                                state.layout.nums.go_bottom(len - 1);
+                                state.redraw(state.layout.terminal_row - 1);
+                            } else {
+                                state.select_to_bottom(start_pos);
+                                state.layout.nums.go_bottom(len - 1);
+                                state.redraw(len as u16 + BEGINNING_ROW - 1);
+                            }
+                        } else {
+                            //normal mode
+                            if len == 0 {
                                 continue;
                             }
+                            if len > (state.layout.terminal_row - BEGINNING_ROW) as usize {
+                                state.layout.nums.skip =
+                                    (len as u16) + BEGINNING_ROW - state.layout.terminal_row;
+                                state.layout.nums.go_bottom(len - 1);
+                                let cursor_pos = state.layout.terminal_row - 1;
+                                state.redraw(cursor_pos);
+                            } else {
+                                state.layout.nums.go_bottom(len - 1);
+                                state.move_cursor(len as u16 + BEGINNING_ROW - 1);
+                            }
+                        }
+                    }
 
-                            //Go down. If lists exceed max-row, lists "scrolls" before the bottom of the list
-                            KeyCode::Char('j') | KeyCode::Down => {
-                                if let Some(start_pos) = state.v_start {
-                                    //In visual mode
-                                    if len == 0 || state.layout.nums.index == len - 1 {
-                                        continue;
-                                    } else if state.layout.y >= state.layout.terminal_row - 4
-                                        && len
-                                            > (state.layout.terminal_row - BEGINNING_ROW) as usize
-                                                - 1
-                                    {
-                                        if state.layout.nums.index >= start_pos {
-                                            state.layout.nums.go_down();
-                                            state.layout.nums.inc_skip();
-                                            let item = state.get_item_mut()?;
-                                            item.selected = true;
-                                            state.redraw(state.layout.y);
-                                        } else {
-                                            let item = state.get_item_mut()?;
-                                            item.selected = false;
-                                            state.layout.nums.go_down();
-                                            state.layout.nums.inc_skip();
-                                            state.redraw(state.layout.y);
-                                        }
-                                    } else if state.layout.nums.index >= start_pos {
-                                        state.layout.nums.go_down();
-                                        let item = state.get_item_mut()?;
-                                        item.selected = true;
-                                        state.redraw(state.layout.y + 1);
-                                    } else {
-                                        let item = state.get_item_mut()?;
-                                        item.selected = false;
-                                        state.layout.nums.go_down();
-                                        state.redraw(state.layout.y + 1);
+                    //Open file or change directory
+                    Action::Open => {
+                        //In visual mode, this is disabled.
+                        if state.v_start.is_some() {
+                            continue;
+                        }
+                        let mut dest: Option<PathBuf> = None;
+                        if let Ok(item) = state.get_item() {
+                            let mut err: Option<FxError> = None;
+                            match item.file_type {
+                                FileType::File => {
+                                    execute!(screen, EnterAlternateScreen)?;
+                                    if let Err(e) = state.open_file(item) {
+                                        err = Some(e);
                                     }
-                                } else {
-                                    //normal mode
-                                    if len == 0 || state.layout.nums.index == len - 1 {
-                                        continue;
-                                    } else if state.layout.y
-                                        >= state.layout.terminal_row - 1 - SCROLL_POINT
-                                        && len
-                                            > (state.layout.terminal_row - BEGINNING_ROW) as usize
-                                                - 1
-                                    {
-                                        state.layout.nums.go_down();
-                                        state.layout.nums.inc_skip();
-                                        state.redraw(state.layout.y);
-                                    } else {
-                                        state.layout.nums.go_down();
-                                        state.move_cursor(state.layout.y + 1);
+                                    execute!(screen, EnterAlternateScreen)?;
+                                    hide_cursor();
+                                    state.reload(state.layout.y)?;
+                                    if let Some(e) = err {
+                                        print_warning(e, state.layout.y);
                                     }
+                                    continue;
                                 }
-                            }
-
-                            //Go up. If lists exceed max-row, lists "scrolls" before the top of the list
-                            KeyCode::Char('k') | KeyCode::Up => {
-                                if let Some(start_pos) = state.v_start {
-                                    //visual mode
-                                    if state.layout.nums.index == 0 {
-                                        continue;
-                                    } else if state.layout.y <= BEGINNING_ROW + 3
-                                        && state.layout.nums.skip != 0
-                                    {
-                                        if state.layout.nums.index > start_pos {
-                                            let item = state.get_item_mut()?;
-                                            item.selected = false;
-                                            state.layout.nums.go_up();
-                                            state.layout.nums.dec_skip();
-                                            state.redraw(state.layout.y);
+                                FileType::Symlink => match &item.symlink_dir_path {
+                                    Some(true_path) => {
+                                        if true_path.exists() {
+                                            dest = Some(true_path.to_path_buf());
                                         } else {
-                                            state.layout.nums.go_up();
-                                            state.layout.nums.dec_skip();
-                                            let item = state.get_item_mut()?;
-                                            item.selected = true;
-                                            state.redraw(state.layout.y);
+                                            print_warning("Broken link.", state.layout.y);
+                                            continue;
                                         }
-                                    } else if state.layout.nums.index > start_pos {
-                                        let item = state.get_item_mut()?;
-                                        item.selected = false;
-                                        state.layout.nums.go_up();
-                                        state.redraw(state.layout.y - 1);
-                                    } else {
-                                        state.layout.nums.go_up();
-                                        let item = state.get_item_mut()?;
-                                        item.selected = true;
-                                        state.redraw(state.layout.y - 1);
                                     }
-                                } else {
-                                    //normal mode
-                                    if state.layout.nums.index == 0 {
+                                    None => {
+                                        execute!(screen, EnterAlternateScreen)?;
+                                        if let Err(e) = state.open_file(item) {
+                                            err = Some(e);
+                                        }
+                                        execute!(screen, EnterAlternateScreen)?;
+                                        hide_cursor();
+                                        state.reload(state.layout.y)?;
+                                        if let Some(e) = err {
+                                            print_warning(e, state.layout.y);
+                                        }
                                         continue;
-                                    } else if state.layout.y <= BEGINNING_ROW + SCROLL_POINT
-                                        && state.layout.nums.skip != 0
-                                    {
-                                        state.layout.nums.go_up();
-                                        state.layout.nums.dec_skip();
-                                        state.redraw(state.layout.y);
+                                    }
+                                },
+                                FileType::Directory => {
+                                    if item.file_path.exists() {
+                                        dest = Some(item.file_path.clone());
                                     } else {
-                                        state.layout.nums.go_up();
-                                        state.move_cursor(state.layout.y - 1);
+                                        print_warning("Invalid directory.", state.layout.y);
+                                        continue;
                                     }
                                 }
                             }
+                        }
+                        if let Some(dest) = dest {
+                            if let Err(e) = state.chdir(&dest, Move::Down) {
+                                print_warning(e, state.layout.y);
+                            }
+                        }
+                    }
 
-                            //Go to top
-                            KeyCode::Char('g') => {
-                                if let Some(start_pos) = state.v_start {
-                                    //visual mode
-                                    if state.layout.nums.index == 0 {
+                    //Open a file in a new window
+                    //This works only if i) [exec] is set in config file
+                    //and ii) the extension of the item matches the key.
+                    //If not, warning message appears.
+                    Action::OpenInNewWindow => {
+                        //In visual mode, this is disabled.
+                        if state.v_start.is_some() {
+                            continue;
+                        }
+                        if let Ok(item) = state.get_item() {
+                            match item.file_type {
+                                FileType::File => {
+                                    if let Err(e) = state.open_file_in_new_window() {
+                                        hide_cursor();
+                                        state.redraw(state.layout.y);
+                                        print_warning(e, state.layout.y);
                                         continue;
-                                    } else {
-                                        go_to_info_line_and_reset();
-                                        print!("g");
-                                        show_cursor();
-                                        screen.flush()?;
-
-                                        if let Event::Key(KeyEvent {
-                                            code,
-                                            kind: KeyEventKind::Press,
-                                            ..
-                                        }) = event::read()?
+                                    }
+                                    hide_cursor();
+                                    state.redraw(state.layout.y);
+                                    continue;
+                                }
+                                _ => {
+                                    continue;
+                                }
+                            }
+                        }
+                    }
+
+                    //Go to the parent directory if exists
+                    Action::GoToParent => {
+                        //In visual mode, this is disabled.
+                        if state.v_start.is_some() {
+                            continue;
+                        }
+                        let pre = state.current_dir.clone();
+
+                        match pre.parent() {
+                            Some(parent_p) => {
+                                if let Err(e) = state.chdir(parent_p, Move::Up) {
+                                    print_warning(e, state.layout.y);
+                                }
+                            }
+                            None => {
+                                continue;
+                            }
+                        }
+                    }
+
+                    // jump forward
+                    Action::JumpForward => {
+                        if let Some(path_to_jump_to) = state.jumplist.get_forward() {
+                            if path_to_jump_to.exists() {
+                                state.chdir(&path_to_jump_to, Move::List)?;
+                            } else {
+                                print_warning(
+                                    "Directory forward not found: Removed from jumplist.",
+                                    state.layout.y,
+                                );
+                                state.jumplist.remove_forward();
+                            }
+                            state.jumplist.pos_forward();
+                        }
+                    }
+
+                    //Set the mark (m{a-z}) or jump to it ('{a-z}).
+                    //Uppercase marks are kept across sessions.
+                    Action::SetMark | Action::JumpToMark => {
+                        let is_set = action == Action::SetMark;
+                        go_to_info_line_and_reset();
+                        print!("{}", if is_set { "m" } else { "'" });
+                        screen.flush()?;
+
+                        let c = loop {
+                            if let Event::Key(KeyEvent {
+                                code,
+                                kind: KeyEventKind::Press,
+                                ..
+                            }) = event::read()?
+                            {
+                                match code {
+                                    KeyCode::Char(c) => break Some(c),
+                                    KeyCode::Esc => break None,
+                                    _ => continue,
+                                }
+                            }
+                        };
+                        go_to_info_line_and_reset();
+                        let Some(c) = c else {
+                            continue;
+                        };
+                        if !c.is_ascii_alphabetic() {
+                            print_warning("Input not supported.", state.layout.y);
+                            continue;
+                        }
+                        if is_set {
+                            state.set_mark(c);
+                            //Uppercase marks are shared with other instances.
+                            if c.is_ascii_uppercase() {
+                                if let Err(e) = state.marks.persist(c, &data_files.marks) {
+                                    print_warning(e, state.layout.y);
+                                }
+                            }
+                            if state.layout.is_marks() {
+                                state.redraw(state.layout.y);
+                            }
+                        } else if let Err(e) = state.jump_to_mark(c) {
+                            print_warning(e, state.layout.y);
+                        }
+                    }
+
+                    //Unpack archive file. Fails if it is not any of supported types
+                    Action::Unpack => {
+                        //In visual mode, this is disabled.
+                        //TODO! Enable this in visual mode.
+                        if state.v_start.is_some() {
+                            continue;
+                        }
+                        print_info("Unpacking...", state.layout.y);
+                        screen.flush()?;
+                        let start = Instant::now();
+                        if let Err(e) = state.unpack() {
+                            state.reload(state.layout.y)?;
+                            print_warning(e, state.layout.y);
+                            continue;
+                        }
+                        let duration = duration_to_string(start.elapsed());
+                        state.reload(state.layout.y)?;
+                        print_info(format!("Unpacked. [{}]", duration), state.layout.y);
+                    }
+
+                    //Jumps to the directory that matches the keyword.
+                    //Without zoxide, the built-in frecency database is used.
+                    Action::Zoxide => {
+                        //In visual mode, this is disabled.
+                        if state.v_start.is_some() {
+                            continue;
+                        }
+                        delete_pointer();
+                        go_to_info_line_and_reset();
+                        print!("z");
+                        show_cursor();
+
+                        let mut command: Vec<char> = vec!['z'];
+                        screen.flush()?;
+
+                        let mut current_pos = 3;
+                        let mut history_cursor = HistoryCursor::default();
+                        let mut completion: Option<Completion> = None;
+                        'zoxide: loop {
+                            if let Event::Key(KeyEvent {
+                                code,
+                                modifiers,
+                                kind: KeyEventKind::Press,
+                                ..
+                            }) = event::read()?
+                            {
+                                //Close the completion popup by keys other than Tab.
+                                if completion.is_some()
+                                    && !matches!(code, KeyCode::Tab | KeyCode::BackTab)
+                                {
+                                    completion = None;
+                                    state.redraw(state.layout.y);
+                                    go_to_info_line_and_reset();
+                                    print!("{}", &command.iter().collect::<String>());
+                                    move_to(current_pos, 2);
+                                }
+                                match (code, modifiers) {
+                                    (KeyCode::Esc, KeyModifiers::NONE) => {
+                                        state.escape();
+                                        break 'zoxide;
+                                    }
+
+                                    (KeyCode::Left, KeyModifiers::NONE) => {
+                                        if current_pos == INITIAL_POS_Z {
+                                            continue;
+                                        };
+                                        current_pos -= 1;
+                                        move_left(1);
+                                    }
+
+                                    (KeyCode::Right, KeyModifiers::NONE) => {
+                                        if current_pos as usize
+                                            == command.len() + INITIAL_POS_Z as usize
                                         {
-                                            match code {
-                                                KeyCode::Char('g') => {
-                                                    hide_cursor();
-                                                    state.select_from_top(start_pos);
+                                            continue;
+                                        };
+                                        current_pos += 1;
+                                        move_right(1);
+                                    }
+
+                                    (KeyCode::Backspace, KeyModifiers::NONE)
+                                    | (KeyCode::Char('h'), KeyModifiers::CONTROL) => {
+                                        if current_pos == INITIAL_POS_Z + 1 {
+                                            state.escape();
+                                            break 'zoxide;
+                                        };
+                                        command.remove((current_pos - INITIAL_POS_Z - 1).into());
+                                        current_pos -= 1;
+                                        history_cursor.reset();
+
+                                        clear_current_line();
+                                        to_info_line();
+                                        print!("{}", &command.iter().collect::<String>(),);
+                                        move_to(current_pos, 2);
+                                    }
+
+                                    (KeyCode::Enter, KeyModifiers::NONE) => {
+                                        hide_cursor();
+                                        let command = command.iter().collect::<String>();
+                                        let commands =
+                                            command.split_whitespace().collect::<Vec<&str>>();
+                                        state
+                                            .history
+                                            .add(HistoryKind::Zoxide, &commands[1..].join(" "));
+                                        if commands.len() == 1 {
+                                            //go to the home directory
+                                            let home_dir = dirs::home_dir().ok_or_else(|| {
+                                                FxError::Dirs("Cannot read home dir.".to_string())
+                                            })?;
+                                            if let Err(e) = state.chdir(&home_dir, Move::Jump) {
+                                                print_warning(e, state.layout.y);
+                                            }
+                                            break 'zoxide;
+                                        } else if !state.uses_zoxide() {
+                                            let query = commands[1..].join(" ");
+                                            match state.z_candidates(&query).into_iter().next() {
+                                                Some(target_dir) => {
                                                     state.layout.nums.reset();
-                                                    state.redraw(BEGINNING_ROW);
+                                                    if let Err(e) = state.chdir(
+                                                        &PathBuf::from(target_dir),
+                                                        Move::Jump,
+                                                    ) {
+                                                        print_warning(e, state.layout.y);
+                                                    }
                                                 }
-
-                                                _ => {
-                                                    state.escape();
+                                                None => print_warning(
+                                                    "Keyword does not match the database.",
+                                                    state.layout.y,
+                                                ),
+                                            }
+                                            break 'zoxide;
+                                        } else if let Ok(output) =
+                                            std::process::Command::new("zoxide")
+                                                .arg("query")
+                                                .args(&commands[1..])
+                                                .output()
+                                        {
+                                            let output = output.stdout;
+                                            if output.is_empty() {
+                                                print_warning(
+                                                    "Keyword does not match the database.",
+                                                    state.layout.y,
+                                                );
+                                                break 'zoxide;
+                                            } else {
+                                                let target_dir = std::str::from_utf8(&output);
+                                                match target_dir {
+                                                    Err(e) => {
+                                                        print_warning(e, state.layout.y);
+                                                        break 'zoxide;
+                                                    }
+                                                    Ok(target_dir) => {
+                                                        hide_cursor();
+                                                        state.layout.nums.reset();
+                                                        let target_path =
+                                                            PathBuf::from(target_dir.trim());
+                                                        if let Err(e) =
+                                                            state.chdir(&target_path, Move::Jump)
+                                                        {
+                                                            print_warning(e, state.layout.y);
+                                                        }
+                                                        break 'zoxide;
+                                                    }
                                                 }
                                             }
+                                        } else {
+                                            print_warning(
+                                                "Failed to execute zoxide",
+                                                state.layout.y,
+                                            );
+                                            break 'zoxide;
                                         }
                                     }
-                                } else {
-                                    //normal mode
-                                    go_to_info_line_and_reset();
-                                    print!("g");
-                                    show_cursor();
-                                    screen.flush()?;
-
-                                    if let Event::Key(KeyEvent {
-                                        code,
-                                        kind: KeyEventKind::Press,
-                                        ..
-                                    }) = event::read()?
-                                    {
-                                        match code {
-                                            KeyCode::Char('g') => {
-                                                hide_cursor();
-                                                state.layout.nums.reset();
-                                                state.redraw(BEGINNING_ROW);
-                                            }
 
-                                            _ => {
-                                                hide_cursor();
-                                                clear_current_line();
-                                                state.move_cursor(state.layout.y);
+                                    (KeyCode::Tab, KeyModifiers::NONE) | (KeyCode::BackTab, _) => {
+                                        let is_next = code == KeyCode::Tab;
+                                        if let Some(c) = completion.as_mut() {
+                                            if is_next {
+                                                c.next();
+                                            } else {
+                                                c.prev();
+                                            }
+                                        } else {
+                                            let query = command.iter().skip(1).collect::<String>();
+                                            let candidates = state.z_candidates(&query);
+                                            if candidates.is_empty() {
+                                                continue;
+                                            }
+                                            //Replace the whole query with the candidate.
+                                            command =
+                                                format!("z {}", query.trim()).chars().collect();
+                                            let mut c =
+                                                Completion::new(2, command.len(), candidates);
+                                            if !is_next {
+                                                c.prev();
+                                            }
+                                            completion = Some(c);
+                                        }
+                                        if let Some(c) = completion.as_mut() {
+                                            c.apply(&mut command);
+                                            if c.candidates.len() > 1 {
+                                                c.print_candidates(
+                                                    state.layout.terminal_row,
+                                                    state.layout.terminal_column,
+                                                );
                                             }
                                         }
+                                        let new_input = command.iter().collect::<String>();
+                                        replace_command_line(
+                                            "",
+                                            &new_input,
+                                            &mut command,
+                                            &mut 0,
+                                            &mut current_pos,
+                                        );
+                                        history_cursor.reset();
+                                    }
+
+                                    (KeyCode::Up, KeyModifiers::NONE)
+                                    | (KeyCode::Char('p'), KeyModifiers::CONTROL) => {
+                                        let input = command.iter().skip(1).collect::<String>();
+                                        if let Some(entry) = history_cursor.prev(
+                                            state.history.entries(HistoryKind::Zoxide),
+                                            input.trim(),
+                                        ) {
+                                            replace_command_line(
+                                                "",
+                                                &format!("z {}", entry),
+                                                &mut command,
+                                                &mut 0,
+                                                &mut current_pos,
+                                            );
+                                        }
+                                    }
+
+                                    (KeyCode::Down, KeyModifiers::NONE)
+                                    | (KeyCode::Char('n'), KeyModifiers::CONTROL) => {
+                                        if let Some(entry) = history_cursor
+                                            .next(state.history.entries(HistoryKind::Zoxide))
+                                        {
+                                            let new_input = if entry.is_empty() {
+                                                "z".to_owned()
+                                            } else {
+                                                format!("z {}", entry)
+                                            };
+                                            replace_command_line(
+                                                "",
+                                                &new_input,
+                                                &mut command,
+                                                &mut 0,
+                                                &mut current_pos,
+                                            );
+                                        }
+                                    }
+
+                                    (KeyCode::Char(c), _) => {
+                                        command.insert((current_pos - INITIAL_POS_Z).into(), c);
+                                        current_pos += 1;
+                                        history_cursor.reset();
+                                        clear_current_line();
+                                        to_info_line();
+                                        print!("{}", &command.iter().collect::<String>(),);
+                                        move_to(current_pos, 2);
                                     }
+
+                                    _ => continue,
                                 }
+                                screen.flush()?;
                             }
+                        }
+                    }
 
-                            //Go to bottom
-                            KeyCode::Char('G') => {
-                                if let Some(start_pos) = state.v_start {
-                                    //visual mode
-                                    if len > (state.layout.terminal_row - BEGINNING_ROW) as usize {
-                                        state.select_to_bottom(start_pos);
-                                        state.layout.nums.skip = (len as u16) + BEGINNING_ROW
-                                            - state.layout.terminal_row;
-                                        state.layout.nums.go_bottom(len - 1);
-                                        state.redraw(state.layout.terminal_row - 1);
-                                    } else {
-                                        state.select_to_bottom(start_pos);
-                                        state.layout.nums.go_bottom(len - 1);
-                                        state.redraw(len as u16 + BEGINNING_ROW - 1);
+                    //fuzzy finder
+                    Action::FuzzyFind => {
+                        //In visual mode, this is disabled.
+                        if state.v_start.is_some() {
+                            continue;
+                        }
+                        delete_pointer();
+                        go_to_info_line_and_reset();
+                        print!("Finding...");
+                        screen.flush()?;
+
+                        let mut finder = Finder::new(
+                            &state.current_dir,
+                            state.layout.show_hidden,
+                            state.respect_gitignore,
+                        );
+                        let mut query: Vec<char> = Vec::new();
+                        // express position in terminal
+                        let mut current_pos = 2 + PROMPT_FIND.len() as u16;
+                        // express position in Vec<Char>
+                        let mut current_char_pos = 0;
+
+                        finder.print(state.layout.terminal_row, state.layout.terminal_column);
+                        go_to_info_line_and_reset();
+                        print!("{}", PROMPT_FIND);
+                        show_cursor();
+                        screen.flush()?;
+
+                        loop {
+                            if let Event::Key(KeyEvent {
+                                code,
+                                modifiers,
+                                kind: KeyEventKind::Press,
+                                ..
+                            }) = event::read()?
+                            {
+                                match (code, modifiers) {
+                                    (KeyCode::Esc, KeyModifiers::NONE) => {
+                                        hide_cursor();
+                                        state.redraw(state.layout.y);
+                                        break;
                                     }
-                                } else {
-                                    //normal mode
-                                    if len == 0 {
+
+                                    (KeyCode::Left, KeyModifiers::NONE) => {
+                                        move_left_command_line(
+                                            &mut query,
+                                            &mut current_char_pos,
+                                            &mut current_pos,
+                                        );
+                                        screen.flush()?;
                                         continue;
                                     }
-                                    if len > (state.layout.terminal_row - BEGINNING_ROW) as usize {
-                                        state.layout.nums.skip = (len as u16) + BEGINNING_ROW
-                                            - state.layout.terminal_row;
-                                        state.layout.nums.go_bottom(len - 1);
-                                        let cursor_pos = state.layout.terminal_row - 1;
-                                        state.redraw(cursor_pos);
-                                    } else {
-                                        state.layout.nums.go_bottom(len - 1);
-                                        state.move_cursor(len as u16 + BEGINNING_ROW - 1);
+
+                                    (KeyCode::Right, KeyModifiers::NONE) => {
+                                        move_right_command_line(
+                                            &mut query,
+                                            &mut current_char_pos,
+                                            &mut current_pos,
+                                        );
+                                        screen.flush()?;
+                                        continue;
                                     }
-                                }
-                            }
 
-                            //Open file or change directory
-                            KeyCode::Char('l') | KeyCode::Enter | KeyCode::Right => {
-                                //In visual mode, this is disabled.
-                                if state.v_start.is_some() {
-                                    continue;
-                                }
-                                let mut dest: Option<PathBuf> = None;
-                                if let Ok(item) = state.get_item() {
-                                    let mut err: Option<FxError> = None;
-                                    match item.file_type {
-                                        FileType::File => {
+                                    (KeyCode::Up, KeyModifiers::NONE)
+                                    | (KeyCode::Char('p'), KeyModifiers::CONTROL)
+                                    | (KeyCode::Char('k'), KeyModifiers::CONTROL) => {
+                                        finder.move_up();
+                                    }
+
+                                    (KeyCode::Down, KeyModifiers::NONE)
+                                    | (KeyCode::Char('n'), KeyModifiers::CONTROL)
+                                    | (KeyCode::Char('j'), KeyModifiers::CONTROL) => {
+                                        finder.move_down();
+                                    }
+
+                                    (KeyCode::Backspace, KeyModifiers::NONE)
+                                    | (KeyCode::Char('h'), KeyModifiers::CONTROL) => {
+                                        if current_char_pos == 0 {
+                                            continue;
+                                        };
+                                        let removed = query.remove(current_char_pos - 1);
+                                        if let Some(to_be_removed) =
+                                            unicode_width::UnicodeWidthChar::width(removed)
+                                        {
+                                            current_char_pos -= 1;
+                                            current_pos -= to_be_removed as u16;
+                                        }
+                                        finder.update(&query.iter().collect::<String>());
+                                    }
+
+                                    //Jump to the selected item.
+                                    (KeyCode::Enter, KeyModifiers::NONE) => {
+                                        hide_cursor();
+                                        match finder.selected_path() {
+                                            Some(target) => {
+                                                if let Err(e) = state.jump_to_item(&target) {
+                                                    state.redraw(state.layout.y);
+                                                    print_warning(e, state.layout.y);
+                                                }
+                                            }
+                                            None => state.redraw(state.layout.y),
+                                        }
+                                        break;
+                                    }
+
+                                    //Open the selected item: cd into the directory, or open the file.
+                                    (KeyCode::Char('o'), KeyModifiers::CONTROL) => {
+                                        hide_cursor();
+                                        let target = match finder.selected_path() {
+                                            Some(target) => target,
+                                            None => {
+                                                state.redraw(state.layout.y);
+                                                break;
+                                            }
+                                        };
+                                        if target.is_dir() {
+                                            if let Err(e) = state.chdir(&target, Move::Jump) {
+                                                state.redraw(state.layout.y);
+                                                print_warning(e, state.layout.y);
+                                            }
+                                            break;
+                                        }
+                                        if let Err(e) = state.jump_to_item(&target) {
+                                            state.redraw(state.layout.y);
+                                            print_warning(e, state.layout.y);
+                                            break;
+                                        }
+                                        if let Ok(item) = state.get_item() {
+                                            let mut err: Option<FxError> = None;
                                             execute!(screen, EnterAlternateScreen)?;
                                             if let Err(e) = state.open_file(item) {
                                                 err = Some(e);
@@ -670,1896 +1415,2151 @@ fn _run(mut state: State, session_path: PathBuf) -> Result<(), FxError> {
                                             if let Some(e) = err {
                                                 print_warning(e, state.layout.y);
                                             }
-                                            continue;
                                         }
-                                        FileType::Symlink => match &item.symlink_dir_path {
-                                            Some(true_path) => {
-                                                if true_path.exists() {
-                                                    dest = Some(true_path.to_path_buf());
-                                                } else {
-                                                    print_warning("Broken link.", state.layout.y);
-                                                    continue;
-                                                }
-                                            }
-                                            None => {
-                                                execute!(screen, EnterAlternateScreen)?;
-                                                if let Err(e) = state.open_file(item) {
-                                                    err = Some(e);
-                                                }
-                                                execute!(screen, EnterAlternateScreen)?;
-                                                hide_cursor();
-                                                state.reload(state.layout.y)?;
-                                                if let Some(e) = err {
-                                                    print_warning(e, state.layout.y);
-                                                }
-                                                continue;
-                                            }
-                                        },
-                                        FileType::Directory => {
-                                            if item.file_path.exists() {
-                                                dest = Some(item.file_path.clone());
-                                            } else {
-                                                print_warning("Invalid directory.", state.layout.y);
+                                        break;
+                                    }
+
+                                    (KeyCode::Char(c), _) => {
+                                        if let Some(to_be_added) =
+                                            unicode_width::UnicodeWidthChar::width(c)
+                                        {
+                                            if current_pos + to_be_added as u16
+                                                > state.layout.terminal_column
+                                            {
                                                 continue;
                                             }
+                                            query.insert(current_char_pos, c);
+                                            current_char_pos += 1;
+                                            current_pos += to_be_added as u16;
                                         }
+                                        finder.update(&query.iter().collect::<String>());
                                     }
+
+                                    _ => continue,
                                 }
-                                if let Some(dest) = dest {
-                                    if let Err(e) = state.chdir(&dest, Move::Down) {
-                                        print_warning(e, state.layout.y);
-                                    }
-                                }
+                                finder
+                                    .print(state.layout.terminal_row, state.layout.terminal_column);
+                                go_to_info_line_and_reset();
+                                print!("{}{}", PROMPT_FIND, query.iter().collect::<String>());
+                                move_to(current_pos, 2);
+                                screen.flush()?;
                             }
+                        }
+                    }
 
-                            //Open a file in a new window
-                            //This works only if i) [exec] is set in config file
-                            //and ii) the extension of the item matches the key.
-                            //If not, warning message appears.
-                            KeyCode::Char('o') => {
-                                //In visual mode, this is disabled.
-                                if state.v_start.is_some() {
-                                    continue;
-                                }
-                                if let Ok(item) = state.get_item() {
-                                    match item.file_type {
-                                        FileType::File => {
-                                            if let Err(e) = state.open_file_in_new_window() {
-                                                hide_cursor();
-                                                state.redraw(state.layout.y);
-                                                print_warning(e, state.layout.y);
+                    //insert mode
+                    Action::NewFile | Action::NewDir => {
+                        //In visual mode, this is disabled.
+                        if state.v_start.is_some() {
+                            continue;
+                        }
+                        let is_dir = action == Action::NewDir;
+                        delete_pointer();
+                        go_to_info_line_and_reset();
+                        if is_dir {
+                            print!("{}", PROMPT_INSERT_DIR);
+                        } else {
+                            print!("{}", PROMPT_INSERT_FILE);
+                        }
+                        show_cursor();
+                        screen.flush()?;
+
+                        let mut new_name: Vec<char> = Vec::new();
+
+                        // express position in terminal
+                        let (mut current_pos, _) = cursor_pos()?;
+                        // express position in Vec<Char>
+                        let mut current_char_pos = 0;
+                        'insert: loop {
+                            if let Event::Key(KeyEvent {
+                                code,
+                                modifiers,
+                                kind: KeyEventKind::Press,
+                                ..
+                            }) = event::read()?
+                            {
+                                match (code, modifiers) {
+                                    // <C-r> to put the item name(s) from register
+                                    (KeyCode::Char('r'), KeyModifiers::CONTROL) => {
+                                        if let Event::Key(KeyEvent {
+                                            code,
+                                            kind: KeyEventKind::Press,
+                                            ..
+                                        }) = event::read()?
+                                        {
+                                            if let Some(reg) = state.registers.check_reg(&code) {
+                                                if !reg.is_empty() {
+                                                    let to_be_inserted = reg
+                                                        .iter()
+                                                        .map(|x| x.file_name.clone())
+                                                        .collect::<Vec<String>>()
+                                                        .join(" ");
+                                                    for c in to_be_inserted.chars() {
+                                                        if let Some(to_be_added) =
+                                                            unicode_width::UnicodeWidthChar::width(
+                                                                c,
+                                                            )
+                                                        {
+                                                            if current_pos + to_be_added as u16
+                                                                > state.layout.terminal_column
+                                                            {
+                                                                continue;
+                                                            }
+                                                            new_name.insert(current_char_pos, c);
+                                                            current_char_pos += 1;
+                                                            current_pos += to_be_added as u16;
+                                                        }
+                                                    }
+                                                    go_to_info_line_and_reset();
+                                                    if is_dir {
+                                                        print!(
+                                                            "{}{}",
+                                                            PROMPT_INSERT_DIR,
+                                                            &new_name.iter().collect::<String>(),
+                                                        );
+                                                    } else {
+                                                        print!(
+                                                            "{}{}",
+                                                            PROMPT_INSERT_FILE,
+                                                            &new_name.iter().collect::<String>(),
+                                                        );
+                                                    }
+                                                    move_to(current_pos + 1, 2);
+                                                    screen.flush()?;
+                                                    continue;
+                                                } else {
+                                                    continue;
+                                                }
+                                            } else {
                                                 continue;
                                             }
-                                            hide_cursor();
-                                            state.redraw(state.layout.y);
-                                            continue;
-                                        }
-                                        _ => {
-                                            continue;
                                         }
                                     }
-                                }
-                            }
 
-                            //Go to the parent directory if exists
-                            KeyCode::Char('h') | KeyCode::Left => {
-                                //In visual mode, this is disabled.
-                                if state.v_start.is_some() {
-                                    continue;
-                                }
-                                let pre = state.current_dir.clone();
-
-                                match pre.parent() {
-                                    Some(parent_p) => {
-                                        if let Err(e) = state.chdir(parent_p, Move::Up) {
-                                            print_warning(e, state.layout.y);
-                                        }
-                                    }
-                                    None => {
-                                        continue;
+                                    (KeyCode::Esc, KeyModifiers::NONE) => {
+                                        state.escape();
+                                        break 'insert;
                                     }
-                                }
-                            }
 
-                            // jump forward
-                            KeyCode::Tab => {
-                                if let Some(path_to_jump_to) = state.jumplist.get_forward() {
-                                    if path_to_jump_to.exists() {
-                                        state.chdir(&path_to_jump_to, Move::List)?;
-                                    } else {
-                                        print_warning(
-                                            "Directory forward not found: Removed from jumplist.",
-                                            state.layout.y,
-                                        );
-                                        state.jumplist.remove_forward();
+                                    (KeyCode::Left, KeyModifiers::NONE) => move_left_command_line(
+                                        &mut new_name,
+                                        &mut current_char_pos,
+                                        &mut current_pos,
+                                    ),
+
+                                    (KeyCode::Right, KeyModifiers::NONE) => {
+                                        move_right_command_line(
+                                            &mut new_name,
+                                            &mut current_char_pos,
+                                            &mut current_pos,
+                                        )
                                     }
-                                    state.jumplist.pos_forward();
-                                }
-                            }
 
-                            //Unpack archive file. Fails if it is not any of supported types
-                            KeyCode::Char('e') => {
-                                //In visual mode, this is disabled.
-                                //TODO! Enable this in visual mode.
-                                if state.v_start.is_some() {
-                                    continue;
+                                    (KeyCode::Backspace, KeyModifiers::NONE)
+                                    | (KeyCode::Char('h'), KeyModifiers::CONTROL) => {
+                                        if current_char_pos == 0 {
+                                            continue;
+                                        };
+                                        let removed = new_name.remove(current_char_pos - 1);
+                                        if let Some(to_be_removed) =
+                                            unicode_width::UnicodeWidthChar::width(removed)
+                                        {
+                                            current_char_pos -= 1;
+                                            current_pos -= to_be_removed as u16;
+
+                                            go_to_info_line_and_reset();
+                                            if is_dir {
+                                                print!(
+                                                    "{}{}",
+                                                    PROMPT_INSERT_DIR,
+                                                    &new_name.iter().collect::<String>(),
+                                                );
+                                            } else {
+                                                print!(
+                                                    "{}{}",
+                                                    PROMPT_INSERT_FILE,
+                                                    &new_name.iter().collect::<String>(),
+                                                );
+                                            }
+                                            move_to(current_pos + 1, 2);
+                                        }
+                                    }
+
+                                    (KeyCode::Char(c), _) => {
+                                        if let Some(to_be_added) =
+                                            unicode_width::UnicodeWidthChar::width(c)
+                                        {
+                                            if current_pos + to_be_added as u16
+                                                > state.layout.terminal_column
+                                            {
+                                                continue;
+                                            }
+                                            new_name.insert(current_char_pos, c);
+                                            current_char_pos += 1;
+                                            current_pos += to_be_added as u16;
+
+                                            go_to_info_line_and_reset();
+                                            if is_dir {
+                                                print!(
+                                                    "{}{}",
+                                                    PROMPT_INSERT_DIR,
+                                                    &new_name.iter().collect::<String>(),
+                                                );
+                                            } else {
+                                                print!(
+                                                    "{}{}",
+                                                    PROMPT_INSERT_FILE,
+                                                    &new_name.iter().collect::<String>(),
+                                                );
+                                            }
+                                            move_to(current_pos + 1, 2);
+                                        }
+                                    }
+
+                                    (KeyCode::Enter, KeyModifiers::NONE) => {
+                                        hide_cursor();
+                                        //Set the command and argument(s).
+                                        let new_name: String = new_name.iter().collect();
+                                        if let Err(e) = state.create_item(&new_name, is_dir) {
+                                            print_warning(e, state.layout.y);
+                                            break 'insert;
+                                        }
+                                        state.last_change = Some(if is_dir {
+                                            Change::NewDir(new_name)
+                                        } else {
+                                            Change::NewFile(new_name)
+                                        });
+                                        state.reload(state.layout.y)?;
+                                        break 'insert;
+                                    }
+
+                                    _ => continue,
                                 }
-                                print_info("Unpacking...", state.layout.y);
                                 screen.flush()?;
-                                let start = Instant::now();
-                                if let Err(e) = state.unpack() {
-                                    state.reload(state.layout.y)?;
-                                    print_warning(e, state.layout.y);
-                                    continue;
-                                }
-                                let duration = duration_to_string(start.elapsed());
-                                state.reload(state.layout.y)?;
-                                print_info(format!("Unpacked. [{}]", duration), state.layout.y);
                             }
+                        }
+                    }
 
-                            //Jumps to the directory that matches the keyword (zoxide required)
-                            KeyCode::Char('z') => {
-                                //If zoxide is not found, show error message.
-                                if !state.has_zoxide {
-                                    print_warning("zoxide not found.", state.layout.y);
-                                    continue;
-                                }
-                                //In visual mode, this is disabled.
-                                if state.v_start.is_some() {
-                                    continue;
-                                }
-                                delete_pointer();
-                                go_to_info_line_and_reset();
-                                print!("z");
-                                show_cursor();
+                    //switch to linewise visual mode
+                    Action::Visual => {
+                        //If in visual mode, return to normal mode.
+                        if state.v_start.is_some() {
+                            state.reset_selection();
+                            state.redraw(state.layout.y);
+                            continue;
+                        }
+                        if len == 0 {
+                            continue;
+                        }
+                        let item = state.get_item_mut()?;
+                        item.selected = true;
+                        state.redraw(state.layout.y);
+                        state.v_start = Some(state.layout.nums.index);
+                        continue;
+                    }
 
-                                let mut command: Vec<char> = vec!['z'];
-                                screen.flush()?;
+                    //Toggle the selection of the item and go down.
+                    //The selection is kept across directories.
+                    Action::ToggleSelect => {
+                        //In visual mode, this is disabled.
+                        if state.v_start.is_some() || len == 0 {
+                            continue;
+                        }
+                        state.toggle_select()?;
+                        if state.layout.nums.index == len - 1 {
+                            state.redraw(state.layout.y);
+                        } else if state.layout.y >= state.layout.terminal_row - 1 - SCROLL_POINT
+                            && len > (state.layout.terminal_row - BEGINNING_ROW) as usize - 1
+                        {
+                            state.layout.nums.go_down();
+                            state.layout.nums.inc_skip();
+                            state.redraw(state.layout.y);
+                        } else {
+                            state.layout.nums.go_down();
+                            state.redraw(state.layout.y + 1);
+                        }
+                    }
 
-                                let mut current_pos = 3;
-                                'zoxide: loop {
-                                    if let Event::Key(KeyEvent {
-                                        code,
-                                        modifiers,
-                                        kind: KeyEventKind::Press,
-                                        ..
-                                    }) = event::read()?
-                                    {
-                                        match (code, modifiers) {
-                                            (KeyCode::Esc, KeyModifiers::NONE) => {
-                                                state.escape();
-                                                break 'zoxide;
-                                            }
+                    //Select all the items in the list
+                    Action::SelectAll => {
+                        //In visual mode, this is disabled.
+                        if state.v_start.is_some() {
+                            continue;
+                        }
+                        let count = state.select_by_glob(&[], true)?;
+                        state.redraw(state.layout.y);
+                        print_info(format!("{} items selected.", count), state.layout.y);
+                    }
 
-                                            (KeyCode::Left, KeyModifiers::NONE) => {
-                                                if current_pos == INITIAL_POS_Z {
-                                                    continue;
-                                                };
-                                                current_pos -= 1;
-                                                move_left(1);
-                                            }
+                    //Toggle sortkey
+                    Action::ToggleSort => {
+                        //In visual mode, this is disabled.
+                        if state.v_start.is_some() {
+                            continue;
+                        }
+                        state.toggle_option(RuntimeOption::SortBy)?;
+                    }
 
-                                            (KeyCode::Right, KeyModifiers::NONE) => {
-                                                if current_pos as usize
-                                                    == command.len() + INITIAL_POS_Z as usize
-                                                {
-                                                    continue;
-                                                };
-                                                current_pos += 1;
-                                                move_right(1);
-                                            }
+                    //Show or hide hidden items
+                    Action::ToggleHidden => {
+                        //In visual mode, this is disabled.
+                        if state.v_start.is_some() {
+                            continue;
+                        }
+                        state.toggle_option(RuntimeOption::ShowHidden)?;
+                    }
 
-                                            (KeyCode::Backspace, KeyModifiers::NONE)
-                                            | (KeyCode::Char('h'), KeyModifiers::CONTROL) => {
-                                                if current_pos == INITIAL_POS_Z + 1 {
-                                                    state.escape();
-                                                    break 'zoxide;
-                                                };
-                                                command.remove(
-                                                    (current_pos - INITIAL_POS_Z - 1).into(),
-                                                );
-                                                current_pos -= 1;
+                    //Toggle whether to show preview. Also hide registers and marks.
+                    Action::TogglePreview => {
+                        if state.layout.has_side() {
+                            state.layout.reset_side();
+                        } else {
+                            state.layout.show_preview();
+                        }
+                        let (new_column, new_row) = state.layout.update_column_and_row()?;
+                        state.refresh(new_column, new_row, state.layout.y)?;
+                    }
 
-                                                clear_current_line();
-                                                to_info_line();
-                                                print!("{}", &command.iter().collect::<String>(),);
-                                                move_to(current_pos, 2);
-                                            }
+                    //Toggle vertical <-> horizontal split
+                    Action::ToggleSplit => {
+                        state.toggle_option(RuntimeOption::Split)?;
+                    }
 
-                                            (KeyCode::Enter, KeyModifiers::NONE) => {
-                                                hide_cursor();
-                                                let command = command.iter().collect::<String>();
-                                                let commands = command
-                                                    .split_whitespace()
-                                                    .collect::<Vec<&str>>();
-                                                if commands.len() == 1 {
-                                                    //go to the home directory
-                                                    let home_dir =
-                                                        dirs::home_dir().ok_or_else(|| {
-                                                            FxError::Dirs(
-                                                                "Cannot read home dir.".to_string(),
-                                                            )
-                                                        })?;
-                                                    if let Err(e) =
-                                                        state.chdir(&home_dir, Move::Jump)
-                                                    {
-                                                        print_warning(e, state.layout.y);
-                                                    }
-                                                    break 'zoxide;
-                                                } else if let Ok(output) =
-                                                    std::process::Command::new("zoxide")
-                                                        .arg("query")
-                                                        .args(&commands[1..])
-                                                        .output()
-                                                {
-                                                    let output = output.stdout;
-                                                    if output.is_empty() {
-                                                        print_warning(
-                                                            "Keyword does not match the database.",
-                                                            state.layout.y,
-                                                        );
-                                                        break 'zoxide;
-                                                    } else {
-                                                        let target_dir =
-                                                            std::str::from_utf8(&output);
-                                                        match target_dir {
-                                                            Err(e) => {
-                                                                print_warning(e, state.layout.y);
-                                                                break 'zoxide;
-                                                            }
-                                                            Ok(target_dir) => {
-                                                                hide_cursor();
-                                                                state.layout.nums.reset();
-                                                                let target_path = PathBuf::from(
-                                                                    target_dir.trim(),
-                                                                );
-                                                                if let Err(e) = state
-                                                                    .chdir(&target_path, Move::Jump)
-                                                                {
-                                                                    print_warning(
-                                                                        e,
-                                                                        state.layout.y,
-                                                                    );
-                                                                }
-                                                                break 'zoxide;
-                                                            }
-                                                        }
-                                                    }
-                                                } else {
-                                                    print_warning(
-                                                        "Failed to execute zoxide",
-                                                        state.layout.y,
-                                                    );
-                                                    break 'zoxide;
-                                                }
-                                            }
+                    //delete
+                    Action::Delete => {
+                        //If read-only, deleting is disabled.
+                        if state.is_ro {
+                            print_warning("Cannot delete item in this directory.", state.layout.y);
+                            continue;
+                        }
+                        if let Some(_start_pos) = state.v_start {
+                            //visual mode
+                            let count = state.list.iter().filter(|item| item.selected).count();
+                            if let Err(e) = state.delete_in_visual(None, false, &mut screen) {
+                                state.reset_selection();
+                                state.redraw(state.layout.y);
+                                print_warning(e, state.layout.y);
+                                continue;
+                            }
+                            state.last_change = Some(Change::Delete {
+                                count,
+                                reg: None,
+                                append: false,
+                            });
+                        } else if !state.selection.is_empty() {
+                            //items selected by <Space>
+                            if let Err(e) = state.delete_selection(None, false, &mut screen) {
+                                state.reload(state.layout.y)?;
+                                print_warning(e, state.layout.y);
+                                continue;
+                            }
+                        } else {
+                            //normal mode
+                            if len == 0 {
+                                continue;
+                            }
+                            let count = count.unwrap_or(1);
+                            if let Err(e) = state.delete_count(count, None, false, &mut screen) {
+                                state.reset_selection();
+                                state.redraw(state.layout.y);
+                                print_warning(e, state.layout.y);
+                                continue;
+                            }
+                            state.last_change = Some(Change::Delete {
+                                count,
+                                reg: None,
+                                append: false,
+                            });
+                        }
+                    }
 
-                                            (KeyCode::Char(c), _) => {
-                                                command.insert(
-                                                    (current_pos - INITIAL_POS_Z).into(),
-                                                    c,
-                                                );
-                                                current_pos += 1;
-                                                clear_current_line();
-                                                to_info_line();
-                                                print!("{}", &command.iter().collect::<String>(),);
-                                                move_to(current_pos, 2);
-                                            }
+                    //yank
+                    Action::Yank => {
+                        if let Some(_start_pos) = state.v_start {
+                            //visual mode
+                            let items: Vec<ItemBuffer> = state
+                                .list
+                                .iter()
+                                .filter(|item| item.selected)
+                                .map(ItemBuffer::new)
+                                .collect();
+                            let item_len = state.registers.yank_item(&items, None, false);
+                            state.reset_selection();
+                            state.list_up();
+                            let mut yank_message: String = item_len.to_string();
+                            yank_message.push_str(" items yanked");
+                            print_info(yank_message, state.layout.y);
+                        } else if !state.selection.is_empty() {
+                            //items selected by <Space>
+                            let item_len = state.yank_selection(None, false);
+                            state.redraw(state.layout.y);
+                            print_info(format!("{} items yanked", item_len), state.layout.y);
+                        } else {
+                            //normal mode
+                            if let Some(count) = count {
+                                let item_len = state.yank_count(count, None, false);
+                                state.escape();
+                                print_info(format!("{} items yanked", item_len), state.layout.y);
+                            } else if let Ok(item) = state.get_item() {
+                                state
+                                    .registers
+                                    .yank_item(&[ItemBuffer::new(item)], None, false);
+                                state.escape();
+                                print_info("1 item yanked.", state.layout.y);
+                            }
+                        }
+                    }
 
-                                            _ => continue,
-                                        }
-                                        screen.flush()?;
-                                    }
+                    //Copy paths to the system clipboard.
+                    Action::CopyPath | Action::CopyName | Action::CopyDir => {
+                        let text = state.clipboard_text(&action);
+                        if state.v_start.is_some() {
+                            state.reset_selection();
+                            state.list_up();
+                        }
+                        if text.is_empty() {
+                            state.escape();
+                            continue;
+                        }
+                        match copy_to_clipboard(&text, state.clipboard_command.as_deref()) {
+                            Ok(_) => {
+                                let lines = text.lines().count();
+                                if lines == 1 {
+                                    print_info(format!("Copied: {}", text), state.layout.y);
+                                } else {
+                                    print_info(format!("{} paths copied.", lines), state.layout.y);
                                 }
                             }
+                            Err(e) => print_warning(e, state.layout.y),
+                        }
+                    }
 
-                            //insert mode
-                            KeyCode::Char('i') | KeyCode::Char('I') => {
-                                //In visual mode, this is disabled.
-                                if state.v_start.is_some() {
-                                    continue;
+                    //put
+                    Action::Put => {
+                        //In visual mode, this is disabled.
+                        if state.v_start.is_some() {
+                            continue;
+                        }
+                        if let Err(e) = state.put(state.registers.unnamed.clone(), &mut screen) {
+                            print_warning(e, state.layout.y);
+                            continue;
+                        }
+                        state.last_change = Some(Change::Put('"'));
+                    }
+
+                    //cut
+                    Action::Cut => {
+                        let item_len = state.cut();
+                        if state.v_start.is_some() {
+                            state.reset_selection();
+                            state.list_up();
+                        }
+                        if item_len == 0 {
+                            continue;
+                        }
+                        print_info(
+                            format!("{} items cut. Press P to move them here.", item_len),
+                            state.layout.y,
+                        );
+                    }
+
+                    //move cut items to the current directory
+                    Action::MoveHere => {
+                        //In visual mode, this is disabled.
+                        if state.v_start.is_some() {
+                            continue;
+                        }
+                        if state.cut.is_empty() {
+                            print_warning("No item cut.", state.layout.y);
+                            continue;
+                        }
+                        let result = state.move_cut_items();
+                        state.reload(state.layout.y)?;
+                        match result {
+                            Ok(item_len) => {
+                                print_info(format!("{} items moved.", item_len), state.layout.y)
+                            }
+                            Err(e) => print_warning(e, state.layout.y),
+                        }
+                    }
+
+                    //rename
+                    Action::Rename => {
+                        //In visual mode, or with items selected by <Space>,
+                        //you can rename multiple items in default editor.
+                        if state.v_start.is_some() || !state.selection.is_empty() {
+                            let items: Vec<ItemBuffer> = if state.v_start.is_some() {
+                                state
+                                    .list
+                                    .iter()
+                                    .filter(|item| item.selected)
+                                    .map(ItemBuffer::new)
+                                    .collect()
+                            } else {
+                                state.selection_buffers()
+                            };
+                            execute!(screen, EnterAlternateScreen)?;
+                            let result = state.rename_multiple_items(&items);
+                            execute!(screen, EnterAlternateScreen)?;
+                            hide_cursor();
+                            state.reset_selection();
+                            state.selection.clear();
+                            state.reload(state.layout.y)?;
+                            match result {
+                                Err(e) => {
+                                    print_warning(e, state.layout.y);
                                 }
-                                let is_dir = code == KeyCode::Char('I');
-                                delete_pointer();
-                                go_to_info_line_and_reset();
-                                if is_dir {
-                                    print!("{}", PROMPT_INSERT_DIR);
-                                } else {
-                                    print!("{}", PROMPT_INSERT_FILE);
+                                Ok(result_len) => {
+                                    let message = {
+                                        match result_len {
+                                            0 => "No item renamed.".to_owned(),
+                                            1 => "1 item renamed.".to_owned(),
+                                            count => {
+                                                format!("{} items renamed.", count)
+                                            }
+                                        }
+                                    };
+                                    print_info(message, state.layout.y);
                                 }
-                                show_cursor();
-                                screen.flush()?;
-
-                                let mut new_name: Vec<char> = Vec::new();
-
-                                // express position in terminal
-                                let (mut current_pos, _) = cursor_pos()?;
-                                // express position in Vec<Char>
-                                let mut current_char_pos = 0;
-                                'insert: loop {
-                                    if let Event::Key(KeyEvent {
-                                        code,
-                                        modifiers,
-                                        kind: KeyEventKind::Press,
-                                        ..
-                                    }) = event::read()?
-                                    {
-                                        match (code, modifiers) {
-                                            // <C-r> to put the item name(s) from register
-                                            (KeyCode::Char('r'), KeyModifiers::CONTROL) => {
-                                                if let Event::Key(KeyEvent {
-                                                    code,
-                                                    kind: KeyEventKind::Press,
-                                                    ..
-                                                }) = event::read()?
-                                                {
-                                                    if let Some(reg) =
-                                                        state.registers.check_reg(&code)
-                                                    {
-                                                        if !reg.is_empty() {
-                                                            let to_be_inserted = reg
-                                                                .iter()
-                                                                .map(|x| x.file_name.clone())
-                                                                .collect::<Vec<String>>()
-                                                                .join(" ");
-                                                            for c in to_be_inserted.chars() {
-                                                                if let Some(to_be_added) =
-                                                                    unicode_width::UnicodeWidthChar::width(c)
-                                                                {
-                                                                    if current_pos + to_be_added as u16
-                                                                        > state.layout.terminal_column
-                                                                    {
-                                                                        continue;
-                                                                    }
-                                                                    new_name.insert(current_char_pos, c);
-                                                                    current_char_pos += 1;
-                                                                    current_pos += to_be_added as u16;
-                                                                }
-                                                            }
-                                                            go_to_info_line_and_reset();
-                                                            if is_dir {
-                                                                print!(
-                                                                    "{}{}",
-                                                                    PROMPT_INSERT_DIR,
-                                                                    &new_name
-                                                                        .iter()
-                                                                        .collect::<String>(),
-                                                                );
-                                                            } else {
-                                                                print!(
-                                                                    "{}{}",
-                                                                    PROMPT_INSERT_FILE,
-                                                                    &new_name
-                                                                        .iter()
-                                                                        .collect::<String>(),
-                                                                );
+                            }
+                            continue;
+                        }
+                        if len == 0 {
+                            continue;
+                        }
+                        let item = state.get_item()?.clone();
+                        show_cursor();
+                        let mut rename = item.file_name.chars().collect::<Vec<char>>();
+                        to_info_line();
+                        clear_current_line();
+                        print!("{}{}", PROMPT_RENAME, &rename.iter().collect::<String>(),);
+                        screen.flush()?;
+
+                        let (mut current_pos, _) = cursor_pos()?;
+                        let mut current_char_pos = rename.len();
+                        loop {
+                            if let Event::Key(KeyEvent {
+                                code,
+                                modifiers,
+                                kind: KeyEventKind::Press,
+                                ..
+                            }) = event::read()?
+                            {
+                                match (code, modifiers) {
+                                    // <C-r> to put the item name(s) from register
+                                    (KeyCode::Char('r'), KeyModifiers::CONTROL) => {
+                                        if let Event::Key(KeyEvent {
+                                            code,
+                                            kind: KeyEventKind::Press,
+                                            ..
+                                        }) = event::read()?
+                                        {
+                                            if let Some(reg) = state.registers.check_reg(&code) {
+                                                if !reg.is_empty() {
+                                                    let to_be_inserted = reg
+                                                        .iter()
+                                                        .map(|x| x.file_name.clone())
+                                                        .collect::<Vec<String>>()
+                                                        .join(" ");
+                                                    for c in to_be_inserted.chars() {
+                                                        if let Some(to_be_added) =
+                                                            unicode_width::UnicodeWidthChar::width(
+                                                                c,
+                                                            )
+                                                        {
+                                                            if current_pos + to_be_added as u16
+                                                                > state.layout.terminal_column
+                                                            {
+                                                                continue;
                                                             }
-                                                            move_to(current_pos + 1, 2);
-                                                            screen.flush()?;
-                                                            continue;
-                                                        } else {
-                                                            continue;
+                                                            rename.insert(current_char_pos, c);
+                                                            current_char_pos += 1;
+                                                            current_pos += to_be_added as u16;
                                                         }
-                                                    } else {
-                                                        continue;
                                                     }
+                                                    go_to_info_line_and_reset();
+                                                    print!(
+                                                        "{}{}",
+                                                        PROMPT_RENAME,
+                                                        &rename.iter().collect::<String>()
+                                                    );
+                                                    move_to(current_pos + 1, 2);
+                                                    screen.flush()?;
+                                                    continue;
+                                                } else {
+                                                    continue;
                                                 }
+                                            } else {
+                                                continue;
                                             }
+                                        }
+                                    }
 
-                                            (KeyCode::Esc, KeyModifiers::NONE) => {
-                                                state.escape();
-                                                break 'insert;
-                                            }
+                                    (KeyCode::Esc, KeyModifiers::NONE) => {
+                                        state.escape();
+                                        break;
+                                    }
 
-                                            (KeyCode::Left, KeyModifiers::NONE) => {
-                                                move_left_command_line(
-                                                    &mut new_name,
-                                                    &mut current_char_pos,
-                                                    &mut current_pos,
-                                                )
-                                            }
+                                    (KeyCode::Left, KeyModifiers::NONE) => move_left_command_line(
+                                        &mut rename,
+                                        &mut current_char_pos,
+                                        &mut current_pos,
+                                    ),
+
+                                    (KeyCode::Right, KeyModifiers::NONE) => {
+                                        move_right_command_line(
+                                            &mut rename,
+                                            &mut current_char_pos,
+                                            &mut current_pos,
+                                        )
+                                    }
 
-                                            (KeyCode::Right, KeyModifiers::NONE) => {
-                                                move_right_command_line(
-                                                    &mut new_name,
-                                                    &mut current_char_pos,
-                                                    &mut current_pos,
-                                                )
-                                            }
+                                    (KeyCode::Backspace, KeyModifiers::NONE)
+                                    | (KeyCode::Char('h'), KeyModifiers::CONTROL) => {
+                                        if current_char_pos == 0 {
+                                            continue;
+                                        };
+                                        let removed = rename.remove(current_char_pos - 1);
+                                        if let Some(to_be_removed) =
+                                            unicode_width::UnicodeWidthChar::width(removed)
+                                        {
+                                            current_char_pos -= 1;
+                                            current_pos -= to_be_removed as u16;
+
+                                            go_to_info_line_and_reset();
+                                            print!(
+                                                "{}{}",
+                                                PROMPT_RENAME,
+                                                &rename.iter().collect::<String>(),
+                                            );
+                                            move_to(current_pos + 1, 2);
+                                        }
+                                    }
 
-                                            (KeyCode::Backspace, KeyModifiers::NONE)
-                                            | (KeyCode::Char('h'), KeyModifiers::CONTROL) => {
-                                                if current_char_pos == 0 {
-                                                    continue;
-                                                };
-                                                let removed = new_name.remove(current_char_pos - 1);
-                                                if let Some(to_be_removed) =
-                                                    unicode_width::UnicodeWidthChar::width(removed)
-                                                {
-                                                    current_char_pos -= 1;
-                                                    current_pos -= to_be_removed as u16;
+                                    (KeyCode::Char(c), _) => {
+                                        if let Some(to_be_added) =
+                                            unicode_width::UnicodeWidthChar::width(c)
+                                        {
+                                            rename.insert(current_char_pos, c);
+                                            current_char_pos += 1;
+                                            current_pos += to_be_added as u16;
+
+                                            go_to_info_line_and_reset();
+                                            print!(
+                                                "{}{}",
+                                                PROMPT_RENAME,
+                                                &rename.iter().collect::<String>(),
+                                            );
+                                            move_to(current_pos + 1, 2);
+                                        }
+                                    }
 
-                                                    go_to_info_line_and_reset();
-                                                    if is_dir {
-                                                        print!(
-                                                            "{}{}",
-                                                            PROMPT_INSERT_DIR,
-                                                            &new_name.iter().collect::<String>(),
-                                                        );
-                                                    } else {
-                                                        print!(
-                                                            "{}{}",
-                                                            PROMPT_INSERT_FILE,
-                                                            &new_name.iter().collect::<String>(),
-                                                        );
-                                                    }
-                                                    move_to(current_pos + 1, 2);
-                                                }
-                                            }
+                                    (KeyCode::Enter, KeyModifiers::NONE) => {
+                                        let rename = rename.iter().collect::<String>();
+                                        if let Err(e) = state.rename_item(&item.file_path, &rename)
+                                        {
+                                            hide_cursor();
+                                            print_warning(e, state.layout.y);
+                                            break;
+                                        }
+                                        if rename != item.file_name {
+                                            state.last_change = Some(Change::Rename {
+                                                from: item.file_name.clone(),
+                                                to: rename,
+                                            });
+                                        }
 
-                                            (KeyCode::Char(c), _) => {
-                                                if let Some(to_be_added) =
-                                                    unicode_width::UnicodeWidthChar::width(c)
-                                                {
-                                                    if current_pos + to_be_added as u16
-                                                        > state.layout.terminal_column
-                                                    {
-                                                        continue;
-                                                    }
-                                                    new_name.insert(current_char_pos, c);
-                                                    current_char_pos += 1;
-                                                    current_pos += to_be_added as u16;
+                                        hide_cursor();
+                                        state.reload(state.layout.y)?;
+                                        break;
+                                    }
 
-                                                    go_to_info_line_and_reset();
-                                                    if is_dir {
-                                                        print!(
-                                                            "{}{}",
-                                                            PROMPT_INSERT_DIR,
-                                                            &new_name.iter().collect::<String>(),
-                                                        );
-                                                    } else {
-                                                        print!(
-                                                            "{}{}",
-                                                            PROMPT_INSERT_FILE,
-                                                            &new_name.iter().collect::<String>(),
-                                                        );
-                                                    }
-                                                    move_to(current_pos + 1, 2);
-                                                }
-                                            }
+                                    _ => continue,
+                                }
+                                screen.flush()?;
+                            }
+                        }
+                    }
 
-                                            (KeyCode::Enter, KeyModifiers::NONE) => {
-                                                hide_cursor();
-                                                //Set the command and argument(s).
-                                                let new_name: String = new_name.iter().collect();
-                                                if is_dir {
-                                                    if let Err(e) = std::fs::create_dir(
-                                                        &state.current_dir.join(new_name),
-                                                    ) {
-                                                        print_warning(e, state.layout.y);
-                                                        break 'insert;
-                                                    }
-                                                } else if let Err(e) = std::fs::File::options()
-                                                    .read(true)
-                                                    .write(true)
-                                                    .create_new(true)
-                                                    .open(&state.current_dir.join(new_name))
-                                                {
-                                                    print_warning(e, state.layout.y);
-                                                    break 'insert;
-                                                }
-                                                state.reload(state.layout.y)?;
-                                                break 'insert;
-                                            }
+                    //Search mode
+                    Action::Search => {
+                        //In visual mode, this is disabled.
+                        //TODO! Enable this in visual mode.
+                        if state.v_start.is_some() {
+                            continue;
+                        }
+                        if len == 0 {
+                            continue;
+                        }
+                        delete_pointer();
+                        show_cursor();
+                        go_to_info_line_and_reset();
+                        print!("{}", PROMPT_SEARCH);
+                        screen.flush()?;
+
+                        let original_nums = state.layout.nums;
+                        let original_y = state.layout.y;
+                        let mut keyword: Vec<char> = Vec::new();
+
+                        // express position in terminal
+                        let mut current_pos = INITIAL_POS_COMMAND_LINE;
+                        // express position in Vec<Char>
+                        let mut current_char_pos = 0;
+                        let mut history_cursor = HistoryCursor::default();
+                        loop {
+                            if let Event::Key(KeyEvent {
+                                code,
+                                modifiers,
+                                kind: KeyEventKind::Press,
+                                ..
+                            }) = event::read()?
+                            {
+                                match (code, modifiers) {
+                                    (KeyCode::Esc, KeyModifiers::NONE) => {
+                                        hide_cursor();
+                                        state.redraw(state.layout.y);
+                                        break;
+                                    }
+
+                                    (KeyCode::Left, KeyModifiers::NONE) => {
+                                        move_left_command_line(
+                                            &mut keyword,
+                                            &mut current_char_pos,
+                                            &mut current_pos,
+                                        );
+                                    }
+
+                                    (KeyCode::Right, KeyModifiers::NONE) => {
+                                        move_right_command_line(
+                                            &mut keyword,
+                                            &mut current_char_pos,
+                                            &mut current_pos,
+                                        );
+                                    }
+
+                                    (KeyCode::Backspace, KeyModifiers::NONE)
+                                    | (KeyCode::Char('h'), KeyModifiers::CONTROL) => {
+                                        if current_char_pos == 0 {
+                                            continue;
+                                        };
+                                        let removed = keyword.remove(current_char_pos - 1);
+                                        if let Some(to_be_removed) =
+                                            unicode_width::UnicodeWidthChar::width(removed)
+                                        {
+                                            current_char_pos -= 1;
+                                            current_pos -= to_be_removed as u16;
+
+                                            history_cursor.reset();
+
+                                            let key = &keyword.iter().collect::<String>();
+                                            state.search_incrementally(
+                                                key,
+                                                original_nums,
+                                                original_y,
+                                            );
+                                            go_to_info_line_and_reset();
+                                            print!("{}{}", PROMPT_SEARCH, key);
+                                            move_to(current_pos, 2);
+                                        }
+                                    }
 
-                                            _ => continue,
+                                    (KeyCode::Up, KeyModifiers::NONE)
+                                    | (KeyCode::Char('p'), KeyModifiers::CONTROL) => {
+                                        let input = keyword.iter().collect::<String>();
+                                        if let Some(entry) = history_cursor.prev(
+                                            state.history.entries(HistoryKind::Search),
+                                            &input,
+                                        ) {
+                                            state.search_incrementally(
+                                                &entry,
+                                                original_nums,
+                                                original_y,
+                                            );
+                                            replace_command_line(
+                                                PROMPT_SEARCH,
+                                                &entry,
+                                                &mut keyword,
+                                                &mut current_char_pos,
+                                                &mut current_pos,
+                                            );
                                         }
-                                        screen.flush()?;
                                     }
-                                }
-                            }
 
-                            //switch to linewise visual mode
-                            KeyCode::Char('V') => {
-                                //If in visual mode, return to normal mode.
-                                if state.v_start.is_some() {
-                                    state.reset_selection();
-                                    state.redraw(state.layout.y);
-                                    continue;
-                                }
-                                if len == 0 {
-                                    continue;
-                                }
-                                let item = state.get_item_mut()?;
-                                item.selected = true;
-                                state.redraw(state.layout.y);
-                                state.v_start = Some(state.layout.nums.index);
-                                continue;
-                            }
+                                    (KeyCode::Down, KeyModifiers::NONE)
+                                    | (KeyCode::Char('n'), KeyModifiers::CONTROL) => {
+                                        if let Some(entry) = history_cursor
+                                            .next(state.history.entries(HistoryKind::Search))
+                                        {
+                                            state.search_incrementally(
+                                                &entry,
+                                                original_nums,
+                                                original_y,
+                                            );
+                                            replace_command_line(
+                                                PROMPT_SEARCH,
+                                                &entry,
+                                                &mut keyword,
+                                                &mut current_char_pos,
+                                                &mut current_pos,
+                                            );
+                                        }
+                                    }
 
-                            //Toggle sortkey
-                            KeyCode::Char('t') => {
-                                //In visual mode, this is disabled.
-                                if state.v_start.is_some() {
-                                    continue;
-                                }
-                                match state.layout.sort_by {
-                                    SortKey::Name => {
-                                        state.layout.sort_by = SortKey::Time;
+                                    (KeyCode::Char(c), _) => {
+                                        if let Some(to_be_added) =
+                                            unicode_width::UnicodeWidthChar::width(c)
+                                        {
+                                            if current_pos + to_be_added as u16
+                                                > state.layout.terminal_column
+                                            {
+                                                continue;
+                                            }
+                                            keyword.insert(current_char_pos, c);
+                                            current_char_pos += 1;
+                                            current_pos += to_be_added as u16;
+
+                                            history_cursor.reset();
+
+                                            let key = &keyword.iter().collect::<String>();
+                                            state.search_incrementally(
+                                                key,
+                                                original_nums,
+                                                original_y,
+                                            );
+                                            go_to_info_line_and_reset();
+                                            print!("{}{}", PROMPT_SEARCH, key);
+                                            move_to(current_pos, 2);
+                                        }
                                     }
-                                    SortKey::Time => {
-                                        state.layout.sort_by = SortKey::Name;
+
+                                    (KeyCode::Enter, KeyModifiers::NONE) => {
+                                        go_to_info_line_and_reset();
+                                        let keyword: String = keyword.iter().collect();
+                                        state.history.add(HistoryKind::Search, &keyword);
+                                        match state.matcher(&keyword) {
+                                            Ok(_) => {
+                                                state.keyword = Some(keyword);
+                                                state.move_cursor(state.layout.y);
+                                            }
+                                            Err(e) => {
+                                                //Clear the highlight.
+                                                state.highlight_matches(&keyword);
+                                                state.keyword = None;
+                                                state.redraw(state.layout.y);
+                                                print_warning(e, state.layout.y);
+                                            }
+                                        }
+                                        break;
                                     }
+
+                                    _ => continue,
                                 }
-                                state.layout.nums.reset();
-                                state.reorder(BEGINNING_ROW);
+                                screen.flush()?;
                             }
+                        }
+                        hide_cursor();
+                    }
 
-                            //Show or hide hidden items
-                            KeyCode::Backspace => {
-                                //In visual mode, this is disabled.
-                                if state.v_start.is_some() {
-                                    continue;
-                                }
-                                match state.layout.show_hidden {
-                                    true => {
-                                        state.list.retain(|x| !x.is_hidden);
-                                        state.layout.show_hidden = false;
-                                    }
-                                    false => {
-                                        state.layout.show_hidden = true;
+                    //Filter mode: hide items that do not contain the keyword
+                    Action::Filter => {
+                        //In visual mode, this is disabled.
+                        if state.v_start.is_some() {
+                            continue;
+                        }
+                        delete_pointer();
+                        show_cursor();
+
+                        let original_nums = state.layout.nums;
+                        let original_y = state.layout.y;
+                        //Start with the current filter, narrowing the whole list.
+                        let previous = state.filter.take();
+                        state.update_list()?;
+                        let original = state.list.clone();
+                        let mut keyword: Vec<char> =
+                            previous.clone().unwrap_or_default().chars().collect();
+
+                        // express position in terminal
+                        let mut current_pos = 2
+                            + PROMPT_FILTER.len() as u16
+                            + unicode_width::UnicodeWidthStr::width(
+                                keyword.iter().collect::<String>().as_str(),
+                            ) as u16;
+                        // express position in Vec<Char>
+                        let mut current_char_pos = keyword.len();
+                        if let Some(previous) = &previous {
+                            state.filter_incrementally(previous, &original);
+                        }
+                        go_to_info_line_and_reset();
+                        print!("{}{}", PROMPT_FILTER, keyword.iter().collect::<String>());
+                        screen.flush()?;
+
+                        loop {
+                            if let Event::Key(KeyEvent {
+                                code,
+                                modifiers,
+                                kind: KeyEventKind::Press,
+                                ..
+                            }) = event::read()?
+                            {
+                                match (code, modifiers) {
+                                    //Cancel, and restore the previous filter.
+                                    (KeyCode::Esc, KeyModifiers::NONE) => {
+                                        hide_cursor();
+                                        state.filter = previous;
                                         state.update_list()?;
+                                        state.layout.nums = original_nums;
+                                        state.layout.y = original_y;
+                                        if state.is_out_of_bounds() {
+                                            state.layout.nums.reset();
+                                            state.layout.y = BEGINNING_ROW;
+                                        }
+                                        state.redraw(state.layout.y);
+                                        break;
                                     }
-                                }
-                                state.layout.nums.reset();
-                                state.redraw(BEGINNING_ROW);
-                            }
-
-                            //Toggle whether to show preview. Also hide registers.
-                            KeyCode::Char('v') => {
-                                if state.layout.is_preview() || state.layout.is_reg() {
-                                    state.layout.reset_side();
-                                } else {
-                                    state.layout.show_preview();
-                                }
-                                let (new_column, new_row) = state.layout.update_column_and_row()?;
-                                state.refresh(new_column, new_row, state.layout.y)?;
-                            }
 
-                            //Toggle vertical <-> horizontal split
-                            KeyCode::Char('s') => match state.layout.split {
-                                Split::Vertical => {
-                                    state.layout.split = Split::Horizontal;
-                                    if state.layout.is_preview() || state.layout.is_reg() {
-                                        let (new_column, mut new_row) = terminal_size()?;
-                                        new_row /= 2;
-                                        state.refresh(new_column, new_row, state.layout.y)?;
+                                    (KeyCode::Left, KeyModifiers::NONE) => {
+                                        move_left_command_line(
+                                            &mut keyword,
+                                            &mut current_char_pos,
+                                            &mut current_pos,
+                                        );
                                     }
-                                }
-                                Split::Horizontal => {
-                                    state.layout.split = Split::Vertical;
-                                    if state.layout.is_preview() || state.layout.is_reg() {
-                                        let (mut new_column, new_row) = terminal_size()?;
-                                        new_column /= 2;
-                                        state.refresh(new_column, new_row, state.layout.y)?;
+
+                                    (KeyCode::Right, KeyModifiers::NONE) => {
+                                        move_right_command_line(
+                                            &mut keyword,
+                                            &mut current_char_pos,
+                                            &mut current_pos,
+                                        );
                                     }
-                                }
-                            },
 
-                            //delete
-                            KeyCode::Char('d') => {
-                                //If read-only, deleting is disabled.
-                                if state.is_ro {
-                                    print_warning(
-                                        "Cannot delete item in this directory.",
-                                        state.layout.y,
-                                    );
-                                    continue;
-                                }
-                                if let Some(_start_pos) = state.v_start {
-                                    //visual mode
-                                    if let Err(e) = state.delete_in_visual(None, false, &mut screen)
-                                    {
-                                        state.reset_selection();
-                                        state.redraw(state.layout.y);
-                                        print_warning(e, state.layout.y);
-                                        continue;
+                                    (KeyCode::Backspace, KeyModifiers::NONE)
+                                    | (KeyCode::Char('h'), KeyModifiers::CONTROL) => {
+                                        if current_char_pos == 0 {
+                                            continue;
+                                        };
+                                        let removed = keyword.remove(current_char_pos - 1);
+                                        if let Some(to_be_removed) =
+                                            unicode_width::UnicodeWidthChar::width(removed)
+                                        {
+                                            current_char_pos -= 1;
+                                            current_pos -= to_be_removed as u16;
+
+                                            let key = &keyword.iter().collect::<String>();
+                                            state.filter_incrementally(key, &original);
+                                            go_to_info_line_and_reset();
+                                            print!("{}{}", PROMPT_FILTER, key);
+                                            move_to(current_pos, 2);
+                                        }
                                     }
-                                } else {
-                                    //normal mode
-                                    if len == 0 {
-                                        continue;
-                                    } else {
-                                        go_to_info_line_and_reset();
-                                        print!("d");
-                                        show_cursor();
-                                        screen.flush()?;
 
-                                        if let Event::Key(KeyEvent {
-                                            code,
-                                            kind: KeyEventKind::Press,
-                                            ..
-                                        }) = event::read()?
+                                    (KeyCode::Char(c), _) => {
+                                        if let Some(to_be_added) =
+                                            unicode_width::UnicodeWidthChar::width(c)
                                         {
-                                            match code {
-                                                KeyCode::Char('d') => {
-                                                    if let Err(e) =
-                                                        state.delete(None, false, &mut screen)
-                                                    {
-                                                        print_warning(e, state.layout.y);
-                                                        continue;
-                                                    }
-                                                }
-                                                _ => {
-                                                    state.escape();
-                                                }
+                                            if current_pos + to_be_added as u16
+                                                > state.layout.terminal_column
+                                            {
+                                                continue;
                                             }
+                                            keyword.insert(current_char_pos, c);
+                                            current_char_pos += 1;
+                                            current_pos += to_be_added as u16;
+
+                                            let key = &keyword.iter().collect::<String>();
+                                            state.filter_incrementally(key, &original);
+                                            go_to_info_line_and_reset();
+                                            print!("{}{}", PROMPT_FILTER, key);
+                                            move_to(current_pos, 2);
+                                        }
+                                    }
+
+                                    //Keep the filter while navigating in the directory.
+                                    (KeyCode::Enter, KeyModifiers::NONE) => {
+                                        hide_cursor();
+                                        let keyword: String = keyword.iter().collect();
+                                        if keyword.is_empty() {
+                                            state.filter = None;
+                                            state.redraw(state.layout.y);
+                                        } else if let Err(e) = state.matcher(&keyword) {
+                                            state.clear_filter()?;
+                                            print_warning(e, state.layout.y);
+                                        } else {
+                                            go_to_info_line_and_reset();
+                                            state.move_cursor(state.layout.y);
                                         }
+                                        break;
                                     }
+
+                                    _ => continue,
                                 }
+                                screen.flush()?;
                             }
+                        }
+                        hide_cursor();
+                    }
 
-                            //yank
-                            KeyCode::Char('y') => {
-                                if let Some(_start_pos) = state.v_start {
-                                    //visual mode
-                                    let items: Vec<ItemBuffer> = state
-                                        .list
-                                        .iter()
-                                        .filter(|item| item.selected)
-                                        .map(ItemBuffer::new)
-                                        .collect();
-                                    let item_len = state.registers.yank_item(&items, None, false);
-                                    state.reset_selection();
-                                    state.list_up();
-                                    let mut yank_message: String = item_len.to_string();
-                                    yank_message.push_str(" items yanked");
-                                    print_info(yank_message, state.layout.y);
-                                } else {
-                                    //normal mode
-                                    if len == 0 {
+                    //Search forward
+                    Action::SearchNext => {
+                        //In visual mode, this is disabled.
+                        if state.v_start.is_some() {
+                            continue;
+                        }
+                        match &state.keyword {
+                            None => {
+                                continue;
+                            }
+                            Some(keyword) => {
+                                let matcher = match state.matcher(keyword) {
+                                    Ok(matcher) => matcher,
+                                    Err(_) => continue,
+                                };
+                                let next = state
+                                    .list
+                                    .iter()
+                                    .skip(state.layout.nums.index + 1)
+                                    .position(|x| matcher.is_match(&x.file_name));
+                                match next {
+                                    None => {
                                         continue;
                                     }
-                                    go_to_info_line_and_reset();
-                                    print!("y");
-                                    show_cursor();
-                                    screen.flush()?;
-
-                                    if let Event::Key(KeyEvent { code, .. }) = event::read()? {
-                                        match code {
-                                            KeyCode::Char('y') => {
-                                                if let Ok(item) = state.get_item() {
-                                                    state.registers.yank_item(
-                                                        &[ItemBuffer::new(item)],
-                                                        None,
-                                                        false,
-                                                    );
-                                                    state.escape();
-                                                    print_info("1 item yanked.", state.layout.y);
-                                                }
-                                            }
-
-                                            _ => {
-                                                state.escape();
-                                            }
-                                        }
+                                    Some(i) => {
+                                        let i = i + state.layout.nums.index + 1;
+                                        state.layout.nums.skip = i as u16;
+                                        state.layout.nums.index = i;
+                                        state.redraw(BEGINNING_ROW);
                                     }
                                 }
                             }
+                        }
+                    }
 
-                            //put
-                            KeyCode::Char('p') => {
-                                //In visual mode, this is disabled.
-                                if state.v_start.is_some() {
-                                    continue;
-                                }
-                                if let Err(e) =
-                                    state.put(state.registers.unnamed.clone(), &mut screen)
-                                {
-                                    print_warning(e, state.layout.y);
+                    //Search backward
+                    Action::SearchPrevious => {
+                        //In visual mode, this is disabled.
+                        if state.v_start.is_some() {
+                            continue;
+                        }
+                        match &state.keyword {
+                            None => {
+                                continue;
+                            }
+                            Some(keyword) => {
+                                let matcher = match state.matcher(keyword) {
+                                    Ok(matcher) => matcher,
+                                    Err(_) => continue,
+                                };
+                                let previous = state
+                                    .list
+                                    .iter()
+                                    .take(state.layout.nums.index)
+                                    .rposition(|x| matcher.is_match(&x.file_name));
+                                match previous {
+                                    None => {
+                                        continue;
+                                    }
+                                    Some(i) => {
+                                        state.layout.nums.skip = i as u16;
+                                        state.layout.nums.index = i;
+                                        state.redraw(BEGINNING_ROW);
+                                    }
                                 }
                             }
+                        }
+                    }
 
-                            //rename
-                            KeyCode::Char('c') => {
-                                //In visual mode, you can rename multiple items in default editor.
-                                if state.v_start.is_some() {
-                                    let items: Vec<ItemBuffer> = state
-                                        .list
-                                        .iter()
-                                        .filter(|item| item.selected)
-                                        .map(ItemBuffer::new)
-                                        .collect();
-                                    execute!(screen, EnterAlternateScreen)?;
-                                    let result = state.rename_multiple_items(&items);
-                                    execute!(screen, EnterAlternateScreen)?;
-                                    hide_cursor();
-                                    state.reset_selection();
-                                    state.reload(state.layout.y)?;
-                                    match result {
-                                        Err(e) => {
-                                            print_warning(e, state.layout.y);
+                    //Tinker with registers
+                    Action::Register => {
+                        go_to_info_line_and_reset();
+                        print!("\"");
+                        show_cursor();
+                        screen.flush()?;
+
+                        //Read the register name, then the keys of the operation
+                        //(e.g. "ayy, "add, "ap).
+                        let mut reg: Option<char> = None;
+                        let mut reg_keys: Vec<KeyInput> = Vec::new();
+                        let reg_action = 'reg: loop {
+                            if let Event::Key(KeyEvent {
+                                code,
+                                modifiers,
+                                kind: KeyEventKind::Press,
+                                ..
+                            }) = event::read()?
+                            {
+                                match (code, reg) {
+                                    (KeyCode::Esc, _) | (KeyCode::Backspace, None) => {
+                                        break 'reg None;
+                                    }
+                                    (KeyCode::Backspace, Some(_)) => {
+                                        if reg_keys.pop().is_none() {
+                                            reg = None;
                                         }
-                                        Ok(result_len) => {
-                                            let message = {
-                                                match result_len {
-                                                    0 => "No item renamed.".to_owned(),
-                                                    1 => "1 item renamed.".to_owned(),
-                                                    count => {
-                                                        format!("{} items renamed.", count)
-                                                    }
-                                                }
-                                            };
-                                            print_info(message, state.layout.y);
+                                    }
+                                    (KeyCode::Char(c), None) => {
+                                        if !c.is_ascii_alphanumeric() {
+                                            print_warning("Input not supported.", state.layout.y);
+                                            continue 'main;
+                                        }
+                                        reg = Some(c);
+                                    }
+                                    (_, None) => continue,
+                                    (_, Some(c)) => {
+                                        reg_keys.push(KeyInput::new(code, modifiers));
+                                        match state.keymap.get(&reg_keys, state.v_start.is_some()) {
+                                            KeyMatch::Found(action) => {
+                                                break 'reg Some((c, action))
+                                            }
+                                            KeyMatch::Pending => {}
+                                            KeyMatch::NotFound => break 'reg None,
                                         }
                                     }
+                                }
+                                go_to_info_line_and_reset();
+                                print!(
+                                    "\"{}{}",
+                                    reg.map(String::from).unwrap_or_default(),
+                                    keys_to_string(&reg_keys)
+                                );
+                                screen.flush()?;
+                            }
+                        };
+
+                        hide_cursor();
+                        let (reg, reg_action) = match reg_action {
+                            Some(reg_action) => reg_action,
+                            None => {
+                                state.escape();
+                                continue;
+                            }
+                        };
+                        match reg_action {
+                            //put
+                            Action::Put => {
+                                //In read-only directory, put disabled
+                                if state.is_ro {
+                                    state.escape();
+                                    print_warning(
+                                        "Cannot put item in this directory.",
+                                        state.layout.y,
+                                    );
                                     continue;
                                 }
-                                if len == 0 {
+                                if state.v_start.is_some() {
+                                    state.escape();
                                     continue;
                                 }
-                                let item = state.get_item()?.clone();
-                                show_cursor();
-                                let mut rename = item.file_name.chars().collect::<Vec<char>>();
-                                to_info_line();
-                                clear_current_line();
-                                print!("{}{}", PROMPT_RENAME, &rename.iter().collect::<String>(),);
-                                screen.flush()?;
-
-                                let (mut current_pos, _) = cursor_pos()?;
-                                let mut current_char_pos = rename.len();
-                                loop {
-                                    if let Event::Key(KeyEvent {
-                                        code,
-                                        modifiers,
-                                        kind: KeyEventKind::Press,
-                                        ..
-                                    }) = event::read()?
-                                    {
-                                        match (code, modifiers) {
-                                            // <C-r> to put the item name(s) from register
-                                            (KeyCode::Char('r'), KeyModifiers::CONTROL) => {
-                                                if let Event::Key(KeyEvent {
-                                                    code,
-                                                    kind: KeyEventKind::Press,
-                                                    ..
-                                                }) = event::read()?
-                                                {
-                                                    if let Some(reg) =
-                                                        state.registers.check_reg(&code)
-                                                    {
-                                                        if !reg.is_empty() {
-                                                            let to_be_inserted = reg
-                                                                .iter()
-                                                                .map(|x| x.file_name.clone())
-                                                                .collect::<Vec<String>>()
-                                                                .join(" ");
-                                                            for c in to_be_inserted.chars() {
-                                                                if let Some(to_be_added) =
-                                                                    unicode_width::UnicodeWidthChar::width(c)
-                                                                {
-                                                                    if current_pos + to_be_added as u16
-                                                                        > state.layout.terminal_column
-                                                                    {
-                                                                        continue;
-                                                                    }
-                                                                    rename.insert(current_char_pos, c);
-                                                                    current_char_pos += 1;
-                                                                    current_pos += to_be_added as u16;
-                                                                }
-                                                            }
-                                                            go_to_info_line_and_reset();
-                                                            print!(
-                                                                "{}{}",
-                                                                PROMPT_RENAME,
-                                                                &rename.iter().collect::<String>()
-                                                            );
-                                                            move_to(current_pos + 1, 2);
-                                                            screen.flush()?;
-                                                            continue;
-                                                        } else {
-                                                            continue;
-                                                        }
-                                                    } else {
-                                                        continue;
-                                                    }
-                                                }
-                                            }
-
-                                            (KeyCode::Esc, KeyModifiers::NONE) => {
-                                                state.escape();
-                                                break;
-                                            }
-
-                                            (KeyCode::Left, KeyModifiers::NONE) => {
-                                                move_left_command_line(
-                                                    &mut rename,
-                                                    &mut current_char_pos,
-                                                    &mut current_pos,
-                                                )
-                                            }
-
-                                            (KeyCode::Right, KeyModifiers::NONE) => {
-                                                move_right_command_line(
-                                                    &mut rename,
-                                                    &mut current_char_pos,
-                                                    &mut current_pos,
-                                                )
-                                            }
-
-                                            (KeyCode::Backspace, KeyModifiers::NONE)
-                                            | (KeyCode::Char('h'), KeyModifiers::CONTROL) => {
-                                                if current_char_pos == 0 {
-                                                    continue;
-                                                };
-                                                let removed = rename.remove(current_char_pos - 1);
-                                                if let Some(to_be_removed) =
-                                                    unicode_width::UnicodeWidthChar::width(removed)
-                                                {
-                                                    current_char_pos -= 1;
-                                                    current_pos -= to_be_removed as u16;
-
-                                                    go_to_info_line_and_reset();
-                                                    print!(
-                                                        "{}{}",
-                                                        PROMPT_RENAME,
-                                                        &rename.iter().collect::<String>(),
-                                                    );
-                                                    move_to(current_pos + 1, 2);
-                                                }
-                                            }
-
-                                            (KeyCode::Char(c), _) => {
-                                                if let Some(to_be_added) =
-                                                    unicode_width::UnicodeWidthChar::width(c)
-                                                {
-                                                    rename.insert(current_char_pos, c);
-                                                    current_char_pos += 1;
-                                                    current_pos += to_be_added as u16;
-
-                                                    go_to_info_line_and_reset();
-                                                    print!(
-                                                        "{}{}",
-                                                        PROMPT_RENAME,
-                                                        &rename.iter().collect::<String>(),
-                                                    );
-                                                    move_to(current_pos + 1, 2);
-                                                }
-                                            }
-
-                                            (KeyCode::Enter, KeyModifiers::NONE) => {
-                                                let rename = rename.iter().collect::<String>();
-                                                let mut to = state.current_dir.clone();
-                                                to.push(rename);
-                                                if let Err(e) =
-                                                    std::fs::rename(&item.file_path, &to)
-                                                {
-                                                    hide_cursor();
-                                                    print_warning(e, state.layout.y);
-                                                    break;
-                                                }
-
-                                                state.operations.branch();
-                                                state.operations.push(OpKind::Rename(vec![(
-                                                    item.file_path.clone(),
-                                                    to,
-                                                )]));
-
-                                                hide_cursor();
-                                                state.reload(state.layout.y)?;
-                                                break;
-                                            }
-
-                                            _ => continue,
-                                        }
-                                        screen.flush()?;
+                                let target = match reg {
+                                    '0' => Some(&state.registers.zero),
+                                    '1'..='9' => state
+                                        .registers
+                                        .numbered
+                                        .get(reg.to_digit(10).unwrap() as usize - 1),
+                                    'a'..='z' => state.registers.named.get(&reg),
+                                    _ => None,
+                                };
+
+                                if let Some(target) = target {
+                                    let target = target.clone();
+                                    if let Err(e) = state.put(target, &mut screen) {
+                                        print_warning(e, state.layout.y);
+                                        continue;
                                     }
+                                    state.last_change = Some(Change::Put(reg));
+                                } else {
+                                    print_warning("Register not found.", state.layout.y);
                                 }
+                                state.move_cursor(state.layout.y);
                             }
 
-                            //Search mode
-                            KeyCode::Char('/') => {
-                                //In visual mode, this is disabled.
-                                //TODO! Enable this in visual mode.
+                            //yank
+                            Action::Yank => {
+                                //Only named registers are available; uppercase appends.
+                                if !reg.is_ascii_alphabetic() {
+                                    state.escape();
+                                    continue;
+                                }
+                                let append = reg.is_ascii_uppercase();
+                                let reg = Some(reg.to_ascii_lowercase());
                                 if state.v_start.is_some() {
+                                    //visual mode
+                                    let items: Vec<ItemBuffer> = state
+                                        .list
+                                        .iter()
+                                        .filter(|item| item.selected)
+                                        .map(ItemBuffer::new)
+                                        .collect();
+                                    let item_len = state.registers.yank_item(&items, reg, append);
+                                    state.reset_selection();
+                                    state.list_up();
+                                    let mut yank_message: String = item_len.to_string();
+                                    yank_message.push_str(" items yanked");
+                                    print_info(yank_message, state.layout.y);
+                                    state.move_cursor(state.layout.y);
+                                } else if !state.selection.is_empty() {
+                                    //items selected by <Space>
+                                    let item_len = state.yank_selection(reg, append);
+                                    state.redraw(state.layout.y);
+                                    print_info(
+                                        format!("{} items yanked", item_len),
+                                        state.layout.y,
+                                    );
+                                } else if let Some(count) = count {
+                                    //normal mode with the count
+                                    let item_len = state.yank_count(count, reg, append);
+                                    state.escape();
+                                    print_info(
+                                        format!("{} items yanked", item_len),
+                                        state.layout.y,
+                                    );
+                                } else if let Ok(item) = state.get_item() {
+                                    //normal mode
+                                    state.registers.yank_item(
+                                        &[ItemBuffer::new(item)],
+                                        reg,
+                                        append,
+                                    );
+                                    state.escape();
+                                    print_info("1 item yanked.", state.layout.y);
+                                } else {
+                                    state.escape();
+                                }
+                            }
+
+                            //delete
+                            Action::Delete => {
+                                //In read-only directory, delete disabled
+                                if state.is_ro {
+                                    state.escape();
+                                    print_warning(
+                                        "Cannot delete item in this directory.",
+                                        state.layout.y,
+                                    );
                                     continue;
                                 }
-                                if len == 0 {
+                                //Only named registers are available; uppercase appends.
+                                if !reg.is_ascii_alphabetic() {
+                                    state.escape();
                                     continue;
                                 }
-                                delete_pointer();
-                                show_cursor();
-                                go_to_info_line_and_reset();
-                                print!("{}", PROMPT_SEARCH);
-                                screen.flush()?;
-
-                                let original_nums = state.layout.nums;
-                                let original_y = state.layout.y;
-                                let mut keyword: Vec<char> = Vec::new();
-
-                                // express position in terminal
-                                let mut current_pos = INITIAL_POS_COMMAND_LINE;
-                                // express position in Vec<Char>
-                                let mut current_char_pos = 0;
-                                loop {
-                                    if let Event::Key(KeyEvent {
-                                        code,
-                                        modifiers,
-                                        kind: KeyEventKind::Press,
-                                        ..
-                                    }) = event::read()?
+                                let append = reg.is_ascii_uppercase();
+                                let reg = Some(reg.to_ascii_lowercase());
+                                if state.v_start.is_some() {
+                                    //visual mode
+                                    let count =
+                                        state.list.iter().filter(|item| item.selected).count();
+                                    if let Err(e) = state.delete_in_visual(reg, append, &mut screen)
                                     {
-                                        match (code, modifiers) {
-                                            (KeyCode::Esc, KeyModifiers::NONE) => {
-                                                hide_cursor();
-                                                state.redraw(state.layout.y);
-                                                break;
-                                            }
-
-                                            (KeyCode::Left, KeyModifiers::NONE) => {
-                                                move_left_command_line(
-                                                    &mut keyword,
-                                                    &mut current_char_pos,
-                                                    &mut current_pos,
-                                                );
-                                            }
-
-                                            (KeyCode::Right, KeyModifiers::NONE) => {
-                                                move_right_command_line(
-                                                    &mut keyword,
-                                                    &mut current_char_pos,
-                                                    &mut current_pos,
-                                                );
-                                            }
-
-                                            (KeyCode::Backspace, KeyModifiers::NONE)
-                                            | (KeyCode::Char('h'), KeyModifiers::CONTROL) => {
-                                                if current_char_pos == 0 {
-                                                    continue;
-                                                };
-                                                let removed = keyword.remove(current_char_pos - 1);
-                                                if let Some(to_be_removed) =
-                                                    unicode_width::UnicodeWidthChar::width(removed)
-                                                {
-                                                    current_char_pos -= 1;
-                                                    current_pos -= to_be_removed as u16;
+                                        state.reset_selection();
+                                        state.redraw(state.layout.y);
+                                        print_warning(e, state.layout.y);
+                                        continue;
+                                    }
+                                    state.last_change = Some(Change::Delete { count, reg, append });
+                                } else if !state.selection.is_empty() {
+                                    //items selected by <Space>
+                                    if let Err(e) = state.delete_selection(reg, append, &mut screen)
+                                    {
+                                        state.reload(state.layout.y)?;
+                                        print_warning(e, state.layout.y);
+                                        continue;
+                                    }
+                                } else {
+                                    //normal mode
+                                    let count = count.unwrap_or(1);
+                                    if let Err(e) =
+                                        state.delete_count(count, reg, append, &mut screen)
+                                    {
+                                        state.reset_selection();
+                                        state.redraw(state.layout.y);
+                                        print_warning(e, state.layout.y);
+                                        continue;
+                                    }
+                                    state.last_change = Some(Change::Delete { count, reg, append });
+                                }
+                                state.move_cursor(state.layout.y);
+                            }
 
-                                                    let key = &keyword.iter().collect::<String>();
+                            _ => {
+                                state.escape();
+                            }
+                        }
+                    }
 
-                                                    let target = state
-                                                        .list
+                    //command line
+                    Action::CommandLine => {
+                        delete_pointer();
+                        go_to_info_line_and_reset();
+                        print!("{}", PROMPT_COMMAND_LINE);
+                        show_cursor();
+                        screen.flush()?;
+
+                        let mut command: Vec<char> = Vec::new();
+
+                        // express position in terminal
+                        let mut current_pos = INITIAL_POS_COMMAND_LINE;
+                        // express position in Vec<Char>
+                        let mut current_char_pos = 0;
+                        let mut history_cursor = HistoryCursor::default();
+                        let mut completion: Option<Completion> = None;
+                        'command: loop {
+                            if let Event::Key(KeyEvent {
+                                code,
+                                modifiers,
+                                kind: KeyEventKind::Press,
+                                ..
+                            }) = event::read()?
+                            {
+                                //Close the completion popup by keys other than Tab.
+                                if completion.is_some()
+                                    && !matches!(code, KeyCode::Tab | KeyCode::BackTab)
+                                {
+                                    completion = None;
+                                    state.redraw(state.layout.y);
+                                    go_to_info_line_and_reset();
+                                    print!(
+                                        "{}{}",
+                                        PROMPT_COMMAND_LINE,
+                                        &command.iter().collect::<String>(),
+                                    );
+                                    move_to(current_pos, 2);
+                                }
+                                match (code, modifiers) {
+                                    (KeyCode::Char('r'), KeyModifiers::CONTROL) => {
+                                        history_cursor.reset();
+                                        if let Event::Key(KeyEvent {
+                                            code,
+                                            kind: KeyEventKind::Press,
+                                            ..
+                                        }) = event::read()?
+                                        {
+                                            if let Some(reg) = state.registers.check_reg(&code) {
+                                                if !reg.is_empty() {
+                                                    let to_be_inserted = reg
                                                         .iter()
-                                                        .position(|x| x.file_name.contains(key));
-
-                                                    match target {
-                                                        Some(i) => {
-                                                            state.layout.nums.skip = i as u16;
-                                                            state.layout.nums.index = i;
-                                                            state.highlight_matches(key);
-                                                            state.redraw(BEGINNING_ROW);
-                                                        }
-                                                        None => {
-                                                            state.highlight_matches(key);
-                                                            state.layout.nums = original_nums;
-                                                            state.layout.y = original_y;
-                                                            state.redraw(state.layout.y);
+                                                        .map(|x| x.file_name.clone())
+                                                        .collect::<Vec<String>>()
+                                                        .join(" ");
+                                                    for c in to_be_inserted.chars() {
+                                                        if let Some(to_be_added) =
+                                                            unicode_width::UnicodeWidthChar::width(
+                                                                c,
+                                                            )
+                                                        {
+                                                            if current_pos + to_be_added as u16
+                                                                > state.layout.terminal_column
+                                                            {
+                                                                continue;
+                                                            }
+                                                            command.insert(current_char_pos, c);
+                                                            current_char_pos += 1;
+                                                            current_pos += to_be_added as u16;
                                                         }
                                                     }
                                                     go_to_info_line_and_reset();
-                                                    print!("{}{}", PROMPT_SEARCH, key);
+                                                    print!(
+                                                        "{}{}",
+                                                        PROMPT_COMMAND_LINE,
+                                                        &command.iter().collect::<String>(),
+                                                    );
                                                     move_to(current_pos, 2);
+                                                    screen.flush()?;
+                                                    continue;
+                                                } else {
+                                                    continue;
                                                 }
+                                            } else {
+                                                continue;
                                             }
+                                        }
+                                    }
 
-                                            (KeyCode::Char(c), _) => {
-                                                if let Some(to_be_added) =
-                                                    unicode_width::UnicodeWidthChar::width(c)
-                                                {
-                                                    if current_pos + to_be_added as u16
-                                                        > state.layout.terminal_column
-                                                    {
-                                                        continue;
-                                                    }
-                                                    keyword.insert(current_char_pos, c);
-                                                    current_char_pos += 1;
-                                                    current_pos += to_be_added as u16;
-
-                                                    let key = &keyword.iter().collect::<String>();
-
-                                                    let target = match state.ignore_case {
-                                                        Some(true) => {
-                                                            state.list.iter().position(|x| {
-                                                                x.file_name
-                                                                    .to_lowercase()
-                                                                    .contains(&key.to_lowercase())
-                                                            })
-                                                        }
-                                                        _ => state.list.iter().position(|x| {
-                                                            x.file_name.contains(key)
-                                                        }),
-                                                    };
-                                                    match target {
-                                                        Some(i) => {
-                                                            state.layout.nums.skip = i as u16;
-                                                            state.layout.nums.index = i;
-                                                            state.highlight_matches(key);
-                                                            state.redraw(BEGINNING_ROW);
-                                                        }
-                                                        None => {
-                                                            state.highlight_matches(key);
-                                                            state.layout.nums = original_nums;
-                                                            state.layout.y = original_y;
-                                                            state.redraw(state.layout.y);
-                                                        }
-                                                    }
+                                    (KeyCode::Esc, KeyModifiers::NONE) => {
+                                        state.escape();
+                                        break 'command;
+                                    }
 
-                                                    go_to_info_line_and_reset();
-                                                    print!("{}{}", PROMPT_SEARCH, key);
-                                                    move_to(current_pos, 2);
-                                                }
-                                            }
+                                    (KeyCode::Left, KeyModifiers::NONE) => {
+                                        move_left_command_line(
+                                            &mut command,
+                                            &mut current_char_pos,
+                                            &mut current_pos,
+                                        );
+                                    }
 
-                                            (KeyCode::Enter, KeyModifiers::NONE) => {
-                                                go_to_info_line_and_reset();
-                                                state.keyword = Some(keyword.iter().collect());
-                                                state.move_cursor(state.layout.y);
-                                                break;
-                                            }
+                                    (KeyCode::Right, KeyModifiers::NONE) => {
+                                        move_right_command_line(
+                                            &mut command,
+                                            &mut current_char_pos,
+                                            &mut current_pos,
+                                        );
+                                    }
 
-                                            _ => continue,
+                                    (KeyCode::Backspace, KeyModifiers::NONE)
+                                    | (KeyCode::Char('h'), KeyModifiers::CONTROL) => {
+                                        if current_char_pos == 0 {
+                                            continue;
+                                        };
+                                        let removed = command.remove(current_char_pos - 1);
+                                        if let Some(to_be_removed) =
+                                            unicode_width::UnicodeWidthChar::width(removed)
+                                        {
+                                            current_char_pos -= 1;
+                                            current_pos -= to_be_removed as u16;
+                                            history_cursor.reset();
+
+                                            go_to_info_line_and_reset();
+                                            print!(
+                                                "{}{}",
+                                                PROMPT_COMMAND_LINE,
+                                                &command.iter().collect::<String>(),
+                                            );
+                                            move_to(current_pos, 2);
                                         }
-                                        screen.flush()?;
                                     }
-                                }
-                                hide_cursor();
-                            }
 
-                            //Search forward
-                            KeyCode::Char('n') => {
-                                //In visual mode, this is disabled.
-                                if state.v_start.is_some() {
-                                    continue;
-                                }
-                                match &state.keyword {
-                                    None => {
-                                        continue;
-                                    }
-                                    Some(keyword) => {
-                                        let next = state
-                                            .list
-                                            .iter()
-                                            .skip(state.layout.nums.index + 1)
-                                            .position(|x| x.file_name.contains(keyword));
-                                        match next {
-                                            None => {
+                                    (KeyCode::Tab, KeyModifiers::NONE) | (KeyCode::BackTab, _) => {
+                                        let is_next = code == KeyCode::Tab;
+                                        if let Some(c) = completion.as_mut() {
+                                            if is_next {
+                                                c.next();
+                                            } else {
+                                                c.prev();
+                                            }
+                                        } else {
+                                            let input = command[..current_char_pos]
+                                                .iter()
+                                                .collect::<String>();
+                                            let (start, candidates) =
+                                                complete_command_line(&input, &state.current_dir);
+                                            if candidates.is_empty() {
                                                 continue;
                                             }
-                                            Some(i) => {
-                                                let i = i + state.layout.nums.index + 1;
-                                                state.layout.nums.skip = i as u16;
-                                                state.layout.nums.index = i;
-                                                state.redraw(BEGINNING_ROW);
+                                            let mut c = Completion::new(
+                                                start,
+                                                current_char_pos,
+                                                candidates,
+                                            );
+                                            if !is_next {
+                                                c.prev();
                                             }
+                                            completion = Some(c);
                                         }
-                                    }
-                                }
-                            }
-
-                            //Search backward
-                            KeyCode::Char('N') => {
-                                //In visual mode, this is disabled.
-                                if state.v_start.is_some() {
-                                    continue;
-                                }
-                                match &state.keyword {
-                                    None => {
-                                        continue;
-                                    }
-                                    Some(keyword) => {
-                                        let previous = state
-                                            .list
-                                            .iter()
-                                            .take(state.layout.nums.index)
-                                            .rposition(|x| x.file_name.contains(keyword));
-                                        match previous {
-                                            None => {
-                                                continue;
-                                            }
-                                            Some(i) => {
-                                                state.layout.nums.skip = i as u16;
-                                                state.layout.nums.index = i;
-                                                state.redraw(BEGINNING_ROW);
+                                        if let Some(c) = completion.as_mut() {
+                                            current_char_pos = c.apply(&mut command);
+                                            current_pos = INITIAL_POS_COMMAND_LINE
+                                                + unicode_width::UnicodeWidthStr::width(
+                                                    command[..current_char_pos]
+                                                        .iter()
+                                                        .collect::<String>()
+                                                        .as_str(),
+                                                )
+                                                    as u16;
+                                            if c.candidates.len() > 1 {
+                                                c.print_candidates(
+                                                    state.layout.terminal_row,
+                                                    state.layout.terminal_column,
+                                                );
                                             }
                                         }
+                                        go_to_info_line_and_reset();
+                                        print!(
+                                            "{}{}",
+                                            PROMPT_COMMAND_LINE,
+                                            &command.iter().collect::<String>(),
+                                        );
+                                        move_to(current_pos, 2);
+                                        history_cursor.reset();
                                     }
-                                }
-                            }
-
-                            //Tinker with registers
-                            KeyCode::Char('"') => {
-                                go_to_info_line_and_reset();
-                                print!("\"");
-                                show_cursor();
-                                screen.flush()?;
 
-                                let mut command: Vec<char> = Vec::new();
+                                    (KeyCode::Up, KeyModifiers::NONE)
+                                    | (KeyCode::Char('p'), KeyModifiers::CONTROL) => {
+                                        let input = command.iter().collect::<String>();
+                                        if let Some(entry) = history_cursor.prev(
+                                            state.history.entries(HistoryKind::Command),
+                                            &input,
+                                        ) {
+                                            replace_command_line(
+                                                PROMPT_COMMAND_LINE,
+                                                &entry,
+                                                &mut command,
+                                                &mut current_char_pos,
+                                                &mut current_pos,
+                                            );
+                                        }
+                                    }
 
-                                let mut current_pos = INITIAL_POS_COMMAND_LINE;
-                                'reg: loop {
-                                    if let Event::Key(KeyEvent {
-                                        code,
-                                        kind: KeyEventKind::Press,
-                                        ..
-                                    }) = event::read()?
-                                    {
-                                        match code {
-                                            KeyCode::Esc => {
-                                                state.escape();
-                                                break 'reg;
-                                            }
+                                    (KeyCode::Down, KeyModifiers::NONE)
+                                    | (KeyCode::Char('n'), KeyModifiers::CONTROL) => {
+                                        if let Some(entry) = history_cursor
+                                            .next(state.history.entries(HistoryKind::Command))
+                                        {
+                                            replace_command_line(
+                                                PROMPT_COMMAND_LINE,
+                                                &entry,
+                                                &mut command,
+                                                &mut current_char_pos,
+                                                &mut current_pos,
+                                            );
+                                        }
+                                    }
 
-                                            KeyCode::Left => {
-                                                if current_pos == INITIAL_POS_COMMAND_LINE {
-                                                    continue;
-                                                };
-                                                current_pos -= 1;
-                                                move_left(1);
+                                    (KeyCode::Char(c), _) => {
+                                        if let Some(to_be_added) =
+                                            unicode_width::UnicodeWidthChar::width(c)
+                                        {
+                                            if current_pos + to_be_added as u16
+                                                > state.layout.terminal_column
+                                            {
+                                                continue;
                                             }
+                                            command.insert(current_char_pos, c);
+                                            current_char_pos += 1;
+                                            current_pos += to_be_added as u16;
+                                            history_cursor.reset();
+
+                                            go_to_info_line_and_reset();
+                                            print!(
+                                                "{}{}",
+                                                PROMPT_COMMAND_LINE,
+                                                &command.iter().collect::<String>(),
+                                            );
+                                            move_to(current_pos, 2);
+                                        }
+                                    }
 
-                                            KeyCode::Right => {
-                                                if current_pos as usize
-                                                    == command.len()
-                                                        + INITIAL_POS_COMMAND_LINE as usize
-                                                {
-                                                    continue;
-                                                };
-                                                current_pos += 1;
-                                                move_right(1);
+                                    (KeyCode::Enter, KeyModifiers::NONE) => {
+                                        hide_cursor();
+                                        //Set the command and argument(s).
+                                        let commands: String = command.iter().collect();
+                                        let commands: Vec<&str> =
+                                            commands.split_whitespace().collect();
+                                        if commands.is_empty() {
+                                            state.escape();
+                                            break;
+                                        }
+                                        state
+                                            .history
+                                            .add(HistoryKind::Command, &commands.join(" "));
+                                        let command = commands[0];
+
+                                        if command == "set" {
+                                            //:set - Set or show runtime options
+                                            match state.set_command(&commands[1..]) {
+                                                Ok(message) if !message.is_empty() => {
+                                                    print_info(message, state.layout.y);
+                                                }
+                                                Ok(_) => state.escape(),
+                                                Err(e) => print_warning(e, state.layout.y),
                                             }
+                                            break 'command;
+                                        }
 
-                                            KeyCode::Backspace => {
-                                                if current_pos == INITIAL_POS_COMMAND_LINE {
-                                                    state.escape();
-                                                    break 'reg;
-                                                } else {
-                                                    command.remove(
-                                                        (current_pos
-                                                            - INITIAL_POS_COMMAND_LINE
-                                                            - 1)
-                                                        .into(),
-                                                    );
-                                                    current_pos -= 1;
+                                        //Keep the current item and selection for placeholders,
+                                        //then exit the visual mode.
+                                        let values = state.placeholder_values();
+                                        let envs = state.command_env();
+                                        if state.v_start.is_some() {
+                                            state.reset_selection();
+                                            state.redraw(state.layout.y);
+                                        }
 
-                                                    clear_current_line();
-                                                    to_info_line();
-                                                    print!(
-                                                        "\"{}",
-                                                        &command.iter().collect::<String>()
+                                        if command == "select" || command == "unselect" {
+                                            //:select {glob}.. - Select items that match the globs
+                                            //:unselect {glob}.. - Unselect them
+                                            //Without globs, all the items in the list.
+                                            let select = command == "select";
+                                            match state.select_by_glob(&commands[1..], select) {
+                                                Ok(count) => {
+                                                    state.redraw(state.layout.y);
+                                                    print_info(
+                                                        format!(
+                                                            "{} items {}.",
+                                                            count,
+                                                            if select {
+                                                                "selected"
+                                                            } else {
+                                                                "unselected"
+                                                            }
+                                                        ),
+                                                        state.layout.y,
                                                     );
-                                                    move_to(current_pos, 2);
                                                 }
+                                                Err(e) => print_warning(e, state.layout.y),
                                             }
+                                            break 'command;
+                                        }
 
-                                            KeyCode::Char(c) => {
-                                                command.insert(
-                                                    (current_pos - INITIAL_POS_COMMAND_LINE).into(),
-                                                    c,
-                                                );
-                                                if ((state.v_start.is_some() || c == 'p')
-                                                    && command.len() == 2)
-                                                    || (state.v_start.is_none()
-                                                        && command.len() == 3)
-                                                {
-                                                    if !command[0].is_ascii_alphanumeric() {
-                                                        print_warning(
-                                                            "Input not supported.",
+                                        if command == "invert" {
+                                            //:invert - Invert the selection of the items in the list
+                                            let count = state.invert_selection();
+                                            state.redraw(state.layout.y);
+                                            print_info(
+                                                format!("{} items selected.", count),
+                                                state.layout.y,
+                                            );
+                                            break 'command;
+                                        }
+
+                                        if command == "bookmark" {
+                                            //:bookmark add {name} - Bookmark the current directory
+                                            //:bookmark rm {name} - Remove the bookmark
+                                            //:bookmark - Pick a bookmark to jump to
+                                            match (commands.get(1).copied(), commands.get(2)) {
+                                                (Some("add"), Some(name)) => {
+                                                    let dir = state.current_dir.clone();
+                                                    match state
+                                                        .bookmarks
+                                                        .update(&data_files.bookmarks, |b| {
+                                                            b.add(name, &dir)
+                                                        }) {
+                                                        Ok(_) => print_info(
+                                                            format!("Bookmarked as {}.", name),
                                                             state.layout.y,
-                                                        );
-                                                        break 'reg;
+                                                        ),
+                                                        Err(e) => print_warning(e, state.layout.y),
                                                     }
-                                                    let action: String =
-                                                        command[1..].iter().collect();
-                                                    match action.as_str() {
-                                                        //put
-                                                        "p" => {
-                                                            //In read-only directory, put disabled
-                                                            if state.is_ro {
-                                                                state.escape();
-                                                                print_warning(
-                                        "Cannot put item in this directory.",
-                                        state.layout.y,
-                                    );
-                                                                break 'reg;
-                                                            }
-                                                            if state.v_start.is_some() {
-                                                                clear_current_line();
-                                                                hide_cursor();
-                                                                state.move_cursor(state.layout.y);
-                                                                break 'reg;
-                                                            }
-                                                            let target = match command[0] {
-                                                                '0' => Some(&state.registers.zero),
-                                                                '1'..='9' => {
-                                                                    state.registers.numbered.get(
-                                                                        command[0]
-                                                                            .to_digit(10)
-                                                                            .unwrap()
-                                                                            as usize
-                                                                            - 1,
-                                                                    )
-                                                                }
-                                                                'a'..='z' => state
-                                                                    .registers
-                                                                    .named
-                                                                    .get(&command[0]),
-                                                                _ => None,
-                                                            };
-
-                                                            if let Some(target) = target {
-                                                                let target = target.clone();
-                                                                if let Err(e) =
-                                                                    state.put(target, &mut screen)
-                                                                {
-                                                                    print_warning(
-                                                                        e,
-                                                                        state.layout.y,
-                                                                    );
-                                                                    break 'reg;
-                                                                }
-                                                            } else {
-                                                                print_warning(
-                                                                    "Register not found.",
-                                                                    state.layout.y,
-                                                                );
-                                                            }
-                                                            state.move_cursor(state.layout.y);
-                                                            break 'reg;
-                                                        }
-                                                        //yank (normal mode)
-                                                        "yy" => {
-                                                            if state.v_start.is_some() {
-                                                                state.move_cursor(state.layout.y);
-                                                                break 'reg;
-                                                            }
-                                                            if command[0].is_ascii_lowercase() {
-                                                                if let Ok(item) = state.get_item() {
-                                                                    state.registers.yank_item(
-                                                                        &[ItemBuffer::new(item)],
-                                                                        Some(command[0]),
-                                                                        false,
-                                                                    );
-                                                                }
-                                                            } else if command[0]
-                                                                .is_ascii_uppercase()
-                                                            {
-                                                                if let Ok(item) = state.get_item() {
-                                                                    state.registers.yank_item(
-                                                                        &[ItemBuffer::new(item)],
-                                                                        Some(
-                                                                            command[0]
-                                                                                .to_ascii_lowercase(
-                                                                                ),
-                                                                        ),
-                                                                        true,
-                                                                    );
-                                                                }
-                                                            } else {
-                                                                state.move_cursor(state.layout.y);
-                                                                break 'reg;
-                                                            }
-                                                            state.escape();
-                                                            print_info(
-                                                                "1 item yanked.",
-                                                                state.layout.y,
-                                                            );
-                                                            break 'reg;
-                                                        }
-                                                        //yank (visual mode)
-                                                        "y" => {
-                                                            if state.v_start.is_none() {
-                                                                state.move_cursor(state.layout.y);
-                                                                break 'reg;
-                                                            }
-                                                            let items: Vec<ItemBuffer> = state
-                                                                .list
-                                                                .iter()
-                                                                .filter(|item| item.selected)
-                                                                .map(ItemBuffer::new)
-                                                                .collect();
-                                                            let item_len: usize;
-                                                            if command[0].is_ascii_lowercase() {
-                                                                item_len =
-                                                                    state.registers.yank_item(
-                                                                        &items,
-                                                                        Some(command[0]),
-                                                                        false,
-                                                                    );
-                                                            } else if command[0]
-                                                                .is_ascii_uppercase()
-                                                            {
-                                                                item_len =
-                                                                    state.registers.yank_item(
-                                                                        &items,
-                                                                        Some(
-                                                                            command[0]
-                                                                                .to_ascii_lowercase(
-                                                                                ),
-                                                                        ),
-                                                                        true,
-                                                                    );
-                                                            } else {
-                                                                state.move_cursor(state.layout.y);
-                                                                break 'reg;
-                                                            }
-                                                            state.reset_selection();
-                                                            state.list_up();
-                                                            let mut yank_message: String =
-                                                                item_len.to_string();
-                                                            yank_message.push_str(" items yanked");
-                                                            print_info(
-                                                                yank_message,
-                                                                state.layout.y,
-                                                            );
-                                                            state.move_cursor(state.layout.y);
-                                                            break 'reg;
-                                                        }
-
-                                                        //delete (normal mode)
-                                                        "dd" => {
-                                                            //In read-only directory, delete
-                                                            //disabled
-                                                            if state.is_ro {
-                                                                state.escape();
-                                                                print_warning(
-                                        "Cannot delete item in this directory.",
-                                        state.layout.y,
-                                    );
-                                                                break 'reg;
-                                                            }
-                                                            if state.v_start.is_some() {
-                                                                state.move_cursor(state.layout.y);
-                                                                break 'reg;
-                                                            }
-                                                            if command[0].is_ascii_lowercase() {
-                                                                if let Err(e) = state.delete(
-                                                                    Some(command[0]),
-                                                                    false,
-                                                                    &mut screen,
-                                                                ) {
-                                                                    print_warning(
-                                                                        e,
-                                                                        state.layout.y,
-                                                                    );
-                                                                    break 'reg;
-                                                                }
-                                                            } else if command[0]
-                                                                .is_ascii_uppercase()
-                                                            {
-                                                                if let Err(e) = state.delete(
-                                                                    Some(
-                                                                        command[0]
-                                                                            .to_ascii_lowercase(),
-                                                                    ),
-                                                                    true,
-                                                                    &mut screen,
-                                                                ) {
-                                                                    print_warning(
-                                                                        e,
-                                                                        state.layout.y,
-                                                                    );
-                                                                    break 'reg;
-                                                                }
-                                                            }
-                                                            state.move_cursor(state.layout.y);
-                                                            break 'reg;
-                                                        }
-                                                        //delete (visual mode)
-                                                        "d" => {
-                                                            //In read-only directory, delete
-                                                            //disabled
-                                                            if state.is_ro {
-                                                                state.escape();
-                                                                print_warning(
-                                        "Cannot delete item in this directory.",
-                                        state.layout.y,
-                                    );
-                                                                break 'reg;
+                                                }
+                                                (Some("rm"), Some(name)) => {
+                                                    match state
+                                                        .bookmarks
+                                                        .update(&data_files.bookmarks, |b| {
+                                                            b.remove(name)
+                                                        }) {
+                                                        Ok(_) => print_info(
+                                                            format!("Bookmark {} removed.", name),
+                                                            state.layout.y,
+                                                        ),
+                                                        Err(e) => print_warning(e, state.layout.y),
+                                                    }
+                                                }
+                                                (None, _) => {
+                                                    //Take in bookmarks added by other instances.
+                                                    state.bookmarks =
+                                                        Bookmarks::read(&data_files.bookmarks);
+                                                    let names = state.bookmarks.names();
+                                                    if names.is_empty() {
+                                                        print_warning(
+                                                            "No bookmarks.",
+                                                            state.layout.y,
+                                                        );
+                                                        break 'command;
+                                                    }
+                                                    let mut picker = Finder::from_entries(
+                                                        "bookmark",
+                                                        &state.current_dir,
+                                                        names,
+                                                    );
+                                                    let mut query = String::new();
+                                                    delete_pointer();
+                                                    loop {
+                                                        picker.print(
+                                                            state.layout.terminal_row,
+                                                            state.layout.terminal_column,
+                                                        );
+                                                        go_to_info_line_and_reset();
+                                                        print!("{}{}", PROMPT_BOOKMARK, query);
+                                                        show_cursor();
+                                                        screen.flush()?;
+
+                                                        let Event::Key(KeyEvent {
+                                                            code,
+                                                            modifiers,
+                                                            kind: KeyEventKind::Press,
+                                                            ..
+                                                        }) = event::read()?
+                                                        else {
+                                                            continue;
+                                                        };
+                                                        match (code, modifiers) {
+                                                            (KeyCode::Esc, KeyModifiers::NONE) => {
+                                                                hide_cursor();
+                                                                state.redraw(state.layout.y);
+                                                                break;
                                                             }
-                                                            if state.v_start.is_none() {
-                                                                state.move_cursor(state.layout.y);
-                                                                break 'reg;
+                                                            (KeyCode::Up, KeyModifiers::NONE)
+                                                            | (
+                                                                KeyCode::Char('p'),
+                                                                KeyModifiers::CONTROL,
+                                                            ) => picker.move_up(),
+                                                            (KeyCode::Down, KeyModifiers::NONE)
+                                                            | (
+                                                                KeyCode::Char('n'),
+                                                                KeyModifiers::CONTROL,
+                                                            ) => picker.move_down(),
+                                                            (
+                                                                KeyCode::Backspace,
+                                                                KeyModifiers::NONE,
+                                                            ) => {
+                                                                query.pop();
+                                                                picker.update(&query);
                                                             }
-                                                            if command[0].is_ascii_lowercase() {
-                                                                if let Err(e) = state
-                                                                    .delete_in_visual(
-                                                                        Some(command[0]),
-                                                                        false,
-                                                                        &mut screen,
-                                                                    )
-                                                                {
-                                                                    state.reset_selection();
-                                                                    state.redraw(state.layout.y);
-                                                                    print_warning(
-                                                                        e,
-                                                                        state.layout.y,
-                                                                    );
-                                                                    break 'reg;
-                                                                }
-                                                            } else if command[0]
-                                                                .is_ascii_uppercase()
-                                                            {
-                                                                if let Err(e) = state
-                                                                    .delete_in_visual(
-                                                                        Some(
-                                                                            command[0]
-                                                                                .to_ascii_lowercase(
-                                                                                ),
-                                                                        ),
-                                                                        true,
-                                                                        &mut screen,
-                                                                    )
-                                                                {
-                                                                    state.reset_selection();
+                                                            (
+                                                                KeyCode::Enter,
+                                                                KeyModifiers::NONE,
+                                                            ) => {
+                                                                hide_cursor();
+                                                                let dir = picker
+                                                                    .selected_entry()
+                                                                    .map(|name| {
+                                                                        state
+                                                                            .bookmarks
+                                                                            .get(name)
+                                                                            .cloned()
+                                                                    });
+                                                                let result = match dir {
+                                                                    Some(Ok(dir)) => {
+                                                                        state.layout.nums.reset();
+                                                                        state
+                                                                            .chdir(&dir, Move::Jump)
+                                                                    }
+                                                                    Some(Err(e)) => Err(e),
+                                                                    None => Ok(()),
+                                                                };
+                                                                if let Err(e) = result {
                                                                     state.redraw(state.layout.y);
                                                                     print_warning(
                                                                         e,
                                                                         state.layout.y,
                                                                     );
-                                                                    break 'reg;
                                                                 }
+                                                                break;
                                                             }
-                                                            state.move_cursor(state.layout.y);
-                                                            break 'reg;
-                                                        }
-                                                        _ => {
-                                                            clear_current_line();
-                                                            hide_cursor();
-                                                            state.move_cursor(state.layout.y);
-                                                            break 'reg;
+                                                            (KeyCode::Char(c), _) => {
+                                                                query.push(c);
+                                                                picker.update(&query);
+                                                            }
+                                                            _ => continue,
                                                         }
                                                     }
-                                                } else {
-                                                    current_pos += 1;
-                                                    clear_current_line();
-                                                    to_info_line();
-                                                    print!(
-                                                        "\"{}",
-                                                        &command.iter().collect::<String>(),
-                                                    );
-                                                    move_to(current_pos, 2);
                                                 }
+                                                _ => print_warning(
+                                                    "Usage: :bookmark [add {name} | rm {name}]",
+                                                    state.layout.y,
+                                                ),
                                             }
+                                            break 'command;
+                                        }
 
-                                            _ => continue,
+                                        if command == "empty" && commands.len() > 1 {
+                                            //:empty --older-than {age}
+                                            let age = match commands[1..] {
+                                                ["--older-than", age] => {
+                                                    Some(trash::parse_age(age))
+                                                }
+                                                _ => None,
+                                            };
+                                            match age {
+                                                Some(Ok(age)) => {
+                                                    if let Err(e) =
+                                                        state.empty_trash_older_than(age, &screen)
+                                                    {
+                                                        state.redraw(state.layout.y);
+                                                        print_warning(e, state.layout.y);
+                                                    }
+                                                }
+                                                Some(Err(e)) => print_warning(e, state.layout.y),
+                                                None => print_warning(
+                                                    "Usage: :empty [--older-than {age}]",
+                                                    state.layout.y,
+                                                ),
+                                            }
+                                            break 'command;
                                         }
-                                        screen.flush()?;
-                                    }
-                                }
-                            }
 
-                            //command line
-                            KeyCode::Char(':') => {
-                                //In visual mode, this is disabled.
-                                if state.v_start.is_some() {
-                                    continue;
-                                }
-                                delete_pointer();
-                                go_to_info_line_and_reset();
-                                print!("{}", PROMPT_COMMAND_LINE);
-                                show_cursor();
-                                screen.flush()?;
+                                        if command == "grep" {
+                                            //:grep - Search file contents recursively
+                                            if commands.len() == 1 {
+                                                print_warning(
+                                                    "Usage: :grep {pattern}",
+                                                    state.layout.y,
+                                                );
+                                                break 'command;
+                                            }
+                                            let pattern = commands[1..].join(" ");
+                                            go_to_info_line_and_reset();
+                                            print!("Searching...");
+                                            screen.flush()?;
+                                            let matches = grep(
+                                                &state.current_dir,
+                                                &pattern,
+                                                state.ignore_case == Some(true),
+                                                state.layout.show_hidden,
+                                                state.respect_gitignore,
+                                            );
+                                            if matches.is_empty() {
+                                                print_warning("No matches.", state.layout.y);
+                                                break 'command;
+                                            }
 
-                                let mut command: Vec<char> = Vec::new();
-
-                                // express position in terminal
-                                let mut current_pos = INITIAL_POS_COMMAND_LINE;
-                                // express position in Vec<Char>
-                                let mut current_char_pos = 0;
-                                'command: loop {
-                                    if let Event::Key(KeyEvent {
-                                        code,
-                                        modifiers,
-                                        kind: KeyEventKind::Press,
-                                        ..
-                                    }) = event::read()?
-                                    {
-                                        match (code, modifiers) {
-                                            (KeyCode::Char('r'), KeyModifiers::CONTROL) => {
+                                            let mut view = GrepView::new(
+                                                &state.current_dir,
+                                                &pattern,
+                                                matches,
+                                            );
+                                            let half_page =
+                                                (state.layout.terminal_row / 2) as usize;
+                                            go_to_info_line_and_reset();
+                                            view.print(&state.layout);
+                                            screen.flush()?;
+                                            loop {
                                                 if let Event::Key(KeyEvent {
                                                     code,
+                                                    modifiers,
                                                     kind: KeyEventKind::Press,
                                                     ..
                                                 }) = event::read()?
                                                 {
-                                                    if let Some(reg) =
-                                                        state.registers.check_reg(&code)
-                                                    {
-                                                        if !reg.is_empty() {
-                                                            let to_be_inserted = reg
-                                                                .iter()
-                                                                .map(|x| x.file_name.clone())
-                                                                .collect::<Vec<String>>()
-                                                                .join(" ");
-                                                            for c in to_be_inserted.chars() {
-                                                                if let Some(to_be_added) =
-                                                        unicode_width::UnicodeWidthChar::width(c)
-                                                    {
-                                                        if current_pos + to_be_added as u16
-                                                            > state.layout.terminal_column
-                                                        {
-                                                            continue;
-                                                        }
-                                                        command.insert(current_char_pos, c);
-                                                        current_char_pos += 1;
-                                                        current_pos += to_be_added as u16;
-                                                    }
+                                                    match (code, modifiers) {
+                                                        (
+                                                            KeyCode::Char('j'),
+                                                            KeyModifiers::NONE,
+                                                        )
+                                                        | (KeyCode::Down, KeyModifiers::NONE)
+                                                        | (
+                                                            KeyCode::Char('n'),
+                                                            KeyModifiers::CONTROL,
+                                                        ) => view.move_down(1),
+                                                        (
+                                                            KeyCode::Char('k'),
+                                                            KeyModifiers::NONE,
+                                                        )
+                                                        | (KeyCode::Up, KeyModifiers::NONE)
+                                                        | (
+                                                            KeyCode::Char('p'),
+                                                            KeyModifiers::CONTROL,
+                                                        ) => view.move_up(1),
+                                                        (
+                                                            KeyCode::Char('d'),
+                                                            KeyModifiers::CONTROL,
+                                                        ) => view.move_down(half_page),
+                                                        (
+                                                            KeyCode::Char('u'),
+                                                            KeyModifiers::CONTROL,
+                                                        ) => view.move_up(half_page),
+                                                        //Jump to the file of the selected match.
+                                                        (KeyCode::Enter, KeyModifiers::NONE) => {
+                                                            if let Some(m) = view.selected_match() {
+                                                                let path = m.path.clone();
+                                                                if let Err(e) =
+                                                                    state.jump_to_item(&path)
+                                                                {
+                                                                    state.redraw(state.layout.y);
+                                                                    print_warning(
+                                                                        e,
+                                                                        state.layout.y,
+                                                                    );
+                                                                }
                                                             }
-                                                            go_to_info_line_and_reset();
-                                                            print!(
-                                                                "{}{}",
-                                                                PROMPT_COMMAND_LINE,
-                                                                &command.iter().collect::<String>(),
-                                                            );
-                                                            move_to(current_pos, 2);
-                                                            screen.flush()?;
-                                                            continue;
-                                                        } else {
-                                                            continue;
+                                                            break;
                                                         }
-                                                    } else {
-                                                        continue;
+                                                        (KeyCode::Esc, KeyModifiers::NONE)
+                                                        | (
+                                                            KeyCode::Char('q'),
+                                                            KeyModifiers::NONE,
+                                                        ) => {
+                                                            state.redraw(state.layout.y);
+                                                            break;
+                                                        }
+                                                        _ => continue,
                                                     }
+                                                    view.print(&state.layout);
+                                                    screen.flush()?;
                                                 }
                                             }
+                                            break 'command;
+                                        }
 
-                                            (KeyCode::Esc, KeyModifiers::NONE) => {
-                                                state.escape();
-                                                break 'command;
-                                            }
-
-                                            (KeyCode::Left, KeyModifiers::NONE) => {
-                                                move_left_command_line(
-                                                    &mut command,
-                                                    &mut current_char_pos,
-                                                    &mut current_pos,
-                                                );
-                                            }
-
-                                            (KeyCode::Right, KeyModifiers::NONE) => {
-                                                move_right_command_line(
-                                                    &mut command,
-                                                    &mut current_char_pos,
-                                                    &mut current_pos,
-                                                );
-                                            }
-
-                                            (KeyCode::Backspace, KeyModifiers::NONE)
-                                            | (KeyCode::Char('h'), KeyModifiers::CONTROL) => {
-                                                if current_char_pos == 0 {
-                                                    continue;
-                                                };
-                                                let removed = command.remove(current_char_pos - 1);
-                                                if let Some(to_be_removed) =
-                                                    unicode_width::UnicodeWidthChar::width(removed)
-                                                {
-                                                    current_char_pos -= 1;
-                                                    current_pos -= to_be_removed as u16;
-
-                                                    go_to_info_line_and_reset();
-                                                    print!(
-                                                        "{}{}",
-                                                        PROMPT_COMMAND_LINE,
-                                                        &command.iter().collect::<String>(),
-                                                    );
-                                                    move_to(current_pos, 2);
+                                        if commands.len() == 1 {
+                                            match command {
+                                                "q" => {
+                                                    //quit
+                                                    break 'main;
                                                 }
-                                            }
-
-                                            (KeyCode::Char(c), _) => {
-                                                if let Some(to_be_added) =
-                                                    unicode_width::UnicodeWidthChar::width(c)
-                                                {
-                                                    if current_pos + to_be_added as u16
-                                                        > state.layout.terminal_column
+                                                "cd" | "z" => {
+                                                    //go to the home directory
+                                                    let home_dir =
+                                                        dirs::home_dir().ok_or_else(|| {
+                                                            FxError::Dirs(
+                                                                "Cannot read home dir.".to_string(),
+                                                            )
+                                                        })?;
+                                                    if let Err(e) =
+                                                        state.chdir(&home_dir, Move::Jump)
                                                     {
-                                                        continue;
+                                                        print_warning(e, state.layout.y);
                                                     }
-                                                    command.insert(current_char_pos, c);
-                                                    current_char_pos += 1;
-                                                    current_pos += to_be_added as u16;
-
-                                                    go_to_info_line_and_reset();
-                                                    print!(
-                                                        "{}{}",
-                                                        PROMPT_COMMAND_LINE,
-                                                        &command.iter().collect::<String>(),
-                                                    );
-                                                    move_to(current_pos, 2);
+                                                    break 'command;
                                                 }
-                                            }
-
-                                            (KeyCode::Enter, KeyModifiers::NONE) => {
-                                                hide_cursor();
-                                                //Set the command and argument(s).
-                                                let commands: String = command.iter().collect();
-                                                let commands: Vec<&str> =
-                                                    commands.split_whitespace().collect();
-                                                if commands.is_empty() {
-                                                    state.escape();
-                                                    break;
+                                                "e" => {
+                                                    //reload current dir
+                                                    state.keyword = None;
+                                                    state.layout.nums.reset();
+                                                    state.reload(BEGINNING_ROW)?;
+                                                    break 'command;
                                                 }
-                                                let command = commands[0];
-
-                                                if commands.len() == 1 {
-                                                    match command {
-                                                        "q" => {
-                                                            //quit
-                                                            break 'main;
-                                                        }
-                                                        "cd" | "z" => {
-                                                            //go to the home directory
-                                                            let home_dir = dirs::home_dir()
-                                                                .ok_or_else(|| {
-                                                                    FxError::Dirs(
-                                                                        "Cannot read home dir."
-                                                                            .to_string(),
-                                                                    )
-                                                                })?;
-                                                            if let Err(e) =
-                                                                state.chdir(&home_dir, Move::Jump)
-                                                            {
-                                                                print_warning(e, state.layout.y);
-                                                            }
-                                                            break 'command;
-                                                        }
-                                                        "e" => {
-                                                            //reload current dir
-                                                            state.keyword = None;
-                                                            state.layout.nums.reset();
-                                                            state.reload(BEGINNING_ROW)?;
-                                                            break 'command;
-                                                        }
-                                                        "h" => {
-                                                            //show help
-                                                            state.show_help(&screen)?;
-                                                            state.redraw(state.layout.y);
-                                                            break 'command;
-                                                        }
-                                                        "reg" => {
-                                                            //:reg - Show registers
-                                                            if state.layout.is_preview() {
-                                                                state.layout.show_reg();
-                                                                state.redraw(state.layout.y);
-                                                            } else if state.layout.is_reg() {
-                                                                state.escape();
-                                                            } else {
-                                                                state.layout.show_reg();
-                                                                let (new_column, new_row) = state
-                                                                    .layout
-                                                                    .update_column_and_row()?;
-                                                                state.refresh(
-                                                                    new_column,
-                                                                    new_row,
-                                                                    state.layout.y,
-                                                                )?;
-                                                                state.escape();
-                                                            }
-                                                            break 'command;
-                                                        }
-                                                        "trash" => {
-                                                            //move to trash dir
-                                                            state.layout.nums.reset();
-                                                            if let Err(e) = state.chdir(
-                                                                &(state.trash_dir.clone()),
-                                                                Move::Jump,
-                                                            ) {
-                                                                print_warning(e, state.layout.y);
-                                                            }
-                                                            break 'command;
-                                                        }
-                                                        "empty" => {
-                                                            //empty the trash dir
-                                                            state.empty_trash(&screen)?;
-                                                            break 'command;
-                                                        }
-                                                        "config" => {
-                                                            //move to the directory that contains
-                                                            //config path
-                                                            state.layout.nums.reset();
-                                                            if let Some(ref config_path) =
-                                                                state.config_path
-                                                            {
-                                                                if let Err(e) = state.chdir(
-                                                                    config_path
-                                                                        .clone()
-                                                                        .parent()
-                                                                        .unwrap(),
-                                                                    Move::Jump,
-                                                                ) {
-                                                                    print_warning(
-                                                                        e,
-                                                                        state.layout.y,
-                                                                    );
-                                                                }
-                                                            } else {
-                                                                print_warning(
-                                                                    "Cannot find the config path.",
-                                                                    state.layout.y,
-                                                                )
-                                                            }
-                                                            break 'command;
-                                                        }
-                                                        _ => {}
+                                                "h" => {
+                                                    //show help
+                                                    state.show_help(&screen)?;
+                                                    state.redraw(state.layout.y);
+                                                    break 'command;
+                                                }
+                                                "reg" => {
+                                                    //:reg - Show registers
+                                                    state.toggle_side_list(Side::Reg)?;
+                                                    break 'command;
+                                                }
+                                                "marks" => {
+                                                    //:marks - Show marks
+                                                    state.toggle_side_list(Side::Marks)?;
+                                                    break 'command;
+                                                }
+                                                "jumps" => {
+                                                    //:jumps - Show the jumplist
+                                                    state.toggle_side_list(Side::Jumps)?;
+                                                    break 'command;
+                                                }
+                                                "trash" => {
+                                                    //:trash - Browse the trash
+                                                    if let Err(e) = state.browse_trash(&screen) {
+                                                        state.redraw(state.layout.y);
+                                                        print_warning(e, state.layout.y);
                                                     }
-                                                } else if commands.len() == 2 && command == "cd" {
-                                                    if let Ok(target) =
-                                                        std::path::Path::new(commands[1])
-                                                            .normalize()
+                                                    break 'command;
+                                                }
+                                                "empty" => {
+                                                    //empty the trash dir
+                                                    state.empty_trash(&screen)?;
+                                                    break 'command;
+                                                }
+                                                "config" => {
+                                                    //move to the directory that contains
+                                                    //config path
+                                                    state.layout.nums.reset();
+                                                    if let Some(ref config_path) = state.config_path
                                                     {
-                                                        if target.exists() {
-                                                            if let Err(e) = state.chdir(
-                                                                &target.into_path_buf(),
-                                                                Move::Jump,
-                                                            ) {
-                                                                print_warning(e, state.layout.y);
-                                                            }
-                                                            break 'command;
-                                                        } else {
-                                                            print_warning(
-                                                                "Path does not exist.",
-                                                                state.layout.y,
-                                                            );
-                                                            break 'command;
+                                                        if let Err(e) = state.chdir(
+                                                            config_path.clone().parent().unwrap(),
+                                                            Move::Jump,
+                                                        ) {
+                                                            print_warning(e, state.layout.y);
                                                         }
                                                     } else {
                                                         print_warning(
-                                                            "Path does not exist.",
+                                                            "Cannot find the config path.",
                                                             state.layout.y,
-                                                        );
-                                                        break 'command;
+                                                        )
                                                     }
+                                                    break 'command;
                                                 }
-
-                                                //Execute command as is
-                                                let mut err: Option<&str> = None;
-                                                execute!(screen, EnterAlternateScreen)?;
-                                                if std::env::set_current_dir(&state.current_dir)
-                                                    .is_err()
-                                                {
-                                                    err =
-                                                        Some("Changing current directory failed.");
-                                                } else if let Ok(sh) = std::env::var("SHELL") {
-                                                    if std::process::Command::new(&sh)
-                                                        .arg("-c")
-                                                        .arg(commands.join(" "))
-                                                        .status()
-                                                        .is_err()
+                                                _ => {}
+                                            }
+                                        } else if commands.len() == 2 && command == "cd" {
+                                            if let Ok(target) =
+                                                std::path::Path::new(commands[1]).normalize()
+                                            {
+                                                if target.exists() {
+                                                    if let Err(e) = state
+                                                        .chdir(&target.into_path_buf(), Move::Jump)
                                                     {
-                                                        err = Some("Command execution failed.");
+                                                        print_warning(e, state.layout.y);
                                                     }
-                                                } else if std::process::Command::new(command)
-                                                    .args(&commands[1..])
-                                                    .status()
-                                                    .is_err()
-                                                {
-                                                    err = Some("Command execution failed.");
-                                                }
-
-                                                execute!(screen, EnterAlternateScreen)?;
-                                                hide_cursor();
-                                                info!("SHELL: {:?}", commands);
-                                                state.reload(state.layout.y)?;
-                                                if let Some(e) = err {
-                                                    print_warning(e, state.layout.y);
+                                                    break 'command;
+                                                } else {
+                                                    print_warning(
+                                                        "Path does not exist.",
+                                                        state.layout.y,
+                                                    );
+                                                    break 'command;
                                                 }
+                                            } else {
+                                                print_warning(
+                                                    "Path does not exist.",
+                                                    state.layout.y,
+                                                );
                                                 break 'command;
                                             }
+                                        }
+
+                                        //Execute command as is
+                                        let mut err: Option<&str> = None;
+                                        execute!(screen, EnterAlternateScreen)?;
+                                        if std::env::set_current_dir(&state.current_dir).is_err() {
+                                            err = Some("Changing current directory failed.");
+                                        } else if let Ok(sh) = std::env::var("SHELL") {
+                                            if std::process::Command::new(&sh)
+                                                .arg("-c")
+                                                .arg(expand_placeholders(
+                                                    &commands.join(" "),
+                                                    &values,
+                                                    true,
+                                                ))
+                                                .envs(envs)
+                                                .status()
+                                                .is_err()
+                                            {
+                                                err = Some("Command execution failed.");
+                                            }
+                                        } else if std::process::Command::new(command)
+                                            .args(expand_args(&commands[1..], &values))
+                                            .envs(envs)
+                                            .status()
+                                            .is_err()
+                                        {
+                                            err = Some("Command execution failed.");
+                                        }
 
-                                            _ => continue,
+                                        execute!(screen, EnterAlternateScreen)?;
+                                        hide_cursor();
+                                        info!("SHELL: {:?}", commands);
+                                        state.reload(state.layout.y)?;
+                                        if let Some(e) = err {
+                                            print_warning(e, state.layout.y);
                                         }
-                                        screen.flush()?;
+                                        break 'command;
                                     }
+
+                                    _ => continue,
                                 }
+                                screen.flush()?;
                             }
+                        }
+                    }
 
-                            //undo
-                            KeyCode::Char('u') => {
-                                //In visual mode, this is disabled.
-                                if state.v_start.is_some() {
-                                    continue;
-                                }
-                                let op_len = state.operations.op_list.len();
-                                if op_len <= state.operations.pos {
-                                    print_info("No operations left.", state.layout.y);
-                                    continue;
-                                }
-                                if let Some(op) = state
-                                    .operations
-                                    .op_list
-                                    .get(op_len - state.operations.pos - 1)
-                                {
-                                    let op = op.clone();
-                                    if let Err(e) = state.undo(&op) {
-                                        print_warning(e, state.layout.y);
-                                        continue;
-                                    }
+                    //repeat the last change
+                    Action::Repeat => {
+                        //In visual mode, this is disabled.
+                        if state.v_start.is_some() {
+                            continue;
+                        }
+                        if state.is_ro {
+                            print_warning("Cannot change items in this directory.", state.layout.y);
+                            continue;
+                        }
+                        if let Err(e) = state.repeat_change(count, &mut screen) {
+                            print_warning(e, state.layout.y);
+                            continue;
+                        }
+                        state.move_cursor(state.layout.y);
+                    }
 
-                                    let new_len = state.list.len();
-                                    if new_len == 0 {
-                                        state.layout.nums.reset();
-                                        state.move_cursor(BEGINNING_ROW);
-                                    } else if state.layout.nums.index > new_len - 1 {
-                                        let new_y = state.layout.y
-                                            - (state.layout.nums.index - (new_len - 1)) as u16;
-                                        state.layout.nums.index = new_len - 1;
-                                        state.move_cursor(new_y)
-                                    } else {
-                                        state.move_cursor(state.layout.y);
-                                    }
-                                }
+                    //undo
+                    Action::Undo => {
+                        //In visual mode, this is disabled.
+                        if state.v_start.is_some() {
+                            continue;
+                        }
+                        let op_len = state.operations.op_list.len();
+                        if op_len <= state.operations.pos {
+                            print_info("No operations left.", state.layout.y);
+                            continue;
+                        }
+                        if let Some(op) = state
+                            .operations
+                            .op_list
+                            .get(op_len - state.operations.pos - 1)
+                        {
+                            let op = op.clone();
+                            if let Err(e) = state.undo(&op) {
+                                print_warning(e, state.layout.y);
+                                continue;
                             }
 
-                            //exit by ZZ
-                            KeyCode::Char('Z') => {
-                                //In visual mode, this is disabled.
-                                if state.v_start.is_some() {
-                                    continue;
-                                }
-                                delete_pointer();
-                                go_to_info_line_and_reset();
-                                print!("Z");
-                                show_cursor();
-                                screen.flush()?;
+                            let new_len = state.list.len();
+                            if new_len == 0 {
+                                state.layout.nums.reset();
+                                state.move_cursor(BEGINNING_ROW);
+                            } else if state.layout.nums.index > new_len - 1 {
+                                let new_y = state.layout.y
+                                    - (state.layout.nums.index - (new_len - 1)) as u16;
+                                state.layout.nums.index = new_len - 1;
+                                state.move_cursor(new_y)
+                            } else {
+                                state.move_cursor(state.layout.y);
+                            }
+                        }
+                    }
 
-                                let mut next_key: Event = event::read()?;
-                                // ignore exactly one keypress Release after a Z is entered
-                                if let Event::Key(KeyEvent {
-                                    kind: KeyEventKind::Release,
-                                    ..
-                                }) = next_key
-                                {
-                                    next_key = event::read()?;
-                                }
+                    //Run the command defined in `commands` of the config file
+                    Action::UserCommand(name) => {
+                        let command = match state.user_commands.get(&name) {
+                            Some(command) => command.clone(),
+                            None => continue,
+                        };
+
+                        if command.background.unwrap_or(false) {
+                            if let Err(e) = state.spawn_user_command(&command) {
+                                print_warning(e, state.layout.y);
+                                continue;
+                            }
+                            state.reset_selection();
+                            if command.reload.unwrap_or(false) {
+                                state.reload(state.layout.y)?;
+                            } else {
+                                state.redraw(state.layout.y);
+                            }
+                            print_info(format!("{}: Started in background.", name), state.layout.y);
+                            continue;
+                        }
 
+                        //Show the output in the main screen.
+                        execute!(screen, LeaveAlternateScreen)?;
+                        leave_raw_mode();
+                        let result = state.run_user_command(&command);
+                        if command.wait.unwrap_or(false) {
+                            print!("\nPress any key to continue...");
+                            screen.flush()?;
+                            enter_raw_mode();
+                            loop {
                                 if let Event::Key(KeyEvent {
-                                    code,
                                     kind: KeyEventKind::Press,
                                     ..
-                                }) = next_key
+                                }) = event::read()?
                                 {
-                                    match code {
-                                        KeyCode::Char('Q') => {
-                                            if state.match_vim_exit_behavior
-                                                || state.export_lwd().is_ok()
-                                            {
-                                                break 'main;
-                                            }
-                                        }
-
-                                        KeyCode::Char('Z') => {
-                                            if !state.match_vim_exit_behavior
-                                                || state.export_lwd().is_ok()
-                                            {
-                                                break 'main;
-                                            }
-                                        }
-
-                                        _ => {
-                                            state.escape();
-                                        }
-                                    }
+                                    break;
                                 }
                             }
-
-                            //If input does not match any of the defined keys, ignore it.
-                            _ => {
-                                continue;
+                        } else {
+                            enter_raw_mode();
+                        }
+                        execute!(screen, EnterAlternateScreen)?;
+                        hide_cursor();
+
+                        state.reset_selection();
+                        if command.reload.unwrap_or(false) {
+                            state.reload(state.layout.y)?;
+                        } else {
+                            state.redraw(state.layout.y);
+                        }
+                        match result {
+                            Ok(status) if status.success() => {
+                                print_info(format!("{}: Done.", name), state.layout.y);
                             }
+                            Ok(status) => {
+                                print_warning(format!("{}: {}", name, status), state.layout.y);
+                            }
+                            Err(e) => print_warning(e, state.layout.y),
                         }
                     }
-                    //Other modifiers disable commands when pressed.
-                    _ => {
-                        continue;
+
+                    //exit by ZZ
+                    Action::Exit => {
+                        //In visual mode, this is disabled.
+                        if state.v_start.is_some() {
+                            continue;
+                        }
+                        break 'main;
+                    }
+
+                    //cd into the last working directory and exit by ZQ
+                    Action::ExitWithLwd => {
+                        //In visual mode, this is disabled.
+                        if state.v_start.is_some() {
+                            continue;
+                        }
+                        if state.export_lwd().is_ok() {
+                            break 'main;
+                        }
                     }
                 }
                 //If you use kitty, clear the screen by the escape sequence or the previewed image remains.
@@ -2586,7 +3586,7 @@ fn _run(mut state: State, session_path: PathBuf) -> Result<(), FxError> {
                     panic!("Error: Too small terminal size (less than 4 rows). Please restart.");
                 };
 
-                if state.layout.is_preview() || state.layout.is_reg() {
+                if state.layout.has_side() {
                     let new_column = match state.layout.split {
                         Split::Vertical => column >> 1,
                         Split::Horizontal => column,
@@ -2620,12 +3620,23 @@ fn _run(mut state: State, session_path: PathBuf) -> Result<(), FxError> {
         }
     }
 
-    //Save session, restore screen state and cursor
-    state.write_session(session_path)?;
+    //Restore screen state and cursor first, so that a failure to save does not leave them broken.
     execute!(screen, LeaveAlternateScreen)?;
     write!(screen, "{}", RestorePosition)?;
     screen.flush()?;
 
+    //Save session and history. A failure is logged without skipping the rest.
+    let results = [
+        state.write_session(data_files.session),
+        state.history.write(&data_files.history),
+        state.frecency.write(&data_files.frecency),
+        state.jumplist.write(&data_files.jumplist),
+        reg_file.save(&state.registers),
+    ];
+    for e in results.into_iter().filter_map(Result::err) {
+        error!("{}", e);
+    }
+
     info!("===FINISH===");
     Ok(())
 }
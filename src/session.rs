@@ -1,7 +1,7 @@
 use super::layout::Split;
 use serde::{Deserialize, Serialize};
 use std::fs::read_to_string;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 #[allow(dead_code)]
 pub const SESSION_EXAMPLE: &str = "sort_by = \"Name\"
@@ -16,9 +16,13 @@ pub struct Session {
     pub show_hidden: bool,
     pub preview: Option<bool>,
     pub split: Option<Split>,
+    /// Directory and item under the cursor at the end of the session,
+    /// restored on startup if `restore_last_dir` is enabled.
+    pub last_dir: Option<PathBuf>,
+    pub last_item: Option<String>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
 pub enum SortKey {
     #[default]
     Name,
@@ -34,6 +38,8 @@ pub fn read_session(session_path: &Path) -> Session {
                 show_hidden: true,
                 preview: Some(false),
                 split: Some(Split::Vertical),
+                last_dir: None,
+                last_item: None,
             },
         },
         Err(_) => Session {
@@ -41,6 +47,8 @@ pub fn read_session(session_path: &Path) -> Session {
             show_hidden: true,
             preview: Some(false),
             split: Some(Split::Vertical),
+            last_dir: None,
+            last_item: None,
         },
     }
 }
@@ -1,24 +1,35 @@
+use super::bookmarks::Bookmarks;
+use super::completion::complete_zoxide;
 use super::config::*;
 use super::errors::FxError;
+use super::frecency::{self, Frecency};
 use super::functions::*;
 use super::help::HELP;
+use super::history::History;
 use super::jumplist::*;
+use super::keymap::{Action, Keymap};
 use super::layout::*;
 use super::magic_image;
 use super::magic_packed;
+use super::marks::Marks;
+use super::matcher::*;
 use super::nums::*;
 use super::op::*;
+use super::options::*;
+use super::repeat::*;
 use super::session::*;
 use super::term::*;
+use super::trash::{self, TrashBackend, TrashView};
 
 use chrono::prelude::*;
 use crossterm::event::KeyEventKind;
-use crossterm::event::{Event, KeyCode, KeyEvent};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
 use crossterm::style::Stylize;
 use log::info;
 use normpath::PathExt;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::env;
 use std::ffi::OsStr;
 use std::fmt::Write as _;
@@ -41,6 +52,7 @@ use std::os::unix::fs::PermissionsExt;
 
 pub const BEGINNING_ROW: u16 = 3;
 pub const EMPTY_WARNING: &str = "Are you sure to empty the trash directory? (if yes: y)";
+const PURGE_WARNING: &str = "Are you sure to delete them permanently? (if yes: y)";
 
 const MAX_SIZE_TO_PREVIEW: u64 = 1_000_000_000;
 const MAX_SIZE_TO_PREVIEW_TEXT: u64 = 1_000_000;
@@ -50,25 +62,55 @@ pub struct State {
     pub list: Vec<ItemInfo>,
     pub current_dir: PathBuf,
     pub trash_dir: PathBuf,
+    pub trash_backend: TrashBackend,
+    /// Directory of `.trashinfo` files, which record where the trashed items came from.
+    pub trash_info_dir: PathBuf,
+    /// Items deleted more than this many days ago are purged on startup.
+    pub trash_max_age_days: Option<u64>,
+    /// The oldest items are purged on startup while the trash is larger than this.
+    pub trash_max_size: Option<u64>,
     pub config_path: Option<PathBuf>,
     pub lwd_file: Option<PathBuf>,
-    pub match_vim_exit_behavior: bool,
     pub has_zoxide: bool,
+    /// Use the built-in frecency database for `z` even if zoxide is installed.
+    pub builtin_z: bool,
+    pub frecency: Frecency,
     pub default: String,
     pub commands: Option<BTreeMap<String, String>>,
     pub ignore_case: Option<bool>,
+    pub smart_case: bool,
+    pub search_mode: SearchMode,
+    pub respect_gitignore: bool,
+    /// Command to copy text to the clipboard in addition to OSC 52.
+    pub clipboard_command: Option<String>,
+    pub keymap: Keymap,
+    pub user_commands: BTreeMap<String, UserCommand>,
     pub registers: Registers,
+    /// Items cut by `X`, to be moved by `P`.
+    pub cut: Vec<ItemBuffer>,
     pub operations: Operation,
+    pub last_change: Option<Change>,
     pub jumplist: JumpList,
+    pub marks: Marks,
+    pub bookmarks: Bookmarks,
+    pub history: History,
+    /// Start in the directory where the last session ended.
+    pub restore_last_dir: bool,
+    /// Name of the item last under the cursor in each directory visited in the session.
+    pub cursor_memory: HashMap<PathBuf, String>,
     pub c_memo: Vec<StateMemo>,
     pub p_memo: Vec<StateMemo>,
     pub keyword: Option<String>,
+    /// Keyword of the filter mode. Items that do not contain it are hidden from the list.
+    pub filter: Option<String>,
     pub layout: Layout,
     pub v_start: Option<usize>,
+    /// Items selected by `<Space>`, kept across directories.
+    pub selection: BTreeSet<PathBuf>,
     pub is_ro: bool,
 }
 
-#[derive(Debug, Default)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 pub struct Registers {
     pub unnamed: Vec<ItemBuffer>,
     pub zero: Vec<ItemBuffer>,
@@ -77,6 +119,21 @@ pub struct Registers {
 }
 
 impl Registers {
+    /// Drop items whose paths no longer exist, and registers that become empty.
+    pub fn prune(&mut self) {
+        let exists = |b: &ItemBuffer| b.file_path.symlink_metadata().is_ok();
+        self.unnamed.retain(exists);
+        self.zero.retain(exists);
+        for reg in self.numbered.iter_mut() {
+            reg.retain(exists);
+        }
+        self.numbered.retain(|reg| !reg.is_empty());
+        for reg in self.named.values_mut() {
+            reg.retain(exists);
+        }
+        self.named.retain(|_, reg| !reg.is_empty());
+    }
+
     /// Append ItemBuffer to named register.
     pub fn append_item(&mut self, items: &[ItemBuffer], reg: char) -> usize {
         let v = self.named.get(&reg);
@@ -192,7 +249,7 @@ impl Registers {
 
 /// To avoid cost copying ItemInfo, use ItemBuffer
 /// when tinkering with register or multiple renaming.
-#[derive(Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct ItemBuffer {
     pub file_type: FileType,
     pub file_name: String,
@@ -207,6 +264,23 @@ impl ItemBuffer {
             file_path: item.file_path.clone(),
         }
     }
+
+    /// Read the item from the path, which may be outside of the current directory.
+    pub fn from_path(path: &std::path::Path) -> Option<Self> {
+        let metadata = fs::symlink_metadata(path).ok()?;
+        let file_type = if metadata.file_type().is_symlink() {
+            FileType::Symlink
+        } else if metadata.is_dir() {
+            FileType::Directory
+        } else {
+            FileType::File
+        };
+        Some(ItemBuffer {
+            file_type,
+            file_name: path.file_name()?.to_string_lossy().to_string(),
+            file_path: path.to_path_buf(),
+        })
+    }
 }
 
 #[derive(Default, Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
@@ -228,7 +302,7 @@ pub struct ItemInfo {
     pub is_dirty: bool,
 }
 
-#[derive(Default, Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Deserialize, Serialize, Default, Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum FileType {
     Directory,
     #[default]
@@ -249,27 +323,39 @@ impl State {
                 (None, Config::default())
             }
         };
-        let mut state = State::default();
-        state.set_config(config.clone());
-
-        let has_zoxide = check_zoxide();
-
-        Ok(State {
+        let mut state = State {
             config_path,
-            has_zoxide,
-            layout: Layout::new(session_path, config)?,
-            ..state
-        })
+            has_zoxide: check_zoxide(),
+            layout: Layout::new(&config)?,
+            ..Default::default()
+        };
+        state.set_config(config);
+        //Restore the options saved in the session file.
+        for value in OptionValue::from_session(&read_session(session_path)) {
+            state.apply_option(value);
+        }
+        Ok(state)
     }
 
     /// Set configuration from config file.
     pub fn set_config(&mut self, config: Config) {
+        for value in OptionValue::from_config(&config) {
+            self.apply_option(value);
+        }
+        self.keymap = Keymap::new(&config);
+        self.user_commands = config.commands.clone().unwrap_or_default();
         self.default = config
             .default
             .unwrap_or_else(|| env::var("EDITOR").unwrap_or_default());
-        self.match_vim_exit_behavior = config.match_vim_exit_behavior.unwrap_or_default();
         self.commands = to_extension_map(&config.exec);
-        self.ignore_case = config.ignore_case;
+        self.builtin_z = config.builtin_z.unwrap_or(false);
+        self.restore_last_dir = config.restore_last_dir.unwrap_or(false);
+        self.clipboard_command = config.clipboard_command;
+        self.trash_backend = config.trash_backend.unwrap_or_default();
+        self.trash_max_age_days = config.trash_max_age_days;
+        self.trash_max_size = config
+            .trash_max_size
+            .and_then(|x| trash::parse_size(&x).ok());
         let colors = config.color.unwrap_or_default();
         self.layout.colors = colors;
     }
@@ -475,6 +561,38 @@ impl State {
         Ok(())
     }
 
+    /// Delete `count` items from the cursor.
+    pub fn delete_count(
+        &mut self,
+        count: usize,
+        reg: Option<char>,
+        append: bool,
+        screen: &mut Stdout,
+    ) -> Result<(), FxError> {
+        if count <= 1 {
+            return self.delete(reg, append, screen);
+        }
+        let start = self.layout.nums.index;
+        let end = start.saturating_add(count).min(self.list.len());
+        for (i, item) in self.list.iter_mut().enumerate() {
+            item.selected = i >= start && i < end;
+        }
+        self.delete_in_visual(reg, append, screen)
+    }
+
+    /// Yank `count` items from the cursor, and return the number of yanked items.
+    pub fn yank_count(&mut self, count: usize, reg: Option<char>, append: bool) -> usize {
+        let start = self.layout.nums.index;
+        let items: Vec<ItemBuffer> = self
+            .list
+            .iter()
+            .skip(start)
+            .take(count)
+            .map(ItemBuffer::new)
+            .collect();
+        self.registers.yank_item(&items, reg, append)
+    }
+
     /// Delete items in visual mode.
     pub fn delete_in_visual(
         &mut self,
@@ -563,27 +681,130 @@ impl State {
             clear_current_line();
             print!("{}", display_count(i, total_selected));
 
-            match item.file_type {
-                FileType::Directory => match self.remove_dir(item, new_op) {
-                    Err(e) => {
-                        return Err(e);
-                    }
-                    Ok(path) => dest.push(path),
-                },
-                FileType::File | FileType::Symlink => match self.remove_file(item, new_op) {
-                    Err(e) => {
-                        return Err(e);
+            if new_op && self.trash_backend == TrashBackend::Freedesktop {
+                dest.push(ItemBuffer {
+                    file_type: item.file_type,
+                    file_name: item.file_name.clone(),
+                    file_path: trash::trash_item(&item.file_path)?,
+                });
+                continue;
+            }
+
+            //Reserve a unique name in the trash, so that items with the same name
+            //from different directories do not replace each other.
+            //The original path is recorded for the trash browser at the same time.
+            let reserved = if new_op {
+                Some(trash::reserve_name(
+                    &self.trash_dir,
+                    &self.trash_info_dir,
+                    &trash_name(&item.file_name),
+                    &item.file_path,
+                )?)
+            } else {
+                None
+            };
+            let to = reserved.as_ref().map(|(to, _)| to.clone());
+            let trashed = match item.file_type {
+                FileType::Directory => self.remove_dir(item, to).map(Some),
+                FileType::File | FileType::Symlink => self.remove_file(item, to),
+            };
+            //Release the name if nothing was moved to the trash.
+            if let Some((_, info)) = &reserved {
+                if !matches!(trashed, Ok(Some(_))) {
+                    let _ = std::fs::remove_file(info);
+                }
+            }
+            if let Some(trashed) = trashed? {
+                dest.push(trashed);
+            }
+        }
+
+        Ok((src.to_vec(), dest))
+    }
+
+    /// Delete the items selected by `<Space>`, which may be in different directories.
+    /// Since undo puts items back into a single directory, an operation is pushed per directory.
+    pub fn delete_selection(
+        &mut self,
+        reg: Option<char>,
+        append: bool,
+        screen: &mut Stdout,
+    ) -> Result<(), FxError> {
+        hide_cursor();
+        print_info("DELETE: Processing...", self.layout.y);
+        screen.flush()?;
+        let start = Instant::now();
+
+        let mut by_dir: BTreeMap<PathBuf, Vec<ItemBuffer>> = BTreeMap::new();
+        for item in self.selection_buffers() {
+            if let Some(parent) = item.file_path.parent() {
+                by_dir.entry(parent.to_path_buf()).or_default().push(item);
+            }
+        }
+        let mut deleted = vec![];
+        let mut result = Ok(());
+        for (dir, items) in by_dir {
+            match self.move_to_trash(&items, true) {
+                Ok((src, dest)) => {
+                    if !dest.is_empty() {
+                        self.operations.branch();
+                        self.operations.push(OpKind::Delete(DeletedFiles {
+                            trash: dest.clone(),
+                            original: src,
+                            dir,
+                        }));
+                        deleted.extend(dest);
                     }
-                    Ok(path) => {
-                        if let Some(p) = path {
-                            dest.push(p);
-                        }
+                }
+                //Keep going, so that the items in other directories are deleted and recorded.
+                Err(e) => {
+                    if result.is_ok() {
+                        result = Err(e);
                     }
-                },
+                }
             }
         }
+        self.yank_deleted(&deleted, reg, append);
+        self.selection.clear();
 
-        Ok((src.to_vec(), dest))
+        self.update_list()?;
+        if self.layout.nums.index >= self.list.len() {
+            self.layout.nums.reset();
+            self.layout.y = BEGINNING_ROW;
+        }
+        self.redraw(self.layout.y);
+        result?;
+        let duration = duration_to_string(start.elapsed());
+        let delete_message = if deleted.len() == 1 {
+            format!("1 item deleted. [{}]", duration)
+        } else {
+            format!("{} items deleted [{}]", deleted.len(), duration)
+        };
+        print_info(delete_message, self.layout.y);
+        Ok(())
+    }
+
+    /// Add deleted items (in the trash directory) to registers.
+    fn yank_deleted(&mut self, dest: &[ItemBuffer], reg: Option<char>, append: bool) {
+        if dest.is_empty() {
+            return;
+        }
+        //save to unnamed reg
+        self.registers.unnamed = dest.to_vec();
+        //If numbered registers is full, pop_back first
+        if self.registers.numbered.len() == 9 {
+            self.registers.numbered.pop_back();
+        }
+        //save to "1
+        self.registers.numbered.push_front(dest.to_vec());
+
+        if let Some(reg) = reg {
+            if append {
+                self.registers.append_item(dest, reg);
+            } else {
+                self.registers.named.insert(reg, dest.to_vec());
+            }
+        }
     }
 
     /// Add dest to register, and item infomation to operation
@@ -595,22 +816,7 @@ impl State {
         append: bool,
     ) -> Result<usize, FxError> {
         if !dest.is_empty() {
-            //save to unnamed reg
-            self.registers.unnamed = dest.to_vec();
-            //If numbered registers is full, pop_back first
-            if self.registers.numbered.len() == 9 {
-                self.registers.numbered.pop_back();
-            }
-            //save to "1
-            self.registers.numbered.push_front(dest.to_vec());
-
-            if let Some(reg) = reg {
-                if append {
-                    self.registers.append_item(dest, reg);
-                } else {
-                    self.registers.named.insert(reg, dest.to_vec());
-                }
-            }
+            self.yank_deleted(dest, reg, append);
 
             //Update operations value
             self.operations.branch();
@@ -625,13 +831,39 @@ impl State {
         Ok(dest.len())
     }
 
-    /// Move single directory recursively to trash directory.
-    fn remove_dir(&mut self, item: &ItemBuffer, new_op: bool) -> Result<ItemBuffer, FxError> {
+    /// Move single directory to trash directory.
+    /// It is renamed at once, or across devices, copied recursively and then removed.
+    fn remove_dir(
+        &mut self,
+        item: &ItemBuffer,
+        to: Option<PathBuf>,
+    ) -> Result<ItemBuffer, FxError> {
         let mut base: usize = 0;
         let mut trash_path: std::path::PathBuf = PathBuf::new();
         let mut target: PathBuf;
 
-        if new_op {
+        if let Some(to) = to {
+            trash_path = to;
+            //rename(2) replaces an existing file or empty directory.
+            if trash_path.symlink_metadata().is_ok() {
+                return Err(FxError::Io(format!(
+                    "Already exists in the trash: {}",
+                    trash_path.display()
+                )));
+            }
+            //Rename if possible, which is much faster than copying.
+            match std::fs::rename(&item.file_path, &trash_path) {
+                Ok(_) => {
+                    return Ok(ItemBuffer {
+                        file_type: item.file_type,
+                        file_name: item.file_name.clone(),
+                        file_path: trash_path,
+                    });
+                }
+                Err(e) if is_cross_device(&e) => {}
+                Err(e) => return Err(e.into()),
+            }
+
             let len = walkdir::WalkDir::new(&item.file_path).into_iter().count();
             let unit = len / 5;
             for (i, entry) in walkdir::WalkDir::new(&item.file_path)
@@ -653,17 +885,7 @@ impl State {
                 let entry_path = entry.path();
                 if i == 0 {
                     base = entry_path.iter().count();
-
-                    let mut trash_name = chrono::Local::now().timestamp().to_string();
-                    trash_name.push('_');
-                    let file_name = entry.file_name().to_str();
-                    if file_name.is_none() {
-                        return Err(FxError::Encode);
-                    }
-                    trash_name.push_str(file_name.unwrap());
-                    trash_path = self.trash_dir.join(&trash_name);
-                    std::fs::create_dir(self.trash_dir.join(&trash_path))?;
-
+                    std::fs::create_dir(&trash_path)?;
                     continue;
                 } else {
                     if entry.file_type().is_symlink() && !entry_path.exists() {
@@ -708,11 +930,10 @@ impl State {
     fn remove_file(
         &mut self,
         item: &ItemBuffer,
-        new_op: bool,
+        to: Option<PathBuf>,
     ) -> Result<Option<ItemBuffer>, FxError> {
         //prepare from and to for copy
         let from = &item.file_path;
-        let mut to = PathBuf::new();
 
         if item.file_type == FileType::Symlink && !from.exists() {
             match std::fs::remove_file(from) {
@@ -720,15 +941,29 @@ impl State {
                 Err(_) => Err(FxError::RemoveItem(from.to_owned())),
             }
         } else {
-            let mut rename = Local::now().timestamp().to_string();
-            rename.push('_');
-            rename.push_str(&item.file_name);
-
-            if new_op {
-                to = self.trash_dir.join(&rename);
+            if let Some(to) = &to {
+                //rename(2) and copy replace an existing file.
+                if to.symlink_metadata().is_ok() {
+                    return Err(FxError::Io(format!(
+                        "Already exists in the trash: {}",
+                        to.display()
+                    )));
+                }
+                //Rename if possible, which is much faster than copying.
+                match std::fs::rename(from, to) {
+                    Ok(_) => {
+                        return Ok(Some(ItemBuffer {
+                            file_type: item.file_type,
+                            file_name: item.file_name.clone(),
+                            file_path: to.clone(),
+                        }));
+                    }
+                    Err(e) if is_cross_device(&e) => {}
+                    Err(e) => return Err(e.into()),
+                }
 
                 //copy
-                if std::fs::copy(from, &to).is_err() {
+                if std::fs::copy(from, to).is_err() {
                     return Err(FxError::PutItem(from.to_owned()));
                 }
             }
@@ -741,7 +976,7 @@ impl State {
             Ok(Some(ItemBuffer {
                 file_type: item.file_type,
                 file_name: item.file_name.clone(),
-                file_path: to,
+                file_path: to.unwrap_or_default(),
             }))
         }
     }
@@ -783,26 +1018,8 @@ impl State {
         targets: &[ItemBuffer],
         target_dir: Option<PathBuf>,
     ) -> Result<usize, FxError> {
-        //make HashSet<String> of file_name
-        let mut name_set = BTreeSet::new();
-        match &target_dir {
-            None => {
-                for item in self.list.iter() {
-                    name_set.insert(item.file_name.clone());
-                }
-            }
-            Some(path) => {
-                for entry in std::fs::read_dir(path)? {
-                    let entry = entry?;
-                    name_set.insert(
-                        entry
-                            .file_name()
-                            .into_string()
-                            .unwrap_or_else(|_| "".to_string()),
-                    );
-                }
-            }
-        }
+        //Read the directory, as the list does not include the items hidden or filtered out.
+        let mut name_set = read_names(target_dir.as_ref().unwrap_or(&self.current_dir))?;
 
         //prepare for operations.push
         let mut put_v = Vec::new();
@@ -918,7 +1135,7 @@ impl State {
         Ok(target)
     }
 
-    /// Undo operations (put/delete/rename)
+    /// Undo operations (put/delete/rename/move)
     pub fn undo(&mut self, op: &OpKind) -> Result<(), FxError> {
         match op {
             OpKind::Rename(op) => {
@@ -953,12 +1170,22 @@ impl State {
                 self.list_up();
                 print_info("UNDONE: DELETE", BEGINNING_ROW);
             }
+            OpKind::Move(op) => {
+                for (original, new) in op.iter().rev() {
+                    move_item(new, original)?;
+                }
+                self.operations.pos += 1;
+                self.update_list()?;
+                self.clear_and_show_headline();
+                self.list_up();
+                print_info("UNDONE: MOVE", BEGINNING_ROW);
+            }
         }
         relog(op, true);
         Ok(())
     }
 
-    /// Redo operations (put/delete/rename)
+    /// Redo operations (put/delete/rename/move)
     pub fn redo(&mut self, op: &OpKind) -> Result<(), FxError> {
         match op {
             OpKind::Rename(op) => {
@@ -987,11 +1214,172 @@ impl State {
                 self.list_up();
                 print_info("REDONE DELETE", BEGINNING_ROW);
             }
+            OpKind::Move(op) => {
+                for (original, new) in op {
+                    move_item(original, new)?;
+                }
+                self.operations.pos -= 1;
+                self.update_list()?;
+                self.clear_and_show_headline();
+                self.list_up();
+                print_info("REDONE: MOVE", BEGINNING_ROW);
+            }
         }
         relog(op, false);
         Ok(())
     }
 
+    /// Mark items to be moved by `P`: the visual selection, the items selected by `<Space>`,
+    /// or the current item. Return the number of the items.
+    pub fn cut(&mut self) -> usize {
+        self.cut = self
+            .selected_paths()
+            .iter()
+            .filter_map(|p| ItemBuffer::from_path(p))
+            .collect();
+        self.cut.len()
+    }
+
+    /// Move the cut items to the current directory, without using the trash.
+    /// Items are renamed like `put` if the name already exists.
+    /// Return the number of moved items.
+    pub fn move_cut_items(&mut self) -> Result<usize, FxError> {
+        if self.is_ro {
+            return Err(FxError::Io("Cannot move into this directory.".to_owned()));
+        }
+        //Read the directory, as the list does not include the items hidden or filtered out.
+        let mut name_set = read_names(&self.current_dir)?;
+        let mut moved = Vec::new();
+        let mut result = Ok(());
+        let items = std::mem::take(&mut self.cut);
+        for (i, item) in items.iter().enumerate() {
+            if item.file_path.parent() == Some(self.current_dir.as_path()) {
+                continue;
+            }
+            //Moving a directory into itself is not allowed.
+            if self.current_dir.starts_with(&item.file_path) {
+                result = Err(FxError::Io(format!(
+                    "Cannot move a directory into itself: {}",
+                    item.file_name
+                )));
+            } else {
+                let name = match item.file_type {
+                    FileType::Directory => rename_dir(&item.file_name, &name_set),
+                    _ => rename_file(&item.file_name, &name_set),
+                };
+                let to = self.current_dir.join(&name);
+                match move_item(&item.file_path, &to) {
+                    Ok(_) => {
+                        //Keep the selection on the moved item.
+                        if self.selection.remove(&item.file_path) {
+                            self.selection.insert(to.clone());
+                        }
+                        name_set.insert(name);
+                        moved.push((item.file_path.clone(), to));
+                    }
+                    Err(e) => result = Err(e),
+                }
+            }
+            //Keep the rest to retry.
+            if result.is_err() {
+                self.cut = items[i..].to_vec();
+                break;
+            }
+        }
+
+        //Record the items moved before an error, so that they can be undone.
+        let total = moved.len();
+        if !moved.is_empty() {
+            self.operations.branch();
+            self.operations.push(OpKind::Move(moved));
+        }
+        result.map(|_| total)
+    }
+
+    /// Create a new file or directory in the current directory.
+    pub fn create_item(&self, name: &str, is_dir: bool) -> Result<(), FxError> {
+        let path = self.current_dir.join(name);
+        if is_dir {
+            std::fs::create_dir(path)?;
+        } else {
+            std::fs::File::options()
+                .read(true)
+                .write(true)
+                .create_new(true)
+                .open(path)?;
+        }
+        Ok(())
+    }
+
+    /// Rename the item in its directory.
+    pub fn rename_item(&mut self, from: &std::path::Path, new_name: &str) -> Result<(), FxError> {
+        let to = from
+            .parent()
+            .map_or_else(|| self.current_dir.clone(), |p| p.to_path_buf())
+            .join(new_name);
+        std::fs::rename(from, &to)?;
+        self.operations.branch();
+        self.operations
+            .push(OpKind::Rename(vec![(from.to_path_buf(), to)]));
+        Ok(())
+    }
+
+    /// Repeat the last change at the cursor.
+    /// The count, if any, overrides the number of items of the last deletion.
+    pub fn repeat_change(
+        &mut self,
+        count: Option<usize>,
+        screen: &mut Stdout,
+    ) -> Result<(), FxError> {
+        let change = match &self.last_change {
+            Some(change) => change.clone(),
+            None => return Ok(()),
+        };
+        match change {
+            Change::Delete {
+                count: last_count,
+                reg,
+                append,
+            } => {
+                if self.list.is_empty() {
+                    return Ok(());
+                }
+                let count = count.unwrap_or(last_count);
+                if let Err(e) = self.delete_count(count, reg, append, screen) {
+                    self.reset_selection();
+                    self.redraw(self.layout.y);
+                    return Err(e);
+                }
+                self.last_change = Some(Change::Delete { count, reg, append });
+            }
+            Change::Put(reg) => match self.registers.check_reg(&KeyCode::Char(reg)) {
+                Some(items) => self.put(items, screen)?,
+                None => return Err(FxError::Io("Register not found.".to_owned())),
+            },
+            Change::Rename { from, to } => {
+                let item = self.get_item()?;
+                let new_name = repeat_rename(&from, &to, &item.file_name).ok_or_else(|| {
+                    FxError::Io("Cannot rename this item in the same way.".to_owned())
+                })?;
+                let path = item.file_path.clone();
+                if path.with_file_name(&new_name).symlink_metadata().is_ok() {
+                    return Err(FxError::Io(format!("{} already exists.", new_name)));
+                }
+                self.rename_item(&path, &new_name)?;
+                self.reload(self.layout.y)?;
+            }
+            Change::NewFile(name) => {
+                self.create_item(&name, false)?;
+                self.reload(self.layout.y)?;
+            }
+            Change::NewDir(name) => {
+                self.create_item(&name, true)?;
+                self.reload(self.layout.y)?;
+            }
+        }
+        Ok(())
+    }
+
     /// Redraw the contents.
     pub fn redraw(&mut self, y: u16) {
         self.clear_and_show_headline();
@@ -1020,7 +1408,7 @@ impl State {
             Split::Vertical => (column + 2, BEGINNING_ROW),
             Split::Horizontal => (1, row + 2),
         };
-        self.layout.preview_space = if self.layout.is_preview() || self.layout.is_reg() {
+        self.layout.preview_space = if self.layout.has_side() {
             match self.layout.split {
                 Split::Vertical => (original_column - column - 1, row - BEGINNING_ROW),
                 Split::Horizontal => (column, original_row - row - 1),
@@ -1072,6 +1460,24 @@ impl State {
             header_space -= 5;
         }
 
+        // If items are selected, print the count.
+        if !self.selection.is_empty() {
+            let count = format!(" [{} selected]", self.selection.len());
+            if count.len() <= header_space {
+                print!("{}", count);
+                header_space -= count.len();
+            }
+        }
+
+        // If the filter is active, print it.
+        if let Some(filter) = &self.filter {
+            let filter = format!(" [filter: {}]", filter);
+            if filter.len() <= header_space {
+                print!("{}", filter);
+                header_space -= filter.len();
+            }
+        }
+
         //If git repository exists, get the branch information and print it.
         if let Ok(repo) = git2::Repository::open(&self.current_dir) {
             if let Ok(head) = repo.head() {
@@ -1113,9 +1519,10 @@ impl State {
         if item.is_dirty {
             color = &self.layout.colors.dirty_fg;
         }
+        let selected = item.selected || self.selection.contains(&item.file_path);
 
         if self.layout.terminal_column < PROPER_WIDTH {
-            if item.selected {
+            if selected {
                 set_color(&TermColor::ForeGround(color));
                 print!("{}", name.negative(),);
                 reset_color();
@@ -1131,7 +1538,7 @@ impl State {
             if self.layout.terminal_column > self.layout.time_start_pos + TIME_WIDTH {
                 clear_until_newline();
             }
-        } else if item.selected {
+        } else if selected {
             set_color(&TermColor::ForeGround(color));
             print!("{}", name.negative(),);
             move_left(1000);
@@ -1245,6 +1652,16 @@ impl State {
             result.retain(|x| !x.is_hidden);
         }
 
+        if let Some(filter) = &self.filter {
+            match self.matcher(filter) {
+                Ok(matcher) => result.retain(|x| matcher.is_match(&x.file_name)),
+                Err(_) => result.clear(),
+            }
+        }
+
+        // Items may have been removed or renamed by commands.
+        self.selection.retain(|p| p.symlink_metadata().is_ok());
+
         self.list = result;
         Ok(())
     }
@@ -1289,6 +1706,13 @@ impl State {
             result.retain(|x| !x.is_hidden);
         }
 
+        if let Some(filter) = &self.filter {
+            match self.matcher(filter) {
+                Ok(matcher) => result.retain(|x| matcher.is_match(&x.file_name)),
+                Err(_) => result.clear(),
+            }
+        }
+
         self.list = result;
     }
 
@@ -1326,7 +1750,11 @@ impl State {
             } else {
                 let mut result: Vec<(PathBuf, PathBuf)> = vec![];
                 for (i, new_name) in new_names.iter().enumerate() {
-                    let mut to = self.current_dir.clone();
+                    //Selected items may be in other directories.
+                    let mut to = items[i]
+                        .file_path
+                        .parent()
+                        .map_or_else(|| self.current_dir.clone(), |p| p.to_path_buf());
                     to.push(new_name);
                     if &items[i].file_name != new_name {
                         std::fs::rename(&items[i].file_path, &to)?;
@@ -1342,6 +1770,196 @@ impl State {
         }
     }
 
+    /// Toggle the selection of the item that the cursor points to.
+    pub fn toggle_select(&mut self) -> Result<(), FxError> {
+        let path = self.get_item()?.file_path.clone();
+        if !self.selection.remove(&path) {
+            self.selection.insert(path);
+        }
+        Ok(())
+    }
+
+    /// Select or unselect the items in the list whose names match any of the globs
+    /// (all the items if no glob is given), and return the number of them.
+    pub fn select_by_glob(&mut self, globs: &[&str], select: bool) -> Result<usize, FxError> {
+        let matchers = globs
+            .iter()
+            .map(|glob| {
+                Matcher::new(
+                    glob,
+                    SearchMode::Glob,
+                    self.ignore_case == Some(true),
+                    self.smart_case,
+                )
+            })
+            .collect::<Result<Vec<Matcher>, FxError>>()?;
+        let mut count = 0;
+        for item in self.list.iter() {
+            if matchers.is_empty() || matchers.iter().any(|m| m.is_match(&item.file_name)) {
+                if select {
+                    self.selection.insert(item.file_path.clone());
+                } else {
+                    self.selection.remove(&item.file_path);
+                }
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Invert the selection of the items in the list, and return the number of selected ones.
+    pub fn invert_selection(&mut self) -> usize {
+        let mut count = 0;
+        for item in self.list.iter() {
+            if !self.selection.remove(&item.file_path) {
+                self.selection.insert(item.file_path.clone());
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Items selected by `<Space>`. Those that no longer exist are skipped.
+    pub fn selection_buffers(&self) -> Vec<ItemBuffer> {
+        self.selection
+            .iter()
+            .filter_map(|path| ItemBuffer::from_path(path))
+            .collect()
+    }
+
+    /// Yank the items selected by `<Space>` and clear the selection.
+    pub fn yank_selection(&mut self, reg: Option<char>, append: bool) -> usize {
+        let items = self.selection_buffers();
+        self.selection.clear();
+        self.registers.yank_item(&items, reg, append)
+    }
+
+    /// Return the paths of the selected items in the visual mode,
+    /// the items selected by `<Space>`,
+    /// or the path of the item that the cursor points to.
+    pub fn selected_paths(&self) -> Vec<PathBuf> {
+        if self.v_start.is_some() {
+            self.list
+                .iter()
+                .filter(|item| item.selected)
+                .map(|item| item.file_path.clone())
+                .collect()
+        } else if !self.selection.is_empty() {
+            self.selection.iter().cloned().collect()
+        } else {
+            self.get_item()
+                .map(|item| vec![item.file_path.clone()])
+                .unwrap_or_default()
+        }
+    }
+
+    /// Text copied to the clipboard by the action: paths of the selected items
+    /// (or the current item) separated by newlines, the name of the current item,
+    /// or the current directory.
+    pub fn clipboard_text(&self, action: &Action) -> String {
+        match action {
+            Action::CopyName => self
+                .get_item()
+                .map(|item| item.file_name.clone())
+                .unwrap_or_default(),
+            Action::CopyDir => self.current_dir.to_string_lossy().to_string(),
+            _ => self
+                .selected_paths()
+                .iter()
+                .map(|p| p.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+
+    /// Values for the placeholders in the command line:
+    /// `%f` (current item), `%s` (selected items), `%d` (current directory)
+    /// and `%r` (items in register a).
+    pub fn placeholder_values(&self) -> BTreeMap<char, Vec<String>> {
+        let mut values = BTreeMap::new();
+        values.insert(
+            'f',
+            self.get_item()
+                .map(|item| vec![item.file_path.to_string_lossy().to_string()])
+                .unwrap_or_default(),
+        );
+        values.insert(
+            's',
+            self.selected_paths()
+                .iter()
+                .map(|path| path.to_string_lossy().to_string())
+                .collect(),
+        );
+        values.insert('d', vec![self.current_dir.to_string_lossy().to_string()]);
+        values.insert(
+            'r',
+            self.registers
+                .named
+                .get(&'a')
+                .map(|v| {
+                    v.iter()
+                        .map(|item| item.file_path.to_string_lossy().to_string())
+                        .collect()
+                })
+                .unwrap_or_default(),
+        );
+        values
+    }
+
+    /// Environment variables for the commands, corresponding to the placeholders.
+    /// Multiple paths are separated by newlines.
+    pub fn command_env(&self) -> Vec<(&'static str, String)> {
+        let values = self.placeholder_values();
+        [
+            ("FX_FILE", 'f'),
+            ("FX_SELECTION", 's'),
+            ("FX_DIR", 'd'),
+            ("FX_REGISTER", 'r'),
+        ]
+        .into_iter()
+        .map(|(key, c)| {
+            (
+                key,
+                values.get(&c).map(|v| v.join("\n")).unwrap_or_default(),
+            )
+        })
+        .collect()
+    }
+
+    /// Prepare the user command defined in the config file.
+    /// Selected items are passed as the positional parameters (`"$@"`).
+    fn prepare_user_command(&self, command: &UserCommand) -> Command {
+        let shell = env::var("SHELL").unwrap_or_else(|_| "sh".to_owned());
+        let mut ex = Command::new(shell);
+        ex.arg("-c")
+            .arg(&command.run)
+            .arg("fx")
+            .args(self.selected_paths())
+            .envs(self.command_env())
+            .current_dir(&self.current_dir);
+        ex
+    }
+
+    /// Run the user command and wait for it to finish.
+    pub fn run_user_command(&self, command: &UserCommand) -> Result<ExitStatus, FxError> {
+        info!("USER COMMAND: {:?}", command.run);
+        Ok(self.prepare_user_command(command).status()?)
+    }
+
+    /// Run the user command in the background.
+    pub fn spawn_user_command(&self, command: &UserCommand) -> Result<(), FxError> {
+        info!("USER COMMAND (background): {:?}", command.run);
+        let mut child = self
+            .prepare_user_command(command)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        //Reap the child process when it exits.
+        std::thread::spawn(move || child.wait());
+        Ok(())
+    }
+
     /// Reset all item's selected state and exit the select mode.
     pub fn reset_selection(&mut self) {
         for item in self.list.iter_mut() {
@@ -1350,16 +1968,77 @@ impl State {
         self.v_start = None;
     }
 
+    /// While typing the keyword, move the cursor to the first matched item,
+    /// or back to the original position if nothing matches.
+    pub fn search_incrementally(&mut self, key: &str, original_nums: Num, original_y: u16) {
+        let target = self.matcher(key).ok().and_then(|matcher| {
+            self.list
+                .iter()
+                .position(|x| matcher.is_match(&x.file_name))
+        });
+        self.highlight_matches(key);
+        match target {
+            Some(i) => {
+                self.layout.nums.skip = i as u16;
+                self.layout.nums.index = i;
+                self.redraw(BEGINNING_ROW);
+            }
+            None => {
+                self.layout.nums = original_nums;
+                self.layout.y = original_y;
+                self.redraw(self.layout.y);
+            }
+        }
+    }
+
+    /// While typing the filter, narrow the original list down to the items that match it.
+    /// If the pattern is invalid (e.g. an incomplete regex), nothing matches.
+    pub fn filter_incrementally(&mut self, key: &str, original: &[ItemInfo]) {
+        self.list = match self.matcher(key) {
+            Ok(matcher) => original
+                .iter()
+                .filter(|x| matcher.is_match(&x.file_name))
+                .cloned()
+                .collect(),
+            Err(_) => vec![],
+        };
+        self.filter = Some(key.to_owned());
+        self.layout.nums.reset();
+        self.redraw(BEGINNING_ROW);
+    }
+
+    /// Clear the filter and show all the items, keeping the cursor on the current item.
+    pub fn clear_filter(&mut self) -> Result<(), FxError> {
+        let current = self.get_item().map(|x| x.file_path.clone()).ok();
+        self.filter = None;
+        self.update_list()?;
+        match current.and_then(|p| self.list.iter().position(|x| x.file_path == p)) {
+            Some(new_pos) => self.redraw_at(new_pos),
+            None => {
+                self.layout.nums.reset();
+                self.redraw(BEGINNING_ROW);
+            }
+        }
+        Ok(())
+    }
+
+    /// Build the matcher of the keyword, following the search options.
+    pub fn matcher(&self, keyword: &str) -> Result<Matcher, FxError> {
+        Matcher::new(
+            keyword,
+            self.search_mode,
+            self.ignore_case == Some(true),
+            self.smart_case,
+        )
+    }
+
     /// Highlight matched items.
     pub fn highlight_matches(&mut self, keyword: &str) {
+        let matcher = self.matcher(keyword).ok();
         for item in self.list.iter_mut() {
-            item.matches = match self.ignore_case {
-                Some(true) => item
-                    .file_name
-                    .to_lowercase()
-                    .contains(&keyword.to_lowercase()),
-                _ => item.file_name.contains(keyword),
-            }
+            item.matches = matcher
+                .as_ref()
+                .is_some_and(|matcher| matcher.is_match(&item.file_name));
         }
     }
 
@@ -1423,6 +2102,174 @@ impl State {
         Ok(())
     }
 
+    /// Browse the trash, restoring items to where they were deleted from, or purging them.
+    pub fn browse_trash(&mut self, mut screen: &Stdout) -> Result<(), FxError> {
+        let entries =
+            trash::read_all_entries(&self.trash_dir, &self.trash_info_dir, self.trash_backend)?;
+        if entries.is_empty() {
+            print_info("The trash is empty.", self.layout.y);
+            return Ok(());
+        }
+        let mut view = TrashView::new(entries);
+        let half_page = (self.layout.terminal_row / 2) as usize;
+        go_to_info_line_and_reset();
+        view.print(&self.layout);
+        screen.flush()?;
+
+        loop {
+            if let Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind: KeyEventKind::Press,
+                ..
+            }) = crossterm::event::read()?
+            {
+                let message = match (code, modifiers) {
+                    (KeyCode::Char('j'), KeyModifiers::NONE)
+                    | (KeyCode::Down, KeyModifiers::NONE) => {
+                        view.move_down(1);
+                        None
+                    }
+                    (KeyCode::Char('k'), KeyModifiers::NONE)
+                    | (KeyCode::Up, KeyModifiers::NONE) => {
+                        view.move_up(1);
+                        None
+                    }
+                    (KeyCode::Char('d'), KeyModifiers::CONTROL) => {
+                        view.move_down(half_page);
+                        None
+                    }
+                    (KeyCode::Char('u'), KeyModifiers::CONTROL) => {
+                        view.move_up(half_page);
+                        None
+                    }
+                    (KeyCode::Char(' '), KeyModifiers::NONE) => {
+                        view.toggle_mark();
+                        view.move_down(1);
+                        None
+                    }
+                    //Restore the marked items (or the current one) to the original location.
+                    (KeyCode::Char('r'), KeyModifiers::NONE) => {
+                        let mut count = 0;
+                        let mut result = Ok(());
+                        for entry in view.targets() {
+                            match entry.restore() {
+                                Ok(_) => count += 1,
+                                Err(e) => {
+                                    result = Err(e);
+                                    break;
+                                }
+                            }
+                        }
+                        view.retain_existing();
+                        Some(result.map(|_| format!("{} items restored.", count)))
+                    }
+                    //Delete the marked items (or the current one) permanently.
+                    (KeyCode::Char('d'), KeyModifiers::NONE) => {
+                        print_warning(PURGE_WARNING, self.layout.y);
+                        screen.flush()?;
+                        let confirmed = matches!(
+                            crossterm::event::read()?,
+                            Event::Key(KeyEvent {
+                                code: KeyCode::Char('y') | KeyCode::Char('Y'),
+                                ..
+                            })
+                        );
+                        if confirmed {
+                            let mut count = 0;
+                            let mut result = Ok(());
+                            for entry in view.targets() {
+                                match entry.purge() {
+                                    Ok(_) => count += 1,
+                                    Err(e) => {
+                                        result = Err(e);
+                                        break;
+                                    }
+                                }
+                            }
+                            view.retain_existing();
+                            Some(result.map(|_| format!("{} items purged.", count)))
+                        } else {
+                            go_to_info_line_and_reset();
+                            None
+                        }
+                    }
+                    //Go to the item in the trash directory.
+                    (KeyCode::Enter, KeyModifiers::NONE) => {
+                        if let Some(entry) = view.entries.get(view.selected) {
+                            let path = entry.path.clone();
+                            self.jump_to_item(&path)?;
+                        }
+                        return Ok(());
+                    }
+                    (KeyCode::Esc, KeyModifiers::NONE)
+                    | (KeyCode::Char('q'), KeyModifiers::NONE) => {
+                        self.reload(self.layout.y)?;
+                        return Ok(());
+                    }
+                    _ => continue,
+                };
+
+                if view.entries.is_empty() {
+                    self.reload(self.layout.y)?;
+                }
+                if !view.entries.is_empty() {
+                    view.print(&self.layout);
+                }
+                match message {
+                    Some(Ok(message)) => print_info(message, self.layout.y),
+                    Some(Err(e)) => print_warning(e, self.layout.y),
+                    None => {}
+                }
+                screen.flush()?;
+                if view.entries.is_empty() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Purge the items deleted more than `age` ago, by `:empty --older-than {age}`.
+    pub fn empty_trash_older_than(
+        &mut self,
+        age: chrono::TimeDelta,
+        mut screen: &Stdout,
+    ) -> Result<(), FxError> {
+        let limit = trash::deleted_before(age)
+            .ok_or_else(|| FxError::Io("Invalid age: too large.".to_owned()))?;
+        let entries: Vec<trash::TrashEntry> =
+            trash::read_all_entries(&self.trash_dir, &self.trash_info_dir, self.trash_backend)?
+                .into_iter()
+                .filter(|x| x.deleted.is_some_and(|deleted| deleted < limit))
+                .collect();
+        if entries.is_empty() {
+            print_info("No items to purge.", self.layout.y);
+            return Ok(());
+        }
+        print_warning(
+            format!("Delete {} items permanently? (if yes: y)", entries.len()),
+            self.layout.y,
+        );
+        screen.flush()?;
+
+        if let Event::Key(KeyEvent {
+            code: KeyCode::Char('y') | KeyCode::Char('Y'),
+            kind: KeyEventKind::Press,
+            ..
+        }) = crossterm::event::read()?
+        {
+            let count = trash::purge_entries(&entries, Some(limit), None)?;
+            if self.current_dir == self.trash_dir {
+                self.reload(self.layout.y)?;
+            }
+            print_info(format!("{} items purged.", count), self.layout.y);
+        } else {
+            go_to_info_line_and_reset();
+            self.move_cursor(self.layout.y);
+        }
+        Ok(())
+    }
+
     /// Empty the trash dir.
     pub fn empty_trash(&mut self, mut screen: &Stdout) -> Result<(), FxError> {
         print_warning(EMPTY_WARNING, self.layout.y);
@@ -1447,6 +2294,14 @@ impl State {
                     if let Err(e) = std::fs::create_dir(&self.trash_dir) {
                         print_warning(e, self.layout.y);
                     }
+                    //`.trashinfo` files go as well.
+                    if self.trash_info_dir.exists() {
+                        if let Err(e) = std::fs::remove_dir_all(&self.trash_info_dir)
+                            .and_then(|_| std::fs::create_dir(&self.trash_info_dir))
+                        {
+                            print_warning(e, self.layout.y);
+                        }
+                    }
                     if self.current_dir == self.trash_dir {
                         self.reload(BEGINNING_ROW)?;
                     }
@@ -1467,6 +2322,9 @@ impl State {
     /// Change directory.
     pub fn chdir(&mut self, p: &std::path::Path, mv: Move) -> Result<(), FxError> {
         std::env::set_current_dir(p)?;
+        self.remember_cursor();
+        // The filter is valid only in the directory where it is set.
+        self.filter = None;
 
         self.is_ro = match has_write_permission(p) {
             Ok(b) => !b,
@@ -1509,15 +2367,7 @@ impl State {
                                         file_name == name
                                     })
                                     .unwrap_or(0);
-                                if new_pos < 3 {
-                                    self.layout.nums.skip = 0;
-                                    self.layout.nums.index = new_pos;
-                                    self.redraw((new_pos as u16) + BEGINNING_ROW);
-                                } else {
-                                    self.layout.nums.skip = (new_pos - 3) as u16;
-                                    self.layout.nums.index = new_pos;
-                                    self.redraw(BEGINNING_ROW + 3);
-                                }
+                                self.redraw_at(new_pos);
                             }
                             None => {
                                 self.layout.nums.reset();
@@ -1549,13 +2399,11 @@ impl State {
                             self.layout.nums.skip = memo.num.skip;
                             self.reload(memo.cursor_pos)?;
                         } else {
-                            self.layout.nums.reset();
-                            self.reload(BEGINNING_ROW)?;
+                            self.reload_at_remembered_item()?;
                         }
                     }
                     None => {
-                        self.layout.nums.reset();
-                        self.reload(BEGINNING_ROW)?;
+                        self.reload_at_remembered_item()?;
                     }
                 }
             }
@@ -1567,19 +2415,18 @@ impl State {
                 self.keyword = None;
                 self.p_memo = Vec::new();
                 self.c_memo = Vec::new();
-                self.layout.nums.reset();
-                self.reload(BEGINNING_ROW)?;
+                self.reload_at_remembered_item()?;
             }
             Move::List => {
                 self.current_dir = p.to_owned();
                 self.keyword = None;
                 self.p_memo = Vec::new();
                 self.c_memo = Vec::new();
-                self.layout.nums.reset();
-                self.reload(BEGINNING_ROW)?;
+                self.reload_at_remembered_item()?;
             }
         }
-        //if zoxide is installed, add the target or increment its rank.
+        //Add the target to the built-in database, and to zoxide if installed.
+        self.frecency.add(p, frecency::now());
         if self.has_zoxide {
             if let Some(p) = p.as_os_str().to_str() {
                 if std::process::Command::new("zoxide")
@@ -1595,6 +2442,149 @@ impl State {
         Ok(())
     }
 
+    /// Remember the item under the cursor before leaving the directory.
+    fn remember_cursor(&mut self) {
+        if let Ok(item) = self.get_item() {
+            let name = item.file_name.clone();
+            self.cursor_memory.insert(self.current_dir.clone(), name);
+        }
+    }
+
+    /// Reload the list with the cursor on the item last used in the directory,
+    /// or on the top if there is none.
+    fn reload_at_remembered_item(&mut self) -> Result<(), FxError> {
+        self.layout.nums.reset();
+        self.update_list()?;
+        let pos = self
+            .cursor_memory
+            .get(&self.current_dir)
+            .and_then(|name| self.list.iter().position(|x| &x.file_name == name));
+        match pos {
+            Some(pos) => self.redraw_at(pos),
+            None => self.redraw(BEGINNING_ROW),
+        }
+        Ok(())
+    }
+
+    /// Move the cursor to the item at the index, scrolling the list only if needed.
+    /// In visual mode, the selection is extended to the item.
+    pub fn go_to_index(&mut self, index: usize) {
+        if self.list.is_empty() {
+            return;
+        }
+        let index = index.min(self.list.len() - 1);
+        let height = (self.layout.terminal_row - BEGINNING_ROW) as usize;
+        let mut skip = self.layout.nums.skip as usize;
+        if index < skip {
+            skip = index;
+        } else if index >= skip + height {
+            skip = index + 1 - height;
+        }
+        if let Some(start_pos) = self.v_start {
+            let (from, to) = if start_pos <= index {
+                (start_pos, index)
+            } else {
+                (index, start_pos)
+            };
+            for (i, item) in self.list.iter_mut().enumerate() {
+                item.selected = from <= i && i <= to;
+            }
+        }
+        self.layout.nums.skip = skip as u16;
+        self.layout.nums.index = index;
+        self.redraw((index - skip) as u16 + BEGINNING_ROW);
+    }
+
+    /// Redraw the list with the cursor on the item at the index.
+    pub fn redraw_at(&mut self, new_pos: usize) {
+        if new_pos < 3 {
+            self.layout.nums.skip = 0;
+            self.layout.nums.index = new_pos;
+            self.redraw((new_pos as u16) + BEGINNING_ROW);
+        } else {
+            self.layout.nums.skip = (new_pos - 3) as u16;
+            self.layout.nums.index = new_pos;
+            self.redraw(BEGINNING_ROW + 3);
+        }
+    }
+
+    /// Go to the directory that contains the target, and move the cursor to it.
+    pub fn jump_to_item(&mut self, target: &std::path::Path) -> Result<(), FxError> {
+        let parent = target.parent().ok_or(FxError::InvalidPath)?;
+        if parent != self.current_dir {
+            self.chdir(parent, Move::Jump)?;
+        }
+        match self.list.iter().position(|x| x.file_path == target) {
+            Some(new_pos) => self.redraw_at(new_pos),
+            None => self.redraw(self.layout.y),
+        }
+        Ok(())
+    }
+
+    /// Show registers, marks or the jumplist in the side space.
+    /// If the list is already shown, just return to the normal mode.
+    pub fn toggle_side_list(&mut self, side: Side) -> Result<(), FxError> {
+        if self.layout.side == side {
+            self.escape();
+        } else if self.layout.has_side() {
+            //The side space is already there.
+            self.layout.show_list(side);
+            self.redraw(self.layout.y);
+        } else {
+            self.layout.show_list(side);
+            let (new_column, new_row) = self.layout.update_column_and_row()?;
+            self.refresh(new_column, new_row, self.layout.y)?;
+            self.escape();
+        }
+        Ok(())
+    }
+
+    /// Set the mark on the current directory and the item under the cursor.
+    pub fn set_mark(&mut self, c: char) {
+        let item = self.get_item().ok().map(|x| x.file_path.clone());
+        self.marks.set(c, &self.current_dir, item.as_deref());
+    }
+
+    /// Go to the marked item, or to the marked directory if the item no longer exists.
+    pub fn jump_to_mark(&mut self, c: char) -> Result<(), FxError> {
+        let mark = self
+            .marks
+            .get(c)
+            .cloned()
+            .ok_or_else(|| FxError::Io(format!("Mark not set: {}", c)))?;
+        match mark.item {
+            Some(item) if item.symlink_metadata().is_ok() => self.jump_to_item(&item),
+            _ => {
+                if !mark.dir.exists() {
+                    return Err(FxError::Io(format!(
+                        "Marked directory not found: {}",
+                        mark.dir.display()
+                    )));
+                }
+                self.layout.nums.reset();
+                self.chdir(&mark.dir, Move::Jump)
+            }
+        }
+    }
+
+    /// Whether `z` uses zoxide rather than the built-in frecency database.
+    pub fn uses_zoxide(&self) -> bool {
+        self.has_zoxide && !self.builtin_z
+    }
+
+    /// Directories that match the keywords, from the best one.
+    pub fn z_candidates(&self, query: &str) -> Vec<String> {
+        if self.uses_zoxide() {
+            return complete_zoxide(query);
+        }
+        let keywords: Vec<&str> = query.split_whitespace().collect();
+        self.frecency
+            .query(&keywords, &self.current_dir, frecency::now())
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect()
+    }
+
     /// For subsequent use by cd in the parent shell
     pub fn export_lwd(&self) -> Result<(), ()> {
         if let Some(lwd_file) = &self.lwd_file {
@@ -1632,6 +2622,14 @@ impl State {
             //Print registers by :reg
             let reg = self.registers.prepare_reg(self.layout.preview_space.0);
             self.layout.print_reg(&reg);
+        } else if self.layout.is_marks() {
+            //Print marks by :marks
+            let marks = self.marks.prepare_marks(self.layout.preview_space.0);
+            self.layout.print_marks(&marks);
+        } else if self.layout.is_jumps() {
+            //Print the jumplist by :jumps
+            let jumps = self.jumplist.prepare_jumps(self.layout.preview_space.0);
+            self.layout.print_jumps(&jumps);
         }
 
         let item = self.get_item().ok();
@@ -1670,14 +2668,7 @@ impl State {
         self.clear_status_line();
 
         if let Some(keyword) = &self.keyword {
-            let count = self
-                .list
-                .iter()
-                .filter(|x| match self.ignore_case {
-                    Some(true) => x.file_name.to_lowercase().contains(&keyword.to_lowercase()),
-                    _ => x.file_name.contains(keyword),
-                })
-                .count();
+            let count = self.list.iter().filter(|x| x.matches).count();
             let count = if count <= 1 {
                 format!("{} match", count)
             } else {
@@ -1797,6 +2788,134 @@ impl State {
         print_pointer();
     }
 
+    /// Return the current value of the option.
+    pub fn get_option(&self, option: RuntimeOption) -> OptionValue {
+        match option {
+            RuntimeOption::SortBy => OptionValue::SortBy(self.layout.sort_by.clone()),
+            RuntimeOption::ShowHidden => OptionValue::ShowHidden(self.layout.show_hidden),
+            RuntimeOption::Preview => OptionValue::Preview(self.layout.is_preview()),
+            RuntimeOption::Split => OptionValue::Split(self.layout.split),
+            RuntimeOption::IgnoreCase => OptionValue::IgnoreCase(self.ignore_case == Some(true)),
+            RuntimeOption::SmartCase => OptionValue::SmartCase(self.smart_case),
+            RuntimeOption::SearchMode => OptionValue::SearchMode(self.search_mode),
+            RuntimeOption::RespectGitignore => {
+                OptionValue::RespectGitignore(self.respect_gitignore)
+            }
+        }
+    }
+
+    /// Store the value of the option without redrawing,
+    /// used to load the config and session files.
+    pub fn apply_option(&mut self, value: OptionValue) {
+        match value {
+            OptionValue::SortBy(sort_by) => self.layout.sort_by = sort_by,
+            OptionValue::ShowHidden(b) => self.layout.show_hidden = b,
+            OptionValue::Preview(b) => {
+                self.layout.side = if b { Side::Preview } else { Side::None };
+            }
+            OptionValue::Split(split) => self.layout.split = split,
+            OptionValue::IgnoreCase(b) => self.ignore_case = Some(b),
+            OptionValue::SmartCase(b) => self.smart_case = b,
+            OptionValue::SearchMode(mode) => self.search_mode = mode,
+            OptionValue::RespectGitignore(b) => self.respect_gitignore = b,
+        }
+    }
+
+    /// Set the value of the option and redraw the screen if needed.
+    pub fn set_option(&mut self, value: OptionValue) -> Result<(), FxError> {
+        if self.get_option(value.option()) == value {
+            return Ok(());
+        }
+        match value {
+            OptionValue::SortBy(sort_by) => {
+                self.layout.sort_by = sort_by;
+                self.layout.nums.reset();
+                self.reorder(BEGINNING_ROW);
+            }
+            OptionValue::ShowHidden(b) => {
+                if b {
+                    self.layout.show_hidden = true;
+                    self.update_list()?;
+                } else {
+                    self.list.retain(|x| !x.is_hidden);
+                    self.layout.show_hidden = false;
+                }
+                self.layout.nums.reset();
+                self.redraw(BEGINNING_ROW);
+            }
+            OptionValue::Preview(b) => {
+                //If registers, marks or the jumplist are shown, the side space is already there.
+                let (new_column, new_row) = if self.layout.has_side() && !self.layout.is_preview() {
+                    self.layout.show_preview();
+                    (self.layout.terminal_column, self.layout.terminal_row)
+                } else {
+                    if b {
+                        self.layout.show_preview();
+                    } else {
+                        self.layout.reset_side();
+                    }
+                    self.layout.update_column_and_row()?
+                };
+                self.refresh(new_column, new_row, self.layout.y)?;
+            }
+            OptionValue::Split(split) => {
+                self.layout.split = split;
+                if self.layout.has_side() {
+                    let (mut new_column, mut new_row) = terminal_size()?;
+                    match self.layout.split {
+                        Split::Vertical => new_column /= 2,
+                        Split::Horizontal => new_row /= 2,
+                    }
+                    self.refresh(new_column, new_row, self.layout.y)?;
+                }
+            }
+            OptionValue::RespectGitignore(_) => self.apply_option(value),
+            OptionValue::IgnoreCase(_) | OptionValue::SmartCase(_) | OptionValue::SearchMode(_) => {
+                self.apply_option(value);
+                //Apply the new options to the current filter and search.
+                if self.filter.is_some() {
+                    self.update_list()?;
+                    self.layout.nums.reset();
+                    self.layout.y = BEGINNING_ROW;
+                }
+                if let Some(keyword) = self.keyword.clone() {
+                    self.highlight_matches(&keyword);
+                }
+                self.redraw(self.layout.y);
+            }
+        }
+        Ok(())
+    }
+
+    /// Toggle the option: negate bool, or switch to the next choice.
+    pub fn toggle_option(&mut self, option: RuntimeOption) -> Result<(), FxError> {
+        let value = self.get_option(option).toggled();
+        self.set_option(value)
+    }
+
+    /// Execute `:set` with the arguments, and return the message to show.
+    /// Without arguments, show all options.
+    pub fn set_command(&mut self, args: &[&str]) -> Result<String, FxError> {
+        if args.is_empty() {
+            return Ok(RuntimeOption::ALL
+                .into_iter()
+                .map(|option| format!("{}={}", option.name(), self.get_option(option)))
+                .collect::<Vec<String>>()
+                .join(" "));
+        }
+        let mut queried = vec![];
+        for arg in args {
+            match parse_set(arg)? {
+                SetCommand::Set(value) => self.set_option(value)?,
+                SetCommand::Toggle(option) => self.toggle_option(option)?,
+                SetCommand::Query(option) => {
+                    queried.push(format!("{}={}", option.name(), self.get_option(option)))
+                }
+            }
+        }
+        Ok(queried.join(" "))
+    }
+
     /// Save the sort key and whether to show hidden items to session file.
     pub fn write_session(&self, session_path: PathBuf) -> Result<(), FxError> {
         let session = Session {
@@ -1804,6 +2923,8 @@ impl State {
             show_hidden: self.layout.show_hidden,
             preview: Some(self.layout.is_preview()),
             split: Some(self.layout.split),
+            last_dir: Some(self.current_dir.clone()),
+            last_item: self.get_item().ok().map(|x| x.file_name.clone()),
         };
         let serialized = serde_yaml::to_string(&session)?;
         fs::write(session_path, serialized)?;
@@ -1938,6 +3059,11 @@ fn read_item(entry: fs::DirEntry) -> ItemInfo {
     }
 }
 
+/// Name of the item in the trash directory, prefixed with the timestamp.
+fn trash_name(file_name: &str) -> String {
+    format!("{}_{}", Local::now().timestamp(), file_name)
+}
+
 /// Check if zoxide is installed.
 fn check_zoxide() -> bool {
     std::process::Command::new("zoxide")
@@ -2087,6 +3213,264 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_filter() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["Cargo.toml", "cargo.lock", "README.md", ".cargo"] {
+            fs::write(dir.path().join(name), "").unwrap();
+        }
+        let mut state = State {
+            current_dir: dir.path().to_path_buf(),
+            filter: Some("cargo".to_owned()),
+            ..Default::default()
+        };
+        state.update_list().unwrap();
+        let names: Vec<&str> = state.list.iter().map(|x| x.file_name.as_str()).collect();
+        assert_eq!(names, vec!["cargo.lock"]);
+
+        state.ignore_case = Some(true);
+        state.layout.show_hidden = true;
+        state.update_list().unwrap();
+        let names: Vec<&str> = state.list.iter().map(|x| x.file_name.as_str()).collect();
+        assert_eq!(names, vec![".cargo", "cargo.lock", "Cargo.toml"]);
+
+        state.filter = Some("\\g*.toml".to_owned());
+        state.update_list().unwrap();
+        let names: Vec<&str> = state.list.iter().map(|x| x.file_name.as_str()).collect();
+        assert_eq!(names, vec!["Cargo.toml"]);
+    }
+
+    #[test]
+    fn test_put_into_filtered_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let current_dir = dir.path().join("current");
+        fs::create_dir(&current_dir).unwrap();
+        fs::write(current_dir.join("a.txt"), "old").unwrap();
+        fs::write(current_dir.join("b.txt"), "").unwrap();
+        fs::write(dir.path().join("a.txt"), "new").unwrap();
+        let mut state = State {
+            current_dir: current_dir.clone(),
+            filter: Some("b".to_owned()),
+            ..Default::default()
+        };
+        state.update_list().unwrap();
+        assert_eq!(state.list.len(), 1);
+
+        //The item filtered out is not overwritten.
+        let item = ItemBuffer::from_path(&dir.path().join("a.txt")).unwrap();
+        assert_eq!(state.put_item(&[item], None).unwrap(), 1);
+        assert_eq!(
+            fs::read_to_string(current_dir.join("a.txt")).unwrap(),
+            "old"
+        );
+        assert_eq!(fs::read_dir(&current_dir).unwrap().count(), 3);
+    }
+
+    #[test]
+    fn test_move_cut_items_onto_hidden() {
+        let dir = tempfile::tempdir().unwrap();
+        let current_dir = dir.path().join("current");
+        fs::create_dir(&current_dir).unwrap();
+        fs::write(current_dir.join(".env"), "old").unwrap();
+        fs::write(dir.path().join(".env"), "new").unwrap();
+        let mut state = State {
+            current_dir: current_dir.clone(),
+            ..Default::default()
+        };
+        state.update_list().unwrap();
+        assert!(state.list.is_empty());
+
+        //The hidden item is not replaced.
+        state.cut = vec![ItemBuffer::from_path(&dir.path().join(".env")).unwrap()];
+        assert_eq!(state.move_cut_items().unwrap(), 1);
+        assert_eq!(fs::read_to_string(current_dir.join(".env")).unwrap(), "old");
+        assert_eq!(fs::read_dir(&current_dir).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_selection() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("a.txt"), "").unwrap();
+        fs::write(dir.path().join("sub/b.txt"), "").unwrap();
+        let mut state = State {
+            current_dir: dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        state.update_list().unwrap();
+        state.layout.nums.index = 1;
+        state.toggle_select().unwrap();
+        state.selection.insert(dir.path().join("sub/b.txt"));
+        assert_eq!(
+            state.selected_paths(),
+            vec![dir.path().join("a.txt"), dir.path().join("sub/b.txt")]
+        );
+        assert_eq!(
+            state.clipboard_text(&Action::CopyPath),
+            format!(
+                "{}\n{}",
+                dir.path().join("a.txt").display(),
+                dir.path().join("sub/b.txt").display()
+            )
+        );
+        assert_eq!(state.clipboard_text(&Action::CopyName), "a.txt".to_string());
+        assert_eq!(
+            state.clipboard_text(&Action::CopyDir),
+            dir.path().display().to_string()
+        );
+        let items = state.selection_buffers();
+        assert_eq!(items[0].file_name, "a.txt".to_string());
+        assert_eq!(items[1].file_type, FileType::File);
+
+        state.toggle_select().unwrap();
+        assert_eq!(state.selection.len(), 1);
+
+        //Items that no longer exist are dropped from the selection.
+        fs::remove_file(dir.path().join("sub/b.txt")).unwrap();
+        state.update_list().unwrap();
+        assert!(state.selection.is_empty());
+        assert_eq!(state.selected_paths(), vec![dir.path().join("a.txt")]);
+    }
+
+    #[test]
+    fn test_yank_count() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["a", "b", "c", "d"] {
+            fs::write(dir.path().join(name), "").unwrap();
+        }
+        let mut state = State {
+            current_dir: dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        state.update_list().unwrap();
+        state.layout.nums.index = 1;
+        assert_eq!(state.yank_count(2, None, false), 2);
+        let names: Vec<&str> = state
+            .registers
+            .unnamed
+            .iter()
+            .map(|x| x.file_name.as_str())
+            .collect();
+        assert_eq!(names, vec!["b", "c"]);
+        assert_eq!(state.yank_count(10, Some('a'), false), 3);
+        assert_eq!(state.registers.named.get(&'a').unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_select_by_glob() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["a.log", "b.log", "c.txt", ".d.log"] {
+            fs::write(dir.path().join(name), "").unwrap();
+        }
+        let mut state = State {
+            current_dir: dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        state.update_list().unwrap();
+
+        //Hidden items are not in the list, so not selected.
+        assert_eq!(state.select_by_glob(&["*.log"], true).unwrap(), 2);
+        assert_eq!(state.selection.len(), 2);
+        assert_eq!(state.select_by_glob(&["a*"], false).unwrap(), 1);
+        assert_eq!(state.selected_paths(), vec![dir.path().join("b.log")]);
+
+        assert_eq!(state.invert_selection(), 2);
+        assert!(!state.selection.contains(&dir.path().join("b.log")));
+
+        assert_eq!(state.select_by_glob(&[], true).unwrap(), 3);
+        assert_eq!(state.select_by_glob(&["*.txt", "b*"], false).unwrap(), 2);
+        assert_eq!(state.selected_paths(), vec![dir.path().join("a.log")]);
+    }
+
+    #[test]
+    fn test_move_to_trash() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash_dir = dir.path().join("trash");
+        let current_dir = dir.path().join("current");
+        fs::create_dir(&trash_dir).unwrap();
+        fs::create_dir_all(current_dir.join("sub")).unwrap();
+        fs::write(current_dir.join("a.txt"), "a").unwrap();
+        fs::write(current_dir.join("sub/b.txt"), "b").unwrap();
+        let mut state = State {
+            current_dir: current_dir.clone(),
+            trash_dir: trash_dir.clone(),
+            trash_info_dir: dir.path().join("info"),
+            ..Default::default()
+        };
+        state.update_list().unwrap();
+
+        let src: Vec<ItemBuffer> = state.list.iter().map(ItemBuffer::new).collect();
+        let (_, dest) = state.move_to_trash(&src, true).unwrap();
+        assert!(fs::read_dir(&current_dir).unwrap().next().is_none());
+        assert_eq!(dest.len(), 2);
+        for item in dest {
+            let name = item.file_path.file_name().unwrap().to_str().unwrap();
+            assert!(name.ends_with(&format!("_{}", item.file_name)));
+            assert_eq!(item.file_path.parent(), Some(trash_dir.as_path()));
+        }
+        //Original paths are recorded for the trash browser.
+        let entries = trash::read_entries(&trash_dir, &dir.path().join("info"), None).unwrap();
+        let mut originals: Vec<PathBuf> = entries.into_iter().filter_map(|x| x.original).collect();
+        originals.sort();
+        assert_eq!(
+            originals,
+            vec![current_dir.join("a.txt"), current_dir.join("sub")]
+        );
+        assert_eq!(fs::read_dir(&trash_dir).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_move_to_trash_same_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash_dir = dir.path().join("trash");
+        fs::create_dir(&trash_dir).unwrap();
+        let mut src = vec![];
+        for sub in ["a", "b"] {
+            fs::create_dir(dir.path().join(sub)).unwrap();
+            let path = dir.path().join(sub).join("README.md");
+            fs::write(&path, sub).unwrap();
+            src.push(ItemBuffer::from_path(&path).unwrap());
+        }
+        let mut state = State {
+            current_dir: dir.path().to_path_buf(),
+            trash_dir: trash_dir.clone(),
+            trash_info_dir: dir.path().join("info"),
+            ..Default::default()
+        };
+
+        //Items with the same name from different directories do not replace each other.
+        let (_, dest) = state.move_to_trash(&src, true).unwrap();
+        assert_eq!(dest.len(), 2);
+        assert_ne!(dest[0].file_path, dest[1].file_path);
+        assert_eq!(fs::read_to_string(&dest[0].file_path).unwrap(), "a");
+        assert_eq!(fs::read_to_string(&dest[1].file_path).unwrap(), "b");
+        let entries = trash::read_entries(&trash_dir, &dir.path().join("info"), None).unwrap();
+        let mut originals: Vec<PathBuf> = entries.into_iter().filter_map(|x| x.original).collect();
+        originals.sort();
+        assert_eq!(
+            originals,
+            vec![
+                dir.path().join("a/README.md"),
+                dir.path().join("b/README.md")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_remove_file_not_overwrite() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("a.txt");
+        let to = dir.path().join("trashed");
+        fs::write(&from, "a").unwrap();
+        fs::write(&to, "trashed").unwrap();
+        let mut state = State::default();
+
+        let item = ItemBuffer::from_path(&from).unwrap();
+        assert!(state.remove_file(&item, Some(to.clone())).is_err());
+        assert_eq!(fs::read_to_string(&from).unwrap(), "a");
+        assert_eq!(fs::read_to_string(&to).unwrap(), "trashed");
+    }
+
     #[test]
     fn test_has_write_permission() {
         let p = std::path::PathBuf::from("./testfiles/permission_test");
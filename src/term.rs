@@ -101,6 +101,24 @@ pub fn move_right_command_line(
     }
 }
 
+/// Replace the whole input of the command line (e.g. by a history entry) and print it.
+pub fn replace_command_line(
+    prompt: &str,
+    new_input: &str,
+    input: &mut Vec<char>,
+    current_char_pos: &mut usize,
+    current_pos: &mut u16,
+) {
+    *input = new_input.chars().collect();
+    *current_char_pos = input.len();
+    *current_pos = 2
+        + unicode_width::UnicodeWidthStr::width(prompt) as u16
+        + unicode_width::UnicodeWidthStr::width(new_input) as u16;
+    go_to_info_line_and_reset();
+    print!("{}{}", prompt, new_input);
+    move_to(*current_pos, 2);
+}
+
 pub fn hide_cursor() {
     print!("{}", Hide);
 }
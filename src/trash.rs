@@ -0,0 +1,844 @@
+use super::errors::FxError;
+use super::functions::{
+    move_item, rename_dir, rename_file, shorten_str_including_wide_char, to_proper_size,
+};
+use super::layout::Layout;
+use super::state::BEGINNING_ROW;
+use super::term::*;
+
+use chrono::{Local, NaiveDateTime, TimeDelta};
+use crossterm::style::Stylize;
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[cfg(unix)]
+use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+
+const TRASH: &str = "Trash";
+const FILES: &str = "files";
+const INFO: &str = "info";
+const TRASHINFO_EXT: &str = ".trashinfo";
+const DELETION_DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// Where deleted items go.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TrashBackend {
+    /// Felix's own trash directory, with `<timestamp>_<name>` entries.
+    #[default]
+    Felix,
+    /// The freedesktop.org Trash specification, shared with desktop environments and other tools.
+    Freedesktop,
+}
+
+/// Home trash of the freedesktop.org Trash specification: `$XDG_DATA_HOME/Trash`.
+pub fn home_trash() -> Result<PathBuf, FxError> {
+    let mut path = dirs::data_dir()
+        .ok_or_else(|| FxError::Dirs("Cannot read the data directory.".to_string()))?;
+    path.push(TRASH);
+    Ok(path)
+}
+
+/// Directory that holds the trashed items: `files` of the trash.
+pub fn files_dir(trash: &Path) -> PathBuf {
+    trash.join(FILES)
+}
+
+/// Directory that holds `.trashinfo` files: `info` of the trash.
+pub fn info_dir(trash: &Path) -> PathBuf {
+    trash.join(INFO)
+}
+
+/// Move the item to the trash following the freedesktop.org Trash specification,
+/// and return its new path in `files`.
+/// Items on other mounts go to `$topdir/.Trash/$uid` or `$topdir/.Trash-$uid`,
+/// falling back to the home trash.
+pub fn trash_item(path: &Path) -> Result<PathBuf, FxError> {
+    let home = home_trash()?;
+    match topdir_trash(path, &home) {
+        Some((trash, topdir)) => trash_item_in(path, &trash, Some(&topdir)),
+        None => trash_item_in(path, &home, None),
+    }
+}
+
+/// Move the item to the trash directory, writing its `.trashinfo` first.
+/// If the trash is at the top of a mount, `Path` is relative to the `topdir`.
+fn trash_item_in(path: &Path, trash: &Path, topdir: Option<&Path>) -> Result<PathBuf, FxError> {
+    let files = files_dir(trash);
+    let info = info_dir(trash);
+    std::fs::create_dir_all(&files)?;
+    std::fs::create_dir_all(&info)?;
+
+    let name = path
+        .file_name()
+        .and_then(|x| x.to_str())
+        .ok_or(FxError::Encode)?;
+    let original = match topdir.and_then(|topdir| path.strip_prefix(topdir).ok()) {
+        Some(relative) => relative,
+        None => path,
+    };
+
+    let (dest, info_path) = reserve_name(&files, &info, name, original)?;
+    if let Err(e) = move_item(path, &dest) {
+        let _ = std::fs::remove_file(info_path);
+        return Err(e);
+    }
+    Ok(dest)
+}
+
+/// Reserve a name in `files` that no other item uses, adding `.N` to `name` on a clash,
+/// and return the path in `files` and its `.trashinfo`.
+/// Creating the `.trashinfo` with the original path reserves the name, as the specification requires.
+/// This is used for felix's own trash directory as well, to record the original path.
+pub fn reserve_name(
+    files: &Path,
+    info: &Path,
+    name: &str,
+    original: &Path,
+) -> Result<(PathBuf, PathBuf), FxError> {
+    std::fs::create_dir_all(info)?;
+    let content = trashinfo(original);
+    let mut n = 1;
+    loop {
+        let trashed = if n == 1 {
+            name.to_owned()
+        } else {
+            format!("{}.{}", name, n)
+        };
+        n += 1;
+        if files.join(&trashed).symlink_metadata().is_ok() {
+            continue;
+        }
+        let info_path = info.join(format!("{}{}", trashed, TRASHINFO_EXT));
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&info_path)
+        {
+            Ok(mut file) => {
+                file.write_all(content.as_bytes())?;
+                return Ok((files.join(trashed), info_path));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Content of `.trashinfo`, with the current time as the deletion date.
+fn trashinfo(original: &Path) -> String {
+    format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        encode_path(original),
+        Local::now().format(DELETION_DATE_FORMAT)
+    )
+}
+
+/// Item in the trash.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrashEntry {
+    /// Path in the trash.
+    pub path: PathBuf,
+    /// `.trashinfo` of the item, if exists.
+    pub info: Option<PathBuf>,
+    /// Where the item was deleted from, if known.
+    pub original: Option<PathBuf>,
+    pub deleted: Option<NaiveDateTime>,
+    pub size: u64,
+}
+
+impl TrashEntry {
+    /// Move the item back to the original location, recreating its parent directories.
+    /// If the name is taken, it is renamed like `put`. Return the restored path.
+    pub fn restore(&self) -> Result<PathBuf, FxError> {
+        let original = self.original.as_ref().ok_or_else(|| {
+            FxError::Io(format!("Original path unknown: {}", self.path.display()))
+        })?;
+        let parent = original
+            .parent()
+            .ok_or_else(|| FxError::Io(format!("Cannot restore to {}", original.display())))?;
+        let name = original
+            .file_name()
+            .and_then(|x| x.to_str())
+            .ok_or(FxError::Encode)?;
+        std::fs::create_dir_all(parent)?;
+
+        let name_set: BTreeSet<String> = std::fs::read_dir(parent)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect();
+        let is_dir = self.path.symlink_metadata()?.is_dir();
+        let name = if is_dir {
+            rename_dir(name, &name_set)
+        } else {
+            rename_file(name, &name_set)
+        };
+        let target = parent.join(name);
+        move_item(&self.path, &target)?;
+        if let Some(info) = &self.info {
+            std::fs::remove_file(info)?;
+        }
+        Ok(target)
+    }
+
+    /// Delete the item permanently.
+    pub fn purge(&self) -> Result<(), FxError> {
+        if self.path.symlink_metadata()?.is_dir() {
+            std::fs::remove_dir_all(&self.path)?;
+        } else {
+            std::fs::remove_file(&self.path)?;
+        }
+        if let Some(info) = &self.info {
+            std::fs::remove_file(info)?;
+        }
+        Ok(())
+    }
+}
+
+/// Items in the trash, from the most recently deleted.
+/// With the freedesktop.org backend, the trash directories at the top of mounts are read as well.
+pub fn read_all_entries(
+    files: &Path,
+    info: &Path,
+    backend: TrashBackend,
+) -> Result<Vec<TrashEntry>, FxError> {
+    let mut entries = read_entries(files, info, None)?;
+    if backend == TrashBackend::Freedesktop {
+        for (trash, topdir) in topdir_trashes() {
+            //Trashes on other mounts may be unreadable, which should not hide the home trash.
+            if let Ok(topdir_entries) =
+                read_entries(&files_dir(&trash), &info_dir(&trash), Some(&topdir))
+            {
+                entries.extend(topdir_entries);
+            }
+        }
+        sort_entries(&mut entries);
+    }
+    Ok(entries)
+}
+
+/// Items in `files` with their metadata in `info`, from the most recently deleted.
+/// Items without `.trashinfo` are listed as well, with the original path unknown.
+/// For a trash at the top of a mount, relative `Path` of `.trashinfo` is resolved against `topdir`.
+pub fn read_entries(
+    files: &Path,
+    info: &Path,
+    topdir: Option<&Path>,
+) -> Result<Vec<TrashEntry>, FxError> {
+    let mut entries = vec![];
+    for entry in std::fs::read_dir(files)? {
+        let entry = entry?;
+        let path = entry.path();
+        let info_path = info.join(format!(
+            "{}{}",
+            entry.file_name().to_string_lossy(),
+            TRASHINFO_EXT
+        ));
+        let (original, deleted) = match std::fs::read_to_string(&info_path) {
+            Ok(content) => parse_trashinfo(&content, topdir),
+            Err(_) => (None, timestamp_prefix(&entry.file_name().to_string_lossy())),
+        };
+        let size = walkdir::WalkDir::new(&path)
+            .into_iter()
+            .filter_map(|x| x.ok())
+            .filter(|x| x.file_type().is_file())
+            .filter_map(|x| x.metadata().ok())
+            .map(|x| x.len())
+            .sum();
+        entries.push(TrashEntry {
+            path,
+            info: info_path.exists().then_some(info_path),
+            original,
+            deleted,
+            size,
+        });
+    }
+    sort_entries(&mut entries);
+    Ok(entries)
+}
+
+/// Deletion time from the `<timestamp>_` prefix of felix's own trash,
+/// for items trashed before `.trashinfo` was written.
+fn timestamp_prefix(name: &str) -> Option<NaiveDateTime> {
+    let (timestamp, _) = name.split_once('_')?;
+    if timestamp.is_empty() || !timestamp.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    chrono::DateTime::from_timestamp(timestamp.parse().ok()?, 0)
+        .map(|deleted| deleted.with_timezone(&Local).naive_local())
+}
+
+/// Sort the entries from the most recently deleted.
+fn sort_entries(entries: &mut [TrashEntry]) {
+    entries.sort_by(|a, b| b.deleted.cmp(&a.deleted).then(a.path.cmp(&b.path)));
+}
+
+/// `Path` and `DeletionDate` of `.trashinfo`.
+/// A relative `Path` is resolved against `topdir`, and dropped if there is none.
+fn parse_trashinfo(
+    content: &str,
+    topdir: Option<&Path>,
+) -> (Option<PathBuf>, Option<NaiveDateTime>) {
+    let mut original = None;
+    let mut deleted = None;
+    for line in content.lines() {
+        if let Some(value) = line.strip_prefix("Path=") {
+            original = decode_path(value).and_then(|x| {
+                if x.is_absolute() {
+                    Some(x)
+                } else {
+                    topdir.map(|topdir| topdir.join(x))
+                }
+            });
+        } else if let Some(value) = line.strip_prefix("DeletionDate=") {
+            deleted = NaiveDateTime::parse_from_str(value.trim(), DELETION_DATE_FORMAT).ok();
+        }
+    }
+    (original, deleted)
+}
+
+/// Purge the entries deleted before `limit`, and then the oldest ones
+/// until the total size is within `max_size`. Entries must be sorted from the newest,
+/// as `read_entries` returns. Return the number of purged entries.
+pub fn purge_entries(
+    entries: &[TrashEntry],
+    limit: Option<NaiveDateTime>,
+    max_size: Option<u64>,
+) -> Result<usize, FxError> {
+    let mut total = 0;
+    let mut count = 0;
+    for entry in entries {
+        let too_old =
+            matches!((entry.deleted, limit), (Some(deleted), Some(limit)) if deleted < limit);
+        total += entry.size;
+        let too_large = max_size.is_some_and(|max_size| total > max_size);
+        if too_old || too_large {
+            entry.purge()?;
+            total -= entry.size;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Purge the trash by `trash_max_age_days` and `trash_max_size` of the config.
+/// Days too large to go back from now purge nothing by age.
+pub fn auto_purge(
+    files: &Path,
+    info: &Path,
+    backend: TrashBackend,
+    max_age_days: Option<u64>,
+    max_size: Option<u64>,
+) -> Result<usize, FxError> {
+    let limit = max_age_days
+        .and_then(|days| i64::try_from(days).ok())
+        .and_then(TimeDelta::try_days)
+        .and_then(deleted_before);
+    purge_entries(&read_all_entries(files, info, backend)?, limit, max_size)
+}
+
+/// The time `age` ago, or None if it is out of range.
+pub fn deleted_before(age: TimeDelta) -> Option<NaiveDateTime> {
+    Local::now().naive_local().checked_sub_signed(age)
+}
+
+/// Parse the age such as `30m`, `12h`, `7d` or `2w`.
+/// Ages too large to go back from now are invalid.
+pub fn parse_age(s: &str) -> Result<TimeDelta, FxError> {
+    let error = || FxError::Io(format!("Invalid age: {} (e.g. 12h, 7d, 2w)", s));
+    let (n, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).ok_or_else(error)?);
+    let n: i64 = n.parse().map_err(|_| error())?;
+    let age = match unit {
+        "m" => TimeDelta::try_minutes(n),
+        "h" => TimeDelta::try_hours(n),
+        "d" => TimeDelta::try_days(n),
+        "w" => TimeDelta::try_weeks(n),
+        _ => None,
+    };
+    age.filter(|age| deleted_before(*age).is_some())
+        .ok_or_else(error)
+}
+
+/// Parse the size such as `500MB` or `10GB`, in the same units as the item list.
+pub fn parse_size(s: &str) -> Result<u64, FxError> {
+    let error = || FxError::Io(format!("Invalid size: {} (e.g. 500MB, 10GB)", s));
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (n, unit) = s.split_at(split);
+    let n: u64 = n.parse().map_err(|_| error())?;
+    let unit: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "KB" => 1_000,
+        "MB" => 1_000_000,
+        "GB" => 1_000_000_000,
+        "TB" => 1_000_000_000_000,
+        _ => return Err(error()),
+    };
+    n.checked_mul(unit).ok_or_else(error)
+}
+
+/// Trash directory at the top of the mount where the item is, and the `topdir`.
+/// None if the item is on the same device as the home trash, or no trash can be used there.
+#[cfg(unix)]
+fn topdir_trash(path: &Path, home: &Path) -> Option<(PathBuf, PathBuf)> {
+    let dev = path.symlink_metadata().ok()?.dev();
+    let home_dev = home
+        .ancestors()
+        .find_map(|p| p.metadata().ok())
+        .map(|m| m.dev());
+    if home_dev == Some(dev) {
+        return None;
+    }
+
+    let mut topdir = path.parent()?;
+    while let Some(parent) = topdir.parent() {
+        match parent.metadata() {
+            Ok(m) if m.dev() == dev => topdir = parent,
+            _ => break,
+        }
+    }
+    let uid = nix::unistd::getuid().as_raw();
+
+    //$topdir/.Trash/$uid, if the administrator has prepared `.Trash` with the sticky bit.
+    let admin = topdir.join(".Trash");
+    if let Ok(m) = admin.symlink_metadata() {
+        if m.is_dir() && m.mode() & 0o1000 != 0 {
+            let trash = admin.join(uid.to_string());
+            if std::fs::create_dir_all(&trash).is_ok() {
+                return Some((trash, topdir.to_path_buf()));
+            }
+        }
+    }
+
+    //$topdir/.Trash-$uid, which must be a directory owned by the user.
+    let trash = topdir.join(format!(".Trash-{}", uid));
+    if trash.symlink_metadata().is_err() {
+        std::fs::DirBuilder::new().mode(0o700).create(&trash).ok()?;
+    }
+    match trash.symlink_metadata() {
+        Ok(m) if m.is_dir() && m.uid() == uid => Some((trash, topdir.to_path_buf())),
+        _ => None,
+    }
+}
+
+#[cfg(not(unix))]
+fn topdir_trash(_path: &Path, _home: &Path) -> Option<(PathBuf, PathBuf)> {
+    None
+}
+
+/// Existing trash directories at the top of the mounts, with their `topdir`.
+/// Mounts are read from `/proc/self/mounts`, so none is found where it is not available.
+#[cfg(unix)]
+fn topdir_trashes() -> Vec<(PathBuf, PathBuf)> {
+    let mounts = std::fs::read_to_string("/proc/self/mounts").unwrap_or_default();
+    let uid = nix::unistd::getuid().as_raw();
+    let mut trashes = vec![];
+    for topdir in mounts
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .map(unescape_mount_point)
+    {
+        for trash in [
+            topdir.join(".Trash").join(uid.to_string()),
+            topdir.join(format!(".Trash-{}", uid)),
+        ] {
+            if files_dir(&trash).is_dir() && !trashes.iter().any(|(x, _)| x == &trash) {
+                trashes.push((trash, topdir.clone()));
+            }
+        }
+    }
+    trashes
+}
+
+#[cfg(not(unix))]
+fn topdir_trashes() -> Vec<(PathBuf, PathBuf)> {
+    vec![]
+}
+
+/// Mount point in `/proc/self/mounts`, where space, tab, newline and backslash are octal-escaped.
+#[cfg(unix)]
+fn unescape_mount_point(s: &str) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+    let bytes = s.as_bytes();
+    let mut unescaped = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let octal = bytes
+            .get(i + 1..i + 4)
+            .and_then(|x| std::str::from_utf8(x).ok())
+            .and_then(|x| u8::from_str_radix(x, 8).ok());
+        match octal {
+            Some(b) if bytes[i] == b'\\' => {
+                unescaped.push(b);
+                i += 4;
+            }
+            _ => {
+                unescaped.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    PathBuf::from(std::ffi::OsString::from_vec(unescaped))
+}
+
+/// Percent-encode the path for `Path` of `.trashinfo`, keeping `/` and unreserved characters.
+fn encode_path(path: &Path) -> String {
+    #[cfg(unix)]
+    let bytes = {
+        use std::os::unix::ffi::OsStrExt;
+        path.as_os_str().as_bytes().to_vec()
+    };
+    #[cfg(not(unix))]
+    let bytes = path.to_string_lossy().as_bytes().to_vec();
+
+    let mut encoded = String::with_capacity(bytes.len());
+    for b in bytes {
+        if b.is_ascii_alphanumeric() || b"/-_.~".contains(&b) {
+            encoded.push(b as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", b));
+        }
+    }
+    encoded
+}
+
+/// Decode the percent-encoded `Path` of `.trashinfo`.
+fn decode_path(s: &str) -> Option<PathBuf> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+        Some(PathBuf::from(std::ffi::OsString::from_vec(decoded)))
+    }
+    #[cfg(not(unix))]
+    String::from_utf8(decoded).ok().map(PathBuf::from)
+}
+
+/// Trash browser of `:trash`.
+pub struct TrashView {
+    pub entries: Vec<TrashEntry>,
+    pub selected: usize,
+    /// Entries marked by `<Space>`, as indices of `entries`.
+    pub marked: BTreeSet<usize>,
+}
+
+impl TrashView {
+    pub fn new(entries: Vec<TrashEntry>) -> Self {
+        TrashView {
+            entries,
+            selected: 0,
+            marked: BTreeSet::new(),
+        }
+    }
+
+    pub fn move_down(&mut self, n: usize) {
+        self.selected = (self.selected + n).min(self.entries.len().saturating_sub(1));
+    }
+
+    pub fn move_up(&mut self, n: usize) {
+        self.selected = self.selected.saturating_sub(n);
+    }
+
+    pub fn toggle_mark(&mut self) {
+        if self.selected >= self.entries.len() {
+            return;
+        }
+        if !self.marked.remove(&self.selected) {
+            self.marked.insert(self.selected);
+        }
+    }
+
+    /// Marked entries, or the one under the cursor if none is marked.
+    pub fn targets(&self) -> Vec<&TrashEntry> {
+        if self.marked.is_empty() {
+            self.entries.get(self.selected).into_iter().collect()
+        } else {
+            self.marked
+                .iter()
+                .filter_map(|i| self.entries.get(*i))
+                .collect()
+        }
+    }
+
+    /// Drop the entries that no longer exist in the trash, after restoring or purging.
+    pub fn retain_existing(&mut self) {
+        self.entries.retain(|x| x.path.symlink_metadata().is_ok());
+        self.marked.clear();
+        self.move_down(0);
+    }
+
+    /// Format the entry as `deletion date  size  original path`.
+    fn format_entry(&self, entry: &TrashEntry) -> String {
+        let deleted = match entry.deleted {
+            Some(deleted) => deleted.format("%Y-%m-%d %H:%M").to_string(),
+            None => "????-??-?? ??:??".to_owned(),
+        };
+        let original = match &entry.original {
+            Some(original) => original.display().to_string(),
+            None => format!("? ({})", entry.path.display()),
+        };
+        format!("{} {:>6} {}", deleted, to_proper_size(entry.size), original)
+    }
+
+    pub fn print(&self, layout: &Layout) {
+        move_to(1, 1);
+        clear_current_line();
+        print!(
+            "[trash] {} items, {}  <Space>:mark r:restore d:purge <CR>:go to q:close",
+            self.entries.len(),
+            to_proper_size(self.entries.iter().map(|x| x.size).sum())
+        );
+
+        let height = layout.terminal_row.saturating_sub(BEGINNING_ROW) as usize;
+        let skip = (self.selected + 1).saturating_sub(height);
+        for row in 0..height {
+            move_to(1, BEGINNING_ROW + row as u16);
+            clear_current_line();
+            let i = skip + row;
+            if let Some(entry) = self.entries.get(i) {
+                let line = shorten_str_including_wide_char(
+                    &self.format_entry(entry),
+                    layout.terminal_column.saturating_sub(4).into(),
+                );
+                let mark = if self.marked.contains(&i) { '*' } else { ' ' };
+                if i == self.selected {
+                    print!(">{} {}", mark, line.negative());
+                } else {
+                    print!(" {} {}", mark, line);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_path() {
+        assert_eq!(encode_path(Path::new("/tmp/a-b_c.txt")), "/tmp/a-b_c.txt");
+        assert_eq!(encode_path(Path::new("/tmp/a b")), "/tmp/a%20b");
+        assert_eq!(encode_path(Path::new("/tmp/写")), "/tmp/%E5%86%99");
+    }
+
+    #[test]
+    fn test_trash_item_in() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash = dir.path().join("Trash");
+        let item = dir.path().join("a b.txt");
+
+        std::fs::write(&item, "1").unwrap();
+        let first = trash_item_in(&item, &trash, None).unwrap();
+        assert_eq!(first, trash.join("files/a b.txt"));
+        assert!(!item.exists());
+        let info = std::fs::read_to_string(trash.join("info/a b.txt.trashinfo")).unwrap();
+        let lines: Vec<&str> = info.lines().collect();
+        assert_eq!(lines[0], "[Trash Info]");
+        assert_eq!(lines[1], format!("Path={}", encode_path(&item)));
+        assert!(lines[2].starts_with("DeletionDate="));
+
+        //The same name gets a suffix.
+        std::fs::write(&item, "2").unwrap();
+        let second = trash_item_in(&item, &trash, None).unwrap();
+        assert_eq!(second, trash.join("files/a b.txt.2"));
+        assert!(trash.join("info/a b.txt.2.trashinfo").exists());
+        assert_eq!(std::fs::read_to_string(second).unwrap(), "2");
+
+        //Relative to the topdir.
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        let nested = dir.path().join("sub/c");
+        std::fs::write(&nested, "").unwrap();
+        trash_item_in(&nested, &trash, Some(dir.path())).unwrap();
+        let info = std::fs::read_to_string(trash.join("info/c.trashinfo")).unwrap();
+        assert!(info.contains("\nPath=sub/c\n"));
+    }
+
+    #[test]
+    fn test_decode_path() {
+        let path = Path::new("/tmp/a b/写.txt");
+        assert_eq!(decode_path(&encode_path(path)), Some(path.to_path_buf()));
+        assert_eq!(decode_path("/tmp/%2"), None);
+    }
+
+    #[test]
+    fn test_restore_and_purge() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash = dir.path().join("Trash");
+        let nested = dir.path().join("sub/a.txt");
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(&nested, "1").unwrap();
+        trash_item_in(&nested, &trash, None).unwrap();
+        let other = dir.path().join("b");
+        std::fs::create_dir(&other).unwrap();
+        trash_item_in(&other, &trash, None).unwrap();
+        std::fs::write(files_dir(&trash).join("unknown"), "").unwrap();
+
+        let entries = read_entries(&files_dir(&trash), &info_dir(&trash), None).unwrap();
+        assert_eq!(entries.len(), 3);
+        let find = |name: &str| {
+            entries
+                .iter()
+                .find(|x| x.path.file_name().unwrap() == name)
+                .unwrap()
+        };
+        assert_eq!(find("a.txt").original, Some(nested.clone()));
+        assert_eq!(find("a.txt").size, 1);
+        assert!(find("a.txt").deleted.is_some());
+        assert!(find("unknown").original.is_none());
+        assert!(find("unknown").deleted.is_none());
+        assert!(find("unknown").restore().is_err());
+
+        //Parent directories are recreated, and a name clash is resolved.
+        std::fs::remove_dir(dir.path().join("sub")).unwrap();
+        assert_eq!(find("a.txt").restore().unwrap(), nested);
+        std::fs::create_dir(&other).unwrap();
+        assert_eq!(find("b").restore().unwrap(), dir.path().join("b_1"));
+        assert!(!info_dir(&trash).join("b.trashinfo").exists());
+
+        find("unknown").purge().unwrap();
+        assert!(read_entries(&files_dir(&trash), &info_dir(&trash), None)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_topdir_trash_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash = dir.path().join(".Trash-1000");
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        let nested = dir.path().join("sub/a.txt");
+        std::fs::write(&nested, "1").unwrap();
+        trash_item_in(&nested, &trash, Some(dir.path())).unwrap();
+
+        //The relative path is resolved against the topdir.
+        let entries =
+            read_entries(&files_dir(&trash), &info_dir(&trash), Some(dir.path())).unwrap();
+        assert_eq!(entries[0].original, Some(nested.clone()));
+        assert_eq!(entries[0].restore().unwrap(), nested);
+
+        let (original, _) = parse_trashinfo("[Trash Info]\nPath=sub/b\n", None);
+        assert!(original.is_none());
+    }
+
+    #[test]
+    fn test_timestamp_prefix() {
+        let deleted = timestamp_prefix("1700000000_a_b.txt").unwrap();
+        assert_eq!(
+            deleted.and_local_timezone(Local).unwrap().timestamp(),
+            1700000000
+        );
+        assert!(timestamp_prefix("a_b.txt").is_none());
+        assert!(timestamp_prefix("_a").is_none());
+        assert!(timestamp_prefix("1700000000").is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unescape_mount_point() {
+        assert_eq!(
+            unescape_mount_point("/media/usb\\040disk"),
+            PathBuf::from("/media/usb disk")
+        );
+        assert_eq!(
+            unescape_mount_point("/mnt/a\\b"),
+            PathBuf::from("/mnt/a\\b")
+        );
+    }
+
+    #[test]
+    fn test_trash_view() {
+        let entry = |name: &str| TrashEntry {
+            path: PathBuf::from(name),
+            info: None,
+            original: None,
+            deleted: None,
+            size: 0,
+        };
+        let mut view = TrashView::new(vec![entry("a"), entry("b"), entry("c")]);
+        assert_eq!(view.targets(), vec![&entry("a")]);
+        view.toggle_mark();
+        view.move_down(2);
+        view.toggle_mark();
+        assert_eq!(view.targets(), vec![&entry("a"), &entry("c")]);
+
+        //None of them exists.
+        view.retain_existing();
+        assert!(view.entries.is_empty());
+        assert!(view.marked.is_empty());
+        assert_eq!(view.selected, 0);
+    }
+
+    #[test]
+    fn test_parse_age_and_size() {
+        assert_eq!(parse_age("7d").unwrap(), TimeDelta::days(7));
+        assert_eq!(parse_age("12h").unwrap(), TimeDelta::hours(12));
+        assert_eq!(parse_age("2w").unwrap(), TimeDelta::weeks(2));
+        assert!(parse_age("7").is_err());
+        assert!(parse_age("100000000d").is_err());
+        assert!(parse_age("9223372036854775807w").is_err());
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("7y").is_err());
+
+        assert_eq!(parse_size("500").unwrap(), 500);
+        assert_eq!(parse_size("500MB").unwrap(), 500_000_000);
+        assert_eq!(parse_size("10 gb").unwrap(), 10_000_000_000);
+        assert!(parse_size("GB").is_err());
+        assert!(parse_size("10PB").is_err());
+    }
+
+    #[test]
+    fn test_purge_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = Local::now().naive_local();
+        let entries: Vec<TrashEntry> = (0..4)
+            .map(|i| {
+                let path = dir.path().join(i.to_string());
+                std::fs::write(&path, "").unwrap();
+                TrashEntry {
+                    path,
+                    info: None,
+                    original: None,
+                    deleted: Some(now - TimeDelta::days(i)),
+                    size: 10,
+                }
+            })
+            .collect();
+        let exists = |i: usize| entries[i].path.exists();
+
+        //Only the total size is over the limit.
+        assert_eq!(purge_entries(&entries, None, Some(30)).unwrap(), 1);
+        assert!(exists(2) && !exists(3));
+        //Deleted more than a day ago.
+        let limit = now - TimeDelta::hours(12);
+        assert_eq!(purge_entries(&entries[..3], Some(limit), None).unwrap(), 2);
+        assert!(exists(0) && !exists(1) && !exists(2));
+
+        //Too many days purge nothing by age.
+        let files = dir.path().join("files");
+        std::fs::create_dir(&files).unwrap();
+        std::fs::write(files.join("a"), "").unwrap();
+        let purged = auto_purge(
+            &files,
+            &dir.path().join("info"),
+            TrashBackend::Felix,
+            Some(u64::MAX / 2),
+            None,
+        );
+        assert_eq!(purged.unwrap(), 0);
+    }
+}